@@ -0,0 +1,51 @@
+// SPDX-FileCopyrightText: 2025 Derek Sauer
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Exposes the device information strings, and the URL broadcast by
+//! `eddystone_url` builds, to the firmware at build time.
+//!
+//! White-label variants can be built from the same source by overriding them
+//! with environment variables, for example:
+//!
+//! ```sh
+//! LOOKPOINT_MANUFACTURER_NAME="Acme" LOOKPOINT_MODEL_NUMBER="Acme-01" cargo build --release
+//! ```
+//!
+//! Unset variables fall back to the defaults below.
+
+use std::env;
+
+/// Name of the manufacturer of the device.
+const DEFAULT_MANUFACTURER_NAME: &str = "Sauerstoff.ca";
+
+/// Model number or name of the device.
+const DEFAULT_MODEL_NUMBER: &str = "Lookpoint-01";
+
+/// URL broadcast by `eddystone_url` builds. Must fit in an Eddystone-URL frame.
+const DEFAULT_EDDYSTONE_URL: &str = "https://sauerstoff.ca/";
+
+/// Hardware revision of the Arduino Nano 33 BLE (Rev2).
+const NANO_33_BLE_HARDWARE_REVISION: &str = "ABX00071";
+
+fn main() {
+    let hardware_revision = if env::var_os("CARGO_FEATURE_NANO_33_BLE").is_some() {
+        NANO_33_BLE_HARDWARE_REVISION
+    } else {
+        "unknown"
+    };
+
+    expose("LOOKPOINT_MANUFACTURER_NAME", DEFAULT_MANUFACTURER_NAME);
+    expose("LOOKPOINT_MODEL_NUMBER", DEFAULT_MODEL_NUMBER);
+    expose("LOOKPOINT_HARDWARE_REVISION", hardware_revision);
+    expose("LOOKPOINT_EDDYSTONE_URL", DEFAULT_EDDYSTONE_URL);
+}
+
+/// Make the environment variable `name` available to `env!`, set to its value
+/// at build time or to `default` if it is unset.
+fn expose(name: &str, default: &str) {
+    println!("cargo:rerun-if-env-changed={name}");
+
+    let value = env::var(name).unwrap_or_else(|_| default.to_owned());
+    println!("cargo:rustc-env={name}={value}");
+}
@@ -0,0 +1,270 @@
+// SPDX-FileCopyrightText: 2025 Derek Sauer
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Encoding advertisements into the AD structures of legacy advertising PDUs.
+
+use crate::device_name::truncate_on_char_boundary;
+
+/// Largest payload of a legacy advertising or scan response PDU.
+pub const LEGACY_PAYLOAD_LENGTH: usize = 31;
+
+/// Encoded AD structures of a legacy advertising or scan response PDU.
+pub type AdvPayload = heapless::Vec<u8, LEGACY_PAYLOAD_LENGTH>;
+
+/// AD type of the Flags structure.
+const AD_TYPE_FLAGS: u8 = 0x01;
+
+/// AD type of the Incomplete List of 16-bit Service UUIDs structure.
+const AD_TYPE_INCOMPLETE_SERVICE_UUIDS16: u8 = 0x02;
+
+/// AD type of the Complete List of 16-bit Service UUIDs structure.
+const AD_TYPE_SERVICE_UUIDS16: u8 = 0x03;
+
+/// AD type of the Shortened Local Name structure.
+const AD_TYPE_SHORTENED_LOCAL_NAME: u8 = 0x08;
+
+/// AD type of the Complete Local Name structure.
+const AD_TYPE_COMPLETE_LOCAL_NAME: u8 = 0x09;
+
+/// AD type of the Service Data - 16-bit UUID structure.
+const AD_TYPE_SERVICE_DATA16: u8 = 0x16;
+
+/// AD type of the Manufacturer Specific Data structure.
+const AD_TYPE_MANUFACTURER_DATA: u8 = 0xff;
+
+/// AD type of the Appearance structure.
+const AD_TYPE_APPEARANCE: u8 = 0x19;
+
+/// AD type of the TX Power Level structure.
+const AD_TYPE_TX_POWER_LEVEL: u8 = 0x0a;
+
+/// Length of the header of an AD structure: its length and AD type.
+const AD_HEADER_LENGTH: usize = 2;
+
+/// Errors encoding advertising data.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum AdvError {
+    /// The AD structures do not fit in the advertising data.
+    AdvDataOverflow,
+
+    /// The AD structures moved to the scan response do not fit in it either.
+    ScanDataOverflow,
+}
+
+/// Encode an AD structure of type `ty` whose data is the concatenation of
+/// `data`, or `None` if it does not fit in a legacy advertising payload.
+fn encode_ad_structure(ty: u8, data: &[&[u8]]) -> Option<AdvPayload> {
+    let length = data.iter().map(|part| part.len()).sum::<usize>();
+
+    let mut encoded = AdvPayload::new();
+    // The length counts the AD type, but not itself.
+    encoded.push(u8::try_from(1 + length).ok()?).ok()?;
+    encoded.push(ty).ok()?;
+    for part in data {
+        encoded.extend_from_slice(part).ok()?;
+    }
+
+    Some(encoded)
+}
+
+/// Accumulates the optional fields of an advertisement and encodes them once
+/// into an advertising data and scan response payload.
+///
+/// The Flags and service UUIDs always go in the advertising data, as centrals
+/// filter on them before scanning. Service UUIDs that do not fit are dropped
+/// with a warning and the rest advertised as an incomplete list. The remaining
+/// fields are placed in the advertising data while they fit, in the order:
+/// service data, manufacturer data, TX power level, appearance, local name.
+/// Whatever does not fit moves to the scan response. A local name that does
+/// not fit moves to the scan response too, leaving as much of it as fits in the
+/// advertising data as a shortened local name.
+#[derive(Clone, Copy, Default)]
+pub struct AdvertisementBuilder<'data> {
+    flags:             Option<u8>,
+    service_uuids16:   &'data [[u8; 2]],
+    service_data16:    Option<([u8; 2], &'data [u8])>,
+    manufacturer_data: Option<(u16, &'data [u8])>,
+    tx_power_level:    Option<i8>,
+    appearance:        Option<[u8; 2]>,
+    local_name:        Option<&'data str>,
+}
+
+impl<'data> AdvertisementBuilder<'data> {
+    /// Create a builder for an empty advertisement.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the Flags of the advertisement.
+    pub fn flags(mut self, flags: u8) -> Self {
+        self.flags = Some(flags);
+        self
+    }
+
+    /// Set the 16-bit service UUIDs advertised, in little endian byte order.
+    pub fn service_uuids16(mut self, service_uuids: &'data [[u8; 2]]) -> Self {
+        self.service_uuids16 = service_uuids;
+        self
+    }
+
+    /// Set the data associated with the 16-bit service UUID `uuid`, in little
+    /// endian byte order.
+    pub fn service_data16(mut self, uuid: [u8; 2], data: &'data [u8]) -> Self {
+        self.service_data16 = Some((uuid, data));
+        self
+    }
+
+    /// Set the manufacturer specific data of the advertisement.
+    pub fn manufacturer_data(mut self, company_identifier: u16, payload: &'data [u8]) -> Self {
+        self.manufacturer_data = Some((company_identifier, payload));
+        self
+    }
+
+    /// Set the advertised transmit power level, in dBm.
+    pub fn tx_power_level(mut self, tx_power_level: i8) -> Self {
+        self.tx_power_level = Some(tx_power_level);
+        self
+    }
+
+    /// Set the advertised GAP appearance, in little endian byte order.
+    pub fn appearance(mut self, appearance: [u8; 2]) -> Self {
+        self.appearance = Some(appearance);
+        self
+    }
+
+    /// Set the complete local name of the advertisement.
+    pub fn local_name(mut self, local_name: &'data str) -> Self {
+        self.local_name = Some(local_name);
+        self
+    }
+
+    /// Encode the advertising data and scan response of a scannable
+    /// advertisement.
+    pub fn build(&self) -> Result<(AdvPayload, AdvPayload), AdvError> {
+        let mut adv_data = self.encode_required()?;
+        let mut scan_data = AdvPayload::new();
+
+        // Each optional structure, encoded unless it is too long for any
+        // payload.
+        let optional = [
+            self.service_data16
+                .map(|(uuid, data)| encode_ad_structure(AD_TYPE_SERVICE_DATA16, &[&uuid, data])),
+            self.manufacturer_data.map(|(company_identifier, payload)| {
+                encode_ad_structure(
+                    AD_TYPE_MANUFACTURER_DATA,
+                    &[&company_identifier.to_le_bytes(), payload],
+                )
+            }),
+            self.tx_power_level
+                .map(|level| encode_ad_structure(AD_TYPE_TX_POWER_LEVEL, &[&[level as u8]])),
+            self.appearance
+                .map(|appearance| encode_ad_structure(AD_TYPE_APPEARANCE, &[&appearance])),
+        ];
+
+        for encoded in optional.into_iter().flatten() {
+            let encoded = encoded.ok_or(AdvError::ScanDataOverflow)?;
+
+            // Keep structures in the advertising data when they fit so passive
+            // scanners see them too.
+            if adv_data.extend_from_slice(&encoded).is_err() {
+                scan_data
+                    .extend_from_slice(&encoded)
+                    .map_err(|_| AdvError::ScanDataOverflow)?;
+            }
+        }
+
+        if let Some(name) = self.local_name {
+            Self::place_local_name(name, &mut adv_data, &mut scan_data)?;
+        }
+
+        Ok((adv_data, scan_data))
+    }
+
+    /// Place the complete local name in the advertising data if it fits.
+    /// Otherwise it goes in the scan response, and as much of it as fits in the
+    /// advertising data as a shortened local name so passive scanners still
+    /// see part of it.
+    fn place_local_name(
+        name: &str,
+        adv_data: &mut AdvPayload,
+        scan_data: &mut AdvPayload,
+    ) -> Result<(), AdvError> {
+        let complete = encode_ad_structure(AD_TYPE_COMPLETE_LOCAL_NAME, &[name.as_bytes()])
+            .ok_or(AdvError::ScanDataOverflow)?;
+        if adv_data.extend_from_slice(&complete).is_ok() {
+            return Ok(());
+        }
+
+        scan_data
+            .extend_from_slice(&complete)
+            .map_err(|_| AdvError::ScanDataOverflow)?;
+
+        let available = LEGACY_PAYLOAD_LENGTH
+            .saturating_sub(adv_data.len())
+            .saturating_sub(AD_HEADER_LENGTH);
+        let shortened = truncate_on_char_boundary(name, available);
+        if !shortened.is_empty() {
+            let encoded =
+                encode_ad_structure(AD_TYPE_SHORTENED_LOCAL_NAME, &[shortened.as_bytes()])
+                    .ok_or(AdvError::AdvDataOverflow)?;
+
+            // UNWRAP: Infallible. The shortened name was sized to fit.
+            adv_data.extend_from_slice(&encoded).unwrap();
+        }
+
+        Ok(())
+    }
+
+    /// Encode the advertising data of a non-scannable advertisement, such as a
+    /// beacon. Every field must fit in the advertising data.
+    pub fn build_beacon(&self) -> Result<AdvPayload, AdvError> {
+        match self.build()? {
+            (adv_data, scan_data) if scan_data.is_empty() => Ok(adv_data),
+            _ => Err(AdvError::AdvDataOverflow),
+        }
+    }
+
+    /// Encode the structures that must be in the advertising data.
+    fn encode_required(&self) -> Result<AdvPayload, AdvError> {
+        let mut adv_data = AdvPayload::new();
+
+        if let Some(flags) = self.flags {
+            let encoded =
+                encode_ad_structure(AD_TYPE_FLAGS, &[&[flags]]).ok_or(AdvError::AdvDataOverflow)?;
+            adv_data
+                .extend_from_slice(&encoded)
+                .map_err(|_| AdvError::AdvDataOverflow)?;
+        }
+
+        if !self.service_uuids16.is_empty() {
+            let available = LEGACY_PAYLOAD_LENGTH
+                .saturating_sub(adv_data.len())
+                .saturating_sub(AD_HEADER_LENGTH);
+            let count = self.service_uuids16.len().min(available / 2);
+            if count == 0 {
+                return Err(AdvError::AdvDataOverflow);
+            }
+
+            let ty = if count == self.service_uuids16.len() {
+                AD_TYPE_SERVICE_UUIDS16
+            } else {
+                #[cfg(feature = "defmt")]
+                defmt::warn!(
+                    "[adv] only {} of {} service UUIDs fit in the advertising data",
+                    count,
+                    self.service_uuids16.len()
+                );
+                AD_TYPE_INCOMPLETE_SERVICE_UUIDS16
+            };
+            let encoded = encode_ad_structure(ty, &[self.service_uuids16[..count].as_flattened()])
+                .ok_or(AdvError::AdvDataOverflow)?;
+            adv_data
+                .extend_from_slice(&encoded)
+                .map_err(|_| AdvError::AdvDataOverflow)?;
+        }
+
+        Ok(adv_data)
+    }
+}
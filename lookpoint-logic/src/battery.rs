@@ -0,0 +1,152 @@
+// SPDX-FileCopyrightText: 2025 Derek Sauer
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Estimating the battery level from its voltage.
+
+/// Battery level, in percent, below which the battery is reported low.
+pub const BATTERY_LOW_THRESHOLD: u8 = 15;
+
+/// Battery level, in percent, the battery must recover to before it is no
+/// longer reported low. The gap with [`BATTERY_LOW_THRESHOLD`] prevents a level
+/// hovering around the threshold from flapping the alert.
+pub const BATTERY_RECOVERED_THRESHOLD: u8 = 20;
+
+/// Discharge curve of a CR2032 coin cell under a light load, as
+/// `(millivolts, percent)` points ordered by decreasing voltage. The cell holds
+/// close to 3 V for most of its life, then drops off quickly.
+const CR2032_CURVE: &[(u16, u8)] = &[
+    (3000, 100),
+    (2900, 80),
+    (2800, 60),
+    (2700, 40),
+    (2600, 20),
+    (2500, 10),
+    (2000, 0),
+];
+
+/// Discharge curve of a single cell LiPo, as `(millivolts, percent)` points
+/// ordered by decreasing voltage.
+const LIPO_CURVE: &[(u16, u8)] = &[
+    (4200, 100),
+    (4100, 90),
+    (3980, 80),
+    (3920, 70),
+    (3870, 60),
+    (3820, 50),
+    (3790, 40),
+    (3770, 30),
+    (3740, 20),
+    (3680, 10),
+    (3450, 5),
+    (3000, 0),
+];
+
+/// Chemistry of the cell powering the device, selecting the discharge curve
+/// used to estimate the battery level.
+///
+/// Values are part of the configuration formats and must remain stable.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum BatteryChemistry {
+    /// CR2032 lithium coin cell.
+    Cr2032    = 0,
+
+    /// Single cell lithium polymer.
+    LiPo      = 1,
+
+    /// A regulated supply, such as the Nano 33 BLE's 3.3 V rail fed through
+    /// its regulator. The measured voltage says nothing of the charge left,
+    /// so no level is estimated.
+    Regulated = 2,
+}
+
+impl TryFrom<u8> for BatteryChemistry {
+    type Error = u8;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::Cr2032),
+            1 => Ok(Self::LiPo),
+            2 => Ok(Self::Regulated),
+            _ => Err(value),
+        }
+    }
+}
+
+impl BatteryChemistry {
+    /// Returns the discharge curve of the chemistry, or `None` for a
+    /// regulated supply.
+    fn curve(self) -> Option<&'static [(u16, u8)]> {
+        match self {
+            Self::Cr2032 => Some(CR2032_CURVE),
+            Self::LiPo => Some(LIPO_CURVE),
+            Self::Regulated => None,
+        }
+    }
+
+    /// Estimate the battery level, in percent, of a cell at `millivolts` by
+    /// interpolating between the points of its discharge curve. Returns
+    /// `None` for a regulated supply.
+    pub fn percent_from_millivolts(self, millivolts: u16) -> Option<u8> {
+        let curve = self.curve()?;
+
+        // Clamp voltages outside of the curve to its ends.
+        let (full_millivolts, full_percent) = curve[0];
+        if millivolts >= full_millivolts {
+            return Some(full_percent);
+        }
+
+        for points in curve.windows(2) {
+            let ((high_millivolts, high_percent), (low_millivolts, low_percent)) =
+                (points[0], points[1]);
+
+            if millivolts >= low_millivolts {
+                let span = u32::from(high_millivolts - low_millivolts);
+                let offset = u32::from(millivolts - low_millivolts);
+                let range = u32::from(high_percent - low_percent);
+
+                // Infallible. The interpolated level lies within the range.
+                return Some(low_percent + (range * offset / span) as u8);
+            }
+        }
+
+        Some(curve[curve.len() - 1].1)
+    }
+}
+
+/// Tracks whether the battery is low, with hysteresis.
+#[derive(Clone, Copy, Default)]
+pub struct LowBattery {
+    low: bool,
+}
+
+impl LowBattery {
+    /// Create a new [`LowBattery`], initially not low.
+    pub const fn new() -> Self {
+        Self { low: false }
+    }
+
+    /// Returns `true` if the battery is currently low.
+    pub fn is_low(&self) -> bool {
+        self.low
+    }
+
+    /// Feed a new battery level, in percent. Returns the new state when a
+    /// threshold is crossed.
+    pub fn update(&mut self, percent: u8) -> Option<bool> {
+        let low = if self.low {
+            percent < BATTERY_RECOVERED_THRESHOLD
+        } else {
+            percent < BATTERY_LOW_THRESHOLD
+        };
+
+        if low == self.low {
+            return None;
+        }
+
+        self.low = low;
+        Some(low)
+    }
+}
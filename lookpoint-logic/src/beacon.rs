@@ -0,0 +1,315 @@
+// SPDX-FileCopyrightText: 2025 Derek Sauer
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Beacon identities, as provisioned through the control point and persisted
+//! in the device configuration, and the frames broadcasting them.
+
+/// iBeacon type and remaining length prefixing the iBeacon payload.
+const IBEACON_PREFIX: [u8; 2] = [0x02, 0x15];
+
+/// Frame type of an Eddystone-UID frame.
+const EDDYSTONE_UID_FRAME: u8 = 0x00;
+
+/// Frame type of an Eddystone-URL frame.
+const EDDYSTONE_URL_FRAME: u8 = 0x10;
+
+/// Longest encoded URL of an Eddystone-URL frame, following its scheme.
+pub const EDDYSTONE_URL_MAX_LENGTH: usize = 17;
+
+/// Length of the Eddystone-URL frame header: frame type, calibrated transmit
+/// power, and URL scheme.
+const EDDYSTONE_URL_HEADER_LENGTH: usize = 3;
+
+/// URL schemes of Eddystone-URL frames, indexed by their code.
+const EDDYSTONE_URL_SCHEMES: [&str; 4] = ["http://www.", "https://www.", "http://", "https://"];
+
+/// Text substituted by a single byte in Eddystone-URL frames, indexed by their
+/// code. The expansions ending in a slash come first so they are preferred.
+const EDDYSTONE_URL_EXPANSIONS: [&str; 14] = [
+    ".com/", ".org/", ".edu/", ".net/", ".info/", ".biz/", ".gov/", ".com", ".org", ".edu", ".net",
+    ".info", ".biz", ".gov",
+];
+
+/// Measured power an iBeacon identity may claim, in dBm at 1 m.
+const IBEACON_MEASURED_POWER_RANGE: core::ops::RangeInclusive<i8> = -127..=-1;
+
+/// Calibrated transmit power an Eddystone identity may claim, in dBm at 0 m.
+const EDDYSTONE_TX_POWER_RANGE: core::ops::RangeInclusive<i8> = -100..=20;
+
+/// Errors validating a beacon identity.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum BeaconError {
+    /// The identity is not the expected length.
+    InvalidLength,
+
+    /// The UUID or namespace is all zeroes or all ones, which scanners treat
+    /// as unset.
+    InvalidUuid,
+
+    /// The measured or calibrated transmit power is out of range.
+    InvalidPower,
+
+    /// The URL does not start with a scheme Eddystone-URL can encode, or
+    /// contains characters outside of printable ASCII.
+    InvalidUrl,
+
+    /// The encoded URL does not fit in the 17 bytes of an Eddystone-URL
+    /// frame.
+    UrlTooLong,
+}
+
+/// Identity of an iBeacon.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct IBeaconIdentity {
+    /// Proximity UUID shared by a deployment's beacons, in big endian byte
+    /// order as it is usually written.
+    pub uuid: [u8; 16],
+
+    /// Group of beacons within the deployment.
+    pub major: u16,
+
+    /// Beacon within the group.
+    pub minor: u16,
+
+    /// Received signal strength at 1 m, in dBm.
+    pub measured_power: i8,
+}
+
+impl IBeaconIdentity {
+    /// Length of an identity written to the control point: the UUID, the
+    /// little endian major and minor, then the measured power.
+    pub const ENCODED_LENGTH: usize = 21;
+
+    /// Validate and decode an identity written to the control point.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, BeaconError> {
+        let bytes: &[u8; Self::ENCODED_LENGTH] =
+            bytes.try_into().map_err(|_| BeaconError::InvalidLength)?;
+
+        // UNWRAP: Infallible. Slicing a fixed length array.
+        let identity = Self {
+            uuid:           bytes[0..16].try_into().unwrap(),
+            major:          u16::from_le_bytes([bytes[16], bytes[17]]),
+            minor:          u16::from_le_bytes([bytes[18], bytes[19]]),
+            measured_power: bytes[20] as i8,
+        };
+
+        if !is_valid_id(&identity.uuid) {
+            return Err(BeaconError::InvalidUuid);
+        }
+
+        if !IBEACON_MEASURED_POWER_RANGE.contains(&identity.measured_power) {
+            return Err(BeaconError::InvalidPower);
+        }
+
+        Ok(identity)
+    }
+
+    /// Encode the identity as written to the control point.
+    pub fn to_bytes(&self) -> [u8; Self::ENCODED_LENGTH] {
+        let mut bytes = [0; Self::ENCODED_LENGTH];
+        bytes[0..16].copy_from_slice(&self.uuid);
+        bytes[16..18].copy_from_slice(&self.major.to_le_bytes());
+        bytes[18..20].copy_from_slice(&self.minor.to_le_bytes());
+        bytes[20] = self.measured_power as u8;
+        bytes
+    }
+
+    /// Returns the iBeacon payload of the manufacturer specific data. Unlike
+    /// the rest of BLE, iBeacon fields are big endian.
+    pub fn manufacturer_payload(&self) -> [u8; 23] {
+        let mut payload = [0; 23];
+        payload[0..2].copy_from_slice(&IBEACON_PREFIX);
+        payload[2..18].copy_from_slice(&self.uuid);
+        payload[18..20].copy_from_slice(&self.major.to_be_bytes());
+        payload[20..22].copy_from_slice(&self.minor.to_be_bytes());
+        payload[22] = self.measured_power as u8;
+        payload
+    }
+}
+
+/// Identity of an Eddystone-UID beacon.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct EddystoneUidIdentity {
+    /// Namespace shared by a deployment's beacons.
+    pub namespace: [u8; 10],
+
+    /// Beacon within the namespace.
+    pub instance: [u8; 6],
+
+    /// Received signal strength at 0 m, in dBm.
+    pub tx_power: i8,
+}
+
+impl EddystoneUidIdentity {
+    /// Length of an identity written to the control point: the namespace, the
+    /// instance, then the calibrated transmit power.
+    pub const ENCODED_LENGTH: usize = 17;
+
+    /// Validate and decode an identity written to the control point.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, BeaconError> {
+        let bytes: &[u8; Self::ENCODED_LENGTH] =
+            bytes.try_into().map_err(|_| BeaconError::InvalidLength)?;
+
+        // UNWRAP: Infallible. Slicing a fixed length array.
+        let identity = Self {
+            namespace: bytes[0..10].try_into().unwrap(),
+            instance:  bytes[10..16].try_into().unwrap(),
+            tx_power:  bytes[16] as i8,
+        };
+
+        if !is_valid_id(&identity.namespace) {
+            return Err(BeaconError::InvalidUuid);
+        }
+
+        if !EDDYSTONE_TX_POWER_RANGE.contains(&identity.tx_power) {
+            return Err(BeaconError::InvalidPower);
+        }
+
+        Ok(identity)
+    }
+
+    /// Encode the identity as written to the control point.
+    pub fn to_bytes(&self) -> [u8; Self::ENCODED_LENGTH] {
+        let mut bytes = [0; Self::ENCODED_LENGTH];
+        bytes[0..10].copy_from_slice(&self.namespace);
+        bytes[10..16].copy_from_slice(&self.instance);
+        bytes[16] = self.tx_power as u8;
+        bytes
+    }
+
+    /// Returns the Eddystone-UID frame carried in the service data.
+    pub fn service_data(&self) -> [u8; 20] {
+        let mut frame = [0; 20];
+        frame[0] = EDDYSTONE_UID_FRAME;
+        frame[1] = self.tx_power as u8;
+        frame[2..12].copy_from_slice(&self.namespace);
+        frame[12..18].copy_from_slice(&self.instance);
+        // The last two bytes are reserved and must be zero.
+        frame
+    }
+}
+
+/// Eddystone-URL frame broadcasting a URL, carried in the service data.
+pub struct EddystoneUrlFrame {
+    frame: heapless::Vec<u8, { EDDYSTONE_URL_HEADER_LENGTH + EDDYSTONE_URL_MAX_LENGTH }>,
+}
+
+impl EddystoneUrlFrame {
+    /// Encode `url`, received at `tx_power` dBm at 0 m.
+    ///
+    /// The URL's scheme and common domain suffixes, such as `.com/`, are each
+    /// compressed to a single byte.
+    pub fn new(url: &str, tx_power: i8) -> Result<Self, BeaconError> {
+        if !EDDYSTONE_TX_POWER_RANGE.contains(&tx_power) {
+            return Err(BeaconError::InvalidPower);
+        }
+
+        // The longest matching scheme, "http://www." before "http://".
+        let (scheme, mut rest) = EDDYSTONE_URL_SCHEMES
+            .iter()
+            .enumerate()
+            .find_map(|(code, scheme)| Some((code as u8, url.strip_prefix(scheme)?)))
+            .ok_or(BeaconError::InvalidUrl)?;
+
+        let mut frame = heapless::Vec::new();
+
+        // UNWRAP: Infallible. The header fits in an empty frame.
+        frame
+            .extend_from_slice(&[EDDYSTONE_URL_FRAME, tx_power as u8, scheme])
+            .unwrap();
+
+        while !rest.is_empty() {
+            let expansion = EDDYSTONE_URL_EXPANSIONS
+                .iter()
+                .position(|expansion| rest.starts_with(expansion));
+
+            let byte = match expansion {
+                Some(code) => {
+                    rest = &rest[EDDYSTONE_URL_EXPANSIONS[code].len()..];
+                    code as u8
+                }
+                None => {
+                    // Bytes outside of printable ASCII are reserved.
+                    let byte = rest.as_bytes()[0];
+                    if !byte.is_ascii_graphic() {
+                        return Err(BeaconError::InvalidUrl);
+                    }
+                    rest = &rest[1..];
+                    byte
+                }
+            };
+
+            frame.push(byte).map_err(|_| BeaconError::UrlTooLong)?;
+        }
+
+        Ok(Self { frame })
+    }
+
+    /// Returns the encoded frame.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.frame
+    }
+}
+
+/// Identity broadcast when the device advertises as a beacon.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum BeaconIdentity {
+    /// No identity has been provisioned, the device does not act as a beacon.
+    Unprovisioned,
+
+    /// Broadcast as an iBeacon.
+    IBeacon(IBeaconIdentity),
+
+    /// Broadcast as an Eddystone-UID beacon.
+    EddystoneUid(EddystoneUidIdentity),
+}
+
+impl BeaconIdentity {
+    /// Length of the identity persisted in the device configuration: a tag
+    /// followed by the longest identity.
+    pub const ENCODED_LENGTH: usize = 1 + IBeaconIdentity::ENCODED_LENGTH;
+
+    /// Encode the identity for the device configuration.
+    pub fn encode(&self) -> [u8; Self::ENCODED_LENGTH] {
+        let mut bytes = [0; Self::ENCODED_LENGTH];
+        match self {
+            Self::Unprovisioned => {}
+            Self::IBeacon(identity) => {
+                bytes[0] = 1;
+                bytes[1..].copy_from_slice(&identity.to_bytes());
+            }
+            Self::EddystoneUid(identity) => {
+                bytes[0] = 2;
+                bytes[1..1 + EddystoneUidIdentity::ENCODED_LENGTH]
+                    .copy_from_slice(&identity.to_bytes());
+            }
+        }
+        bytes
+    }
+
+    /// Decode an identity from the device configuration. Identities that no
+    /// longer validate are treated as unprovisioned.
+    pub fn decode(bytes: &[u8; Self::ENCODED_LENGTH]) -> Self {
+        let (tag, identity) = (bytes[0], &bytes[1..]);
+        let decoded = match tag {
+            1 => IBeaconIdentity::from_bytes(identity).map(Self::IBeacon),
+            2 => {
+                EddystoneUidIdentity::from_bytes(&identity[..EddystoneUidIdentity::ENCODED_LENGTH])
+                    .map(Self::EddystoneUid)
+            }
+            _ => Ok(Self::Unprovisioned),
+        };
+
+        decoded.unwrap_or(Self::Unprovisioned)
+    }
+}
+
+/// Returns `false` for identifiers scanners treat as unset.
+fn is_valid_id(id: &[u8]) -> bool {
+    !id.iter().all(|&byte| byte == 0x00) && !id.iter().all(|&byte| byte == 0xff)
+}
@@ -0,0 +1,98 @@
+// SPDX-FileCopyrightText: 2025 Derek Sauer
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Layout of the device configuration persisted to flash.
+
+use crate::battery::BatteryChemistry;
+use crate::beacon::BeaconIdentity;
+
+/// Number of sensors the firmware samples, each enabled by a bit of
+/// [`DeviceConfig::enabled_sensors`].
+pub const SENSOR_COUNT: usize = 5;
+
+/// Mask with every sensor enabled.
+pub const ALL_SENSORS: u8 = (1 << SENSOR_COUNT) - 1;
+
+/// Marks a page holding a device configuration. Erased flash reads as all ones
+/// and never matches.
+const CONFIG_MAGIC: [u8; 4] = *b"LPCF";
+
+/// Version of the encoded configuration's layout. Configurations of another
+/// version are discarded in favour of the defaults.
+const CONFIG_VERSION: u8 = 1;
+
+/// Offset of the version in the encoded configuration, following the magic.
+const VERSION_OFFSET: usize = CONFIG_MAGIC.len();
+
+/// Offset of the beacon identity in the encoded configuration.
+const BEACON_OFFSET: usize = VERSION_OFFSET + 1;
+
+/// Offset of the enabled sensor mask in the encoded configuration.
+const ENABLED_SENSORS_OFFSET: usize = BEACON_OFFSET + BeaconIdentity::ENCODED_LENGTH;
+
+/// Offset of the battery chemistry in the encoded configuration.
+const BATTERY_CHEMISTRY_OFFSET: usize = ENABLED_SENSORS_OFFSET + 1;
+
+/// Length of the encoded configuration. New fields are appended so
+/// configurations stored before they existed read them from erased flash, as
+/// all ones.
+pub const ENCODED_LENGTH: usize = BATTERY_CHEMISTRY_OFFSET + 1;
+
+/// Battery chemistry assumed until one is configured. The Nano 33 BLE
+/// measures its regulated 3.3 V rail, which no discharge curve applies to.
+const DEFAULT_BATTERY_CHEMISTRY: BatteryChemistry = BatteryChemistry::Regulated;
+
+/// Configuration differentiating units running identical firmware.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DeviceConfig {
+    /// Identity broadcast when advertising as a beacon.
+    pub beacon: BeaconIdentity,
+
+    /// Mask of the sensors sampled, one bit per sensor. Erased flash enables
+    /// every sensor.
+    pub enabled_sensors: u8,
+
+    /// Chemistry of the cell powering the device.
+    pub battery_chemistry: BatteryChemistry,
+}
+
+impl DeviceConfig {
+    /// Configuration of a unit that has not been provisioned.
+    pub const DEFAULT: Self = Self {
+        beacon:            BeaconIdentity::Unprovisioned,
+        enabled_sensors:   ALL_SENSORS,
+        battery_chemistry: DEFAULT_BATTERY_CHEMISTRY,
+    };
+
+    /// Encode the configuration for storage.
+    pub fn encode(&self) -> [u8; ENCODED_LENGTH] {
+        let mut bytes = [0; ENCODED_LENGTH];
+        bytes[..VERSION_OFFSET].copy_from_slice(&CONFIG_MAGIC);
+        bytes[VERSION_OFFSET] = CONFIG_VERSION;
+        bytes[BEACON_OFFSET..ENABLED_SENSORS_OFFSET].copy_from_slice(&self.beacon.encode());
+        bytes[ENABLED_SENSORS_OFFSET] = self.enabled_sensors;
+        bytes[BATTERY_CHEMISTRY_OFFSET] = self.battery_chemistry as u8;
+        bytes
+    }
+
+    /// Decode a stored configuration, or `None` if none was stored.
+    pub fn decode(bytes: &[u8; ENCODED_LENGTH]) -> Option<Self> {
+        if bytes[..VERSION_OFFSET] != CONFIG_MAGIC || bytes[VERSION_OFFSET] != CONFIG_VERSION {
+            return None;
+        }
+
+        // UNWRAP: Infallible. Slicing a fixed length array.
+        let beacon = bytes[BEACON_OFFSET..ENABLED_SENSORS_OFFSET]
+            .try_into()
+            .unwrap();
+
+        Some(Self {
+            beacon:            BeaconIdentity::decode(beacon),
+            enabled_sensors:   bytes[ENABLED_SENSORS_OFFSET] & ALL_SENSORS,
+            battery_chemistry: BatteryChemistry::try_from(bytes[BATTERY_CHEMISTRY_OFFSET])
+                .unwrap_or(DEFAULT_BATTERY_CHEMISTRY),
+        })
+    }
+}
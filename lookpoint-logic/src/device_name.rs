@@ -0,0 +1,24 @@
+// SPDX-FileCopyrightText: 2025 Derek Sauer
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Fitting the device name in the room an advertisement leaves for it.
+
+/// Returns the longest prefix of `name` that is at most `max_length` bytes long
+/// and ends on a UTF-8 character boundary.
+///
+/// A multi-byte character straddling `max_length` is dropped entirely rather
+/// than split.
+pub fn truncate_on_char_boundary(name: &str, max_length: usize) -> &str {
+    if name.len() <= max_length {
+        return name;
+    }
+
+    // Index 0 is always a character boundary so this terminates.
+    let mut end = max_length;
+    while !name.is_char_boundary(end) {
+        end -= 1;
+    }
+
+    &name[..end]
+}
@@ -0,0 +1,26 @@
+// SPDX-FileCopyrightText: 2025 Derek Sauer
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Logic of the Lookpoint firmware that does not touch the hardware: encoding
+//! advertisements, framing the records stored in flash, and interpreting
+//! measurements.
+//!
+//! Nothing here depends on the chip, the BLE stack, or the executor, so it
+//! builds for the host and is tested there:
+//!
+//! ```sh
+//! cargo test -p lookpoint-logic --target x86_64-unknown-linux-gnu
+//! ```
+
+#![no_std]
+
+pub mod advertising;
+pub mod battery;
+pub mod beacon;
+pub mod config;
+pub mod device_name;
+pub mod reset_reason;
+pub mod settings;
+pub mod storage;
+pub mod subscriptions;
@@ -0,0 +1,74 @@
+// SPDX-FileCopyrightText: 2025 Derek Sauer
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Decoding why the chip last reset from the nRF52's RESETREAS register.
+
+/// RESETREAS bit set by the reset pin.
+const RESETPIN: u32 = 1 << 0;
+
+/// RESETREAS bit set by the watchdog.
+const DOG: u32 = 1 << 1;
+
+/// RESETREAS bit set by a soft reset.
+const SREQ: u32 = 1 << 2;
+
+/// RESETREAS bit set by a CPU lockup.
+const LOCKUP: u32 = 1 << 3;
+
+/// RESETREAS bits set by waking from System OFF: by a DETECT signal from GPIO,
+/// by LPCOMP, by NFC field detection, or by VBUS rising.
+const WAKE_FROM_SYSTEM_OFF: u32 = 1 << 16 | 1 << 17 | 1 << 19 | 1 << 20;
+
+/// RESETREAS bit set by entering debug interface mode.
+const DIF: u32 = 1 << 18;
+
+/// Cause of the chip's last reset.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ResetReason {
+    /// Power was applied, or the supply dropped below the brownout level.
+    PowerOn,
+
+    /// The reset pin was asserted.
+    ResetPin,
+
+    /// The watchdog timed out.
+    Watchdog,
+
+    /// The firmware requested a reset.
+    SoftReset,
+
+    /// The CPU locked up, for example after a fault within a fault handler.
+    Lockup,
+
+    /// The chip woke from System OFF.
+    WakeFromSystemOff,
+
+    /// A debugger entered or left debug interface mode.
+    Debugger,
+}
+
+impl ResetReason {
+    /// Decode the value of the RESETREAS register. When several reasons are
+    /// set, the reset pin takes precedence, then the watchdog, a soft reset, a
+    /// lockup, waking from System OFF, and the debugger. No reason is set after
+    /// a power on reset or a brownout.
+    pub fn from_resetreas(resetreas: u32) -> Self {
+        if resetreas & RESETPIN != 0 {
+            Self::ResetPin
+        } else if resetreas & DOG != 0 {
+            Self::Watchdog
+        } else if resetreas & SREQ != 0 {
+            Self::SoftReset
+        } else if resetreas & LOCKUP != 0 {
+            Self::Lockup
+        } else if resetreas & WAKE_FROM_SYSTEM_OFF != 0 {
+            Self::WakeFromSystemOff
+        } else if resetreas & DIF != 0 {
+            Self::Debugger
+        } else {
+            Self::PowerOn
+        }
+    }
+}
@@ -0,0 +1,320 @@
+// SPDX-FileCopyrightText: 2025 Derek Sauer
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Framing of the typed settings persisted to flash.
+//!
+//! Each setting is stored under a [`Key`] naming its type, so it can only be
+//! read and written as that type. Every setting shares one record, encoded as:
+//!
+//! | Offset | Length | Field                                         |
+//! |--------|--------|-----------------------------------------------|
+//! | 0      | 4      | Magic, `LPST`                                 |
+//! | 4      | 4      | Sequence number, little endian                |
+//! | 8      | 1      | Length of the settings                        |
+//! | 9      | n      | Settings, each as its key, length and value   |
+//! | 9 + n  | 4      | CRC-32 of the preceding bytes, little endian  |
+//!
+//! A brownout can cut a write short and leave a torn record. Records are
+//! therefore written alternately to [`RECORD_SLOTS`] slots, and
+//! [`Settings::from_records`] takes the newest intact one: a torn write
+//! reverts to the record written before it.
+
+use core::marker::PhantomData;
+use core::ops::Range;
+
+use crate::storage::{MAX_RECORD_LENGTH, crc32};
+
+/// Number of slots the settings records are written to in turn, the record
+/// numbered `n` to slot `n % RECORD_SLOTS`.
+pub const RECORD_SLOTS: usize = 2;
+
+/// Marks a slot holding settings. Erased flash reads as all ones and never
+/// matches.
+const SETTINGS_MAGIC: [u8; 4] = *b"LPST";
+
+/// Offset of the record's sequence number in the encoded record, following
+/// the magic.
+const SEQUENCE_OFFSET: usize = SETTINGS_MAGIC.len();
+
+/// Offset of the length of the settings in the encoded record.
+const LENGTH_OFFSET: usize = SEQUENCE_OFFSET + 4;
+
+/// Offset of the first setting in the encoded record.
+const SETTINGS_OFFSET: usize = LENGTH_OFFSET + 1;
+
+/// Length of the CRC following the settings in the encoded record.
+const CRC_LENGTH: usize = 4;
+
+/// Room for settings in a record.
+const SETTINGS_CAPACITY: usize = MAX_RECORD_LENGTH - SETTINGS_OFFSET - CRC_LENGTH;
+
+/// Length of the header preceding each setting's value: its key and the
+/// value's length.
+const HEADER_LENGTH: usize = 2;
+
+/// Longest encoded value of a setting.
+pub const MAX_VALUE_LENGTH: usize = 32;
+
+/// An encoded settings record.
+pub type Record = heapless::Vec<u8, MAX_RECORD_LENGTH>;
+
+/// The settings no longer fit in a record.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SettingsFull;
+
+/// Identifies a setting, and the type of its value.
+pub struct Key<T> {
+    id:     u8,
+    _value: PhantomData<T>,
+}
+
+impl<T> Key<T> {
+    /// Create the key of the setting `id`. Ids are part of the stored format
+    /// and must remain stable.
+    pub const fn new(id: u8) -> Self {
+        Self {
+            id,
+            _value: PhantomData,
+        }
+    }
+}
+
+impl<T> Clone for Key<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Key<T> {}
+
+/// Value of a setting, encoded for storage.
+pub trait Value: Sized {
+    /// Encode the value into `buffer`, returning the encoded length.
+    fn encode(&self, buffer: &mut [u8; MAX_VALUE_LENGTH]) -> usize;
+
+    /// Decode a stored value, or `None` if it is invalid.
+    fn decode(bytes: &[u8]) -> Option<Self>;
+}
+
+impl Value for i8 {
+    fn encode(&self, buffer: &mut [u8; MAX_VALUE_LENGTH]) -> usize {
+        buffer[0] = *self as u8;
+        1
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        match bytes {
+            [value] => Some(*value as i8),
+            _ => None,
+        }
+    }
+}
+
+impl Value for i16 {
+    fn encode(&self, buffer: &mut [u8; MAX_VALUE_LENGTH]) -> usize {
+        buffer[..2].copy_from_slice(&self.to_le_bytes());
+        2
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        bytes.try_into().ok().map(i16::from_le_bytes)
+    }
+}
+
+impl Value for u32 {
+    fn encode(&self, buffer: &mut [u8; MAX_VALUE_LENGTH]) -> usize {
+        buffer[..4].copy_from_slice(&self.to_le_bytes());
+        4
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        bytes.try_into().ok().map(u32::from_le_bytes)
+    }
+}
+
+impl Value for heapless::String<MAX_VALUE_LENGTH> {
+    fn encode(&self, buffer: &mut [u8; MAX_VALUE_LENGTH]) -> usize {
+        buffer[..self.len()].copy_from_slice(self.as_bytes());
+        self.len()
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        let string = core::str::from_utf8(bytes).ok()?;
+        heapless::String::try_from(string).ok()
+    }
+}
+
+/// Returns the range of the setting with key `id` in `settings`, header
+/// included, if any.
+fn find(settings: &[u8], id: u8) -> Option<Range<usize>> {
+    let mut offset = 0;
+    while offset + HEADER_LENGTH <= settings.len() {
+        let end = offset + HEADER_LENGTH + usize::from(settings[offset + 1]);
+        if end > settings.len() {
+            return None;
+        }
+
+        if settings[offset] == id {
+            return Some(offset..end);
+        }
+
+        offset = end;
+    }
+
+    None
+}
+
+/// Encode `settings` for storage as the record numbered `sequence`.
+fn encode(sequence: u32, settings: &[u8]) -> Record {
+    let mut bytes = Record::new();
+    // UNWRAP: Infallible. The settings fit in a record between the header and
+    // the CRC.
+    bytes.extend_from_slice(&SETTINGS_MAGIC).unwrap();
+    bytes.extend_from_slice(&sequence.to_le_bytes()).unwrap();
+    bytes.push(settings.len() as u8).unwrap();
+    bytes.extend_from_slice(settings).unwrap();
+
+    let crc = crc32(&bytes);
+    bytes.extend_from_slice(&crc.to_le_bytes()).unwrap();
+    bytes
+}
+
+/// Decode a stored settings record, returning its sequence number and
+/// settings, or `None` if none was stored or it is torn or corrupt.
+fn decode(bytes: &[u8; MAX_RECORD_LENGTH]) -> Option<(u32, heapless::Vec<u8, SETTINGS_CAPACITY>)> {
+    if bytes[..SEQUENCE_OFFSET] != SETTINGS_MAGIC {
+        return None;
+    }
+
+    let length = usize::from(bytes[LENGTH_OFFSET]);
+    if length > SETTINGS_CAPACITY {
+        return None;
+    }
+
+    let crc_offset = SETTINGS_OFFSET + length;
+    // UNWRAP: Infallible. The sequence number and CRC are 4 bytes long.
+    let crc = u32::from_le_bytes(
+        bytes[crc_offset..crc_offset + CRC_LENGTH]
+            .try_into()
+            .unwrap(),
+    );
+    if crc != crc32(&bytes[..crc_offset]) {
+        return None;
+    }
+
+    let sequence = u32::from_le_bytes(bytes[SEQUENCE_OFFSET..LENGTH_OFFSET].try_into().unwrap());
+    let settings = heapless::Vec::from_slice(&bytes[SETTINGS_OFFSET..crc_offset]).ok()?;
+    Some((sequence, settings))
+}
+
+/// Returns `true` if `bytes` start a settings record that is torn or corrupt,
+/// rather than holding an intact record or erased flash.
+pub fn is_torn(bytes: &[u8; MAX_RECORD_LENGTH]) -> bool {
+    bytes[..SEQUENCE_OFFSET] == SETTINGS_MAGIC && decode(bytes).is_none()
+}
+
+/// Settings, each encoded as its header followed by its value, and the
+/// sequence number of the record they were last stored in.
+#[derive(Clone, Default)]
+pub struct Settings {
+    sequence: u32,
+    settings: heapless::Vec<u8, SETTINGS_CAPACITY>,
+}
+
+impl Settings {
+    /// Create settings with every setting unset.
+    pub const fn new() -> Self {
+        Self {
+            sequence: 0,
+            settings: heapless::Vec::new(),
+        }
+    }
+
+    /// Load the newest intact record of those read from each slot. Every
+    /// setting is unset if none is intact.
+    pub fn from_records(records: &[[u8; MAX_RECORD_LENGTH]; RECORD_SLOTS]) -> Self {
+        let [first, second] = records.each_ref().map(decode);
+
+        // Sequence numbers wrap around, the newer record is at most half the
+        // range ahead.
+        let newest = match (first, second) {
+            (Some(first), Some(second)) => {
+                if (second.0.wrapping_sub(first.0) as i32) > 0 {
+                    Some(second)
+                } else {
+                    Some(first)
+                }
+            }
+            (first, second) => first.or(second),
+        };
+
+        let (sequence, settings) = newest.unwrap_or_default();
+        Self { sequence, settings }
+    }
+
+    /// Returns the sequence number of the record the settings were last
+    /// stored in.
+    pub fn sequence(&self) -> u32 {
+        self.sequence
+    }
+
+    /// Returns the length of the encoded settings.
+    pub fn len(&self) -> usize {
+        self.settings.len()
+    }
+
+    /// Returns `true` if every setting is unset.
+    pub fn is_empty(&self) -> bool {
+        self.settings.is_empty()
+    }
+
+    /// Returns the value of the setting `key`, or `None` if it is unset or its
+    /// stored value is invalid.
+    pub fn get<T: Value>(&self, key: Key<T>) -> Option<T> {
+        let setting = find(&self.settings, key.id)?;
+        T::decode(&self.settings[setting.start + HEADER_LENGTH..setting.end])
+    }
+
+    /// Set the setting `key` to `value`, returning the record to persist and
+    /// the slot to write it to.
+    ///
+    /// The older of the two records is overwritten, keeping the newer one
+    /// should this write be torn. Fails, leaving the settings unchanged, if
+    /// they no longer fit in a record.
+    pub fn set<T: Value>(
+        &mut self,
+        key: Key<T>,
+        value: &T,
+    ) -> Result<(usize, Record), SettingsFull> {
+        let mut encoded = [0; MAX_VALUE_LENGTH];
+        let length = value.encode(&mut encoded);
+
+        // Replace any earlier value of the setting.
+        let mut updated = heapless::Vec::<u8, SETTINGS_CAPACITY>::new();
+        // UNWRAP: Infallible. Copying a subset of settings of the same capacity.
+        match find(&self.settings, key.id) {
+            Some(setting) => {
+                updated
+                    .extend_from_slice(&self.settings[..setting.start])
+                    .unwrap();
+                updated
+                    .extend_from_slice(&self.settings[setting.end..])
+                    .unwrap();
+            }
+            None => updated.extend_from_slice(&self.settings).unwrap(),
+        }
+
+        updated
+            .extend_from_slice(&[key.id, length as u8])
+            .and_then(|()| updated.extend_from_slice(&encoded[..length]))
+            .map_err(|_| SettingsFull)?;
+
+        self.settings = updated;
+        self.sequence = self.sequence.wrapping_add(1);
+
+        let slot = self.sequence as usize % RECORD_SLOTS;
+        Ok((slot, encode(self.sequence, &self.settings)))
+    }
+}
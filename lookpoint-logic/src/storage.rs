@@ -0,0 +1,26 @@
+// SPDX-FileCopyrightText: 2025 Derek Sauer
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Limits and checksums shared by the records stored in flash.
+
+/// Largest record written to a page in one request.
+pub const MAX_RECORD_LENGTH: usize = 256;
+
+/// Returns the CRC-32 (IEEE 802.3) of `bytes`, detecting records corrupted
+/// in flash.
+pub fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = u32::MAX;
+    for &byte in bytes {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+
+    !crc
+}
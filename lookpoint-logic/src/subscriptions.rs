@@ -0,0 +1,108 @@
+// SPDX-FileCopyrightText: 2025 Derek Sauer
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Tracks which characteristics a client subscribed to notifications of, so
+//! notifications are only sent to clients that asked for them.
+
+use core::cell::Cell;
+
+/// Bit of a Client Characteristic Configuration Descriptor's value enabling
+/// notifications.
+const CCCD_NOTIFY: u16 = 0x0001;
+
+/// Characteristics that notify subscribed clients.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Notifying {
+    StationaryTime = 0,
+    Link           = 1,
+    BatteryLevel   = 2,
+    Temperature    = 3,
+    ConsoleOutput  = 4,
+    LastMotion     = 5,
+    Rssi           = 6,
+}
+
+impl Notifying {
+    /// Bit of this characteristic in a [`Subscriptions`] table.
+    const fn bit(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+/// Subscriptions of the client of a single connection, updated as it writes
+/// the CCCDs of notifying characteristics.
+///
+/// A table is created for each connection and dropped when it ends. A bonded
+/// client's table is restored from the CCCD values kept for it with
+/// [`Subscriptions::from_cccds`], other clients start with no subscriptions.
+pub struct Subscriptions {
+    /// One bit per [`Notifying`] characteristic.
+    subscribed: Cell<u8>,
+}
+
+impl Subscriptions {
+    /// Create a table with no subscriptions.
+    pub const fn new() -> Self {
+        Self {
+            subscribed: Cell::new(0),
+        }
+    }
+
+    /// Create a table from `bits`, as returned by [`Subscriptions::bits`].
+    pub const fn from_bits(bits: u8) -> Self {
+        Self {
+            subscribed: Cell::new(bits),
+        }
+    }
+
+    /// Create a table from the CCCD values `cccds`, pairs of a CCCD's handle
+    /// and value. `characteristics` pairs each notifying characteristic with
+    /// the handle of its CCCD, if it has one. A characteristic is subscribed
+    /// if its CCCD's value enables notifications.
+    pub fn from_cccds(
+        characteristics: &[(Notifying, Option<u16>)],
+        cccds: impl IntoIterator<Item = (u16, u16)>,
+    ) -> Self {
+        let subscriptions = Self::new();
+
+        for (cccd_handle, value) in cccds {
+            let characteristic = characteristics
+                .iter()
+                .find(|&&(_, handle)| handle == Some(cccd_handle));
+            if let Some(&(characteristic, _)) = characteristic {
+                subscriptions.set(characteristic, value & CCCD_NOTIFY != 0);
+            }
+        }
+
+        subscriptions
+    }
+
+    /// Record whether the client is subscribed to `characteristic`.
+    pub fn set(&self, characteristic: Notifying, subscribed: bool) {
+        let mask = self.subscribed.get();
+        self.subscribed.set(if subscribed {
+            mask | characteristic.bit()
+        } else {
+            mask & !characteristic.bit()
+        });
+    }
+
+    /// Returns `true` if the client is subscribed to `characteristic`.
+    pub fn is_subscribed(&self, characteristic: Notifying) -> bool {
+        self.subscribed.get() & characteristic.bit() != 0
+    }
+
+    /// Returns the subscriptions as one bit per [`Notifying`] characteristic,
+    /// to persist them.
+    pub fn bits(&self) -> u8 {
+        self.subscribed.get()
+    }
+}
+
+impl Default for Subscriptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
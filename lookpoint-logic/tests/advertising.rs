@@ -0,0 +1,233 @@
+// SPDX-FileCopyrightText: 2025 Derek Sauer
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use lookpoint_logic::advertising::{AdvError, AdvertisementBuilder, LEGACY_PAYLOAD_LENGTH};
+
+/// Flags of a general discoverable, LE only advertisement.
+const FLAGS: u8 = 0x06;
+
+/// Company identifier of the status in the manufacturer specific data.
+const COMPANY_IDENTIFIER: u16 = 0xffff;
+
+/// AD types checked by the tests.
+const FLAGS_TYPE: u8 = 0x01;
+const INCOMPLETE_SERVICE_UUIDS16: u8 = 0x02;
+const SERVICE_UUIDS16: u8 = 0x03;
+const SHORTENED_LOCAL_NAME: u8 = 0x08;
+const COMPLETE_LOCAL_NAME: u8 = 0x09;
+const TX_POWER_LEVEL: u8 = 0x0a;
+const APPEARANCE: u8 = 0x19;
+const MANUFACTURER_DATA: u8 = 0xff;
+
+/// Split an encoded payload into its AD structures, as `(type, data)`.
+fn structures(payload: &[u8]) -> Vec<(u8, &[u8])> {
+    let mut structures = Vec::new();
+    let mut rest = payload;
+    while let [length, ..] = rest {
+        let length = usize::from(*length);
+        assert!(
+            length >= 1 && length < rest.len(),
+            "malformed {payload:02x?}"
+        );
+        structures.push((rest[1], &rest[2..1 + length]));
+        rest = &rest[1 + length..];
+    }
+    structures
+}
+
+/// Returns the data of the structure of type `ty` in `payload`, if any.
+fn find(payload: &[u8], ty: u8) -> Option<&[u8]> {
+    structures(payload)
+        .into_iter()
+        .find_map(|(found, data)| (found == ty).then_some(data))
+}
+
+/// The 16-bit UUIDs of `count` services, in little endian byte order.
+fn service_uuids(count: u16) -> Vec<[u8; 2]> {
+    (0..count)
+        .map(|index| (0x1800 + index).to_le_bytes())
+        .collect()
+}
+
+#[test]
+fn everything_fits_in_the_advertising_data() {
+    let uuids = service_uuids(2);
+    let (adv_data, scan_data) = AdvertisementBuilder::new()
+        .flags(FLAGS)
+        .service_uuids16(&uuids)
+        .local_name("Lookpoint")
+        .build()
+        .unwrap();
+
+    assert_eq!(
+        adv_data.as_slice(),
+        [
+            &[2, FLAGS_TYPE, FLAGS][..],
+            &[5, SERVICE_UUIDS16, 0x00, 0x18, 0x01, 0x18],
+            &[10, COMPLETE_LOCAL_NAME],
+            b"Lookpoint",
+        ]
+        .concat()
+    );
+    assert!(scan_data.is_empty());
+}
+
+#[test]
+fn long_name_moves_to_the_scan_response() {
+    let uuids = service_uuids(2);
+    let name = "Lookpoint Tracker 0042";
+    let (adv_data, scan_data) = AdvertisementBuilder::new()
+        .flags(FLAGS)
+        .service_uuids16(&uuids)
+        .manufacturer_data(COMPANY_IDENTIFIER, &[0x01])
+        .local_name(name)
+        .build()
+        .unwrap();
+
+    // The complete name goes in the scan response.
+    assert_eq!(
+        structures(&scan_data),
+        [(COMPLETE_LOCAL_NAME, name.as_bytes())]
+    );
+
+    // Whatever room is left in the advertising data carries the start of it:
+    // 31 bytes, less the flags (3), UUIDs (6), status (5) and name header (2).
+    assert_eq!(find(&adv_data, COMPLETE_LOCAL_NAME), None);
+    assert_eq!(
+        find(&adv_data, SHORTENED_LOCAL_NAME),
+        Some(&name.as_bytes()[..15])
+    );
+    assert_eq!(adv_data.len(), LEGACY_PAYLOAD_LENGTH);
+}
+
+#[test]
+fn shortened_name_is_cut_on_a_character_boundary() {
+    let uuids = service_uuids(2);
+    // The 15th and 16th bytes are a single character.
+    let name = "Lookpoint Trac\u{e9} 0042";
+    let (adv_data, _) = AdvertisementBuilder::new()
+        .flags(FLAGS)
+        .service_uuids16(&uuids)
+        .manufacturer_data(COMPANY_IDENTIFIER, &[0x01])
+        .local_name(name)
+        .build()
+        .unwrap();
+
+    assert_eq!(
+        find(&adv_data, SHORTENED_LOCAL_NAME),
+        Some(&b"Lookpoint Trac"[..])
+    );
+}
+
+#[test]
+fn too_many_uuids_with_a_long_name_overflow_into_an_incomplete_list() {
+    let uuids = service_uuids(20);
+    let name = "Lookpoint Tracker 0042";
+    let (adv_data, scan_data) = AdvertisementBuilder::new()
+        .flags(FLAGS)
+        .service_uuids16(&uuids)
+        .local_name(name)
+        .build()
+        .unwrap();
+
+    // Only 13 UUIDs fit after the flags and the list's header, and the list
+    // is marked incomplete.
+    let listed = find(&adv_data, INCOMPLETE_SERVICE_UUIDS16).unwrap();
+    assert_eq!(listed, uuids[..13].as_flattened());
+    assert_eq!(find(&adv_data, SERVICE_UUIDS16), None);
+    assert_eq!(adv_data.len(), LEGACY_PAYLOAD_LENGTH);
+
+    // The UUIDs leave no room for even a shortened name, so it is only in
+    // the scan response.
+    assert_eq!(find(&adv_data, SHORTENED_LOCAL_NAME), None);
+    assert_eq!(
+        structures(&scan_data),
+        [(COMPLETE_LOCAL_NAME, name.as_bytes())]
+    );
+}
+
+#[test]
+fn optional_fields_that_do_not_fit_move_to_the_scan_response() {
+    let uuids = service_uuids(4);
+    let (adv_data, scan_data) = AdvertisementBuilder::new()
+        .flags(FLAGS)
+        .service_uuids16(&uuids)
+        .manufacturer_data(COMPANY_IDENTIFIER, &[0x55; 12])
+        .tx_power_level(-8)
+        .appearance([0xc0, 0x07])
+        .build()
+        .unwrap();
+
+    // Flags (3), UUIDs (10) and manufacturer data (16) leave 2 bytes, too
+    // few for the 3 byte TX power level or the 4 byte appearance.
+    assert!(find(&adv_data, MANUFACTURER_DATA).is_some());
+    assert_eq!(
+        structures(&scan_data),
+        [
+            (TX_POWER_LEVEL, &[0xf8][..]),
+            (APPEARANCE, &[0xc0, 0x07][..])
+        ]
+    );
+}
+
+#[test]
+fn manufacturer_data_carries_the_company_identifier_first() {
+    let (adv_data, _) = AdvertisementBuilder::new()
+        .manufacturer_data(0x004c, &[0x02, 0x15])
+        .build()
+        .unwrap();
+
+    assert_eq!(
+        adv_data.as_slice(),
+        [5, MANUFACTURER_DATA, 0x4c, 0x00, 0x02, 0x15]
+    );
+}
+
+#[test]
+fn name_too_long_for_the_scan_response_overflows() {
+    let name = "Lookpoint Tracker with a very long name";
+    let result = AdvertisementBuilder::new()
+        .flags(FLAGS)
+        .local_name(name)
+        .build();
+
+    assert_eq!(result, Err(AdvError::ScanDataOverflow));
+}
+
+#[test]
+fn field_too_long_for_any_payload_overflows() {
+    let result = AdvertisementBuilder::new()
+        .manufacturer_data(COMPANY_IDENTIFIER, &[0; 28])
+        .build();
+
+    assert_eq!(result, Err(AdvError::ScanDataOverflow));
+}
+
+#[test]
+fn scan_response_overflows_once_full() {
+    let uuids = service_uuids(13);
+    let result = AdvertisementBuilder::new()
+        .flags(FLAGS)
+        .service_uuids16(&uuids)
+        .manufacturer_data(COMPANY_IDENTIFIER, &[0; 20])
+        .local_name("Lookpoint Tracker")
+        .build();
+
+    // The manufacturer data fills the scan response, leaving no room for the
+    // name.
+    assert_eq!(result, Err(AdvError::ScanDataOverflow));
+}
+
+#[test]
+fn beacons_must_fit_in_the_advertising_data() {
+    let uuids = service_uuids(2);
+    let builder = AdvertisementBuilder::new()
+        .flags(FLAGS)
+        .service_uuids16(&uuids)
+        .local_name("Lookpoint");
+    assert!(builder.build_beacon().is_ok());
+
+    let builder = builder.local_name("Lookpoint Tracker 0042");
+    assert_eq!(builder.build_beacon(), Err(AdvError::AdvDataOverflow));
+}
@@ -0,0 +1,115 @@
+// SPDX-FileCopyrightText: 2025 Derek Sauer
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use lookpoint_logic::battery::{
+    BATTERY_LOW_THRESHOLD, BATTERY_RECOVERED_THRESHOLD, BatteryChemistry, LowBattery,
+};
+
+#[test]
+fn voltages_beyond_the_curve_are_clamped() {
+    assert_eq!(
+        BatteryChemistry::LiPo.percent_from_millivolts(4300),
+        Some(100)
+    );
+    assert_eq!(
+        BatteryChemistry::LiPo.percent_from_millivolts(2500),
+        Some(0)
+    );
+    assert_eq!(
+        BatteryChemistry::Cr2032.percent_from_millivolts(3300),
+        Some(100)
+    );
+    assert_eq!(
+        BatteryChemistry::Cr2032.percent_from_millivolts(1800),
+        Some(0)
+    );
+}
+
+#[test]
+fn curve_points_map_exactly() {
+    assert_eq!(
+        BatteryChemistry::LiPo.percent_from_millivolts(4200),
+        Some(100)
+    );
+    assert_eq!(
+        BatteryChemistry::LiPo.percent_from_millivolts(3820),
+        Some(50)
+    );
+    assert_eq!(
+        BatteryChemistry::LiPo.percent_from_millivolts(3000),
+        Some(0)
+    );
+    assert_eq!(
+        BatteryChemistry::Cr2032.percent_from_millivolts(2800),
+        Some(60)
+    );
+}
+
+#[test]
+fn levels_between_points_are_interpolated() {
+    // Halfway between 3820 mV at 50% and 3870 mV at 60%.
+    assert_eq!(
+        BatteryChemistry::LiPo.percent_from_millivolts(3845),
+        Some(55)
+    );
+
+    // Halfway between 2000 mV at 0% and 2500 mV at 10%.
+    assert_eq!(
+        BatteryChemistry::Cr2032.percent_from_millivolts(2250),
+        Some(5)
+    );
+}
+
+#[test]
+fn levels_never_increase_as_the_voltage_drops() {
+    for chemistry in [BatteryChemistry::Cr2032, BatteryChemistry::LiPo] {
+        let mut previous = 100;
+        for millivolts in (1500..=4500).rev() {
+            // UNWRAP: Infallible. Both chemistries have a discharge curve.
+            let percent = chemistry.percent_from_millivolts(millivolts).unwrap();
+            assert!(percent <= previous, "{chemistry:?} at {millivolts} mV");
+            previous = percent;
+        }
+    }
+}
+
+#[test]
+fn regulated_supplies_have_no_level() {
+    // The Nano 33 BLE's rail reads as 3.3 V whatever the battery's charge.
+    assert_eq!(
+        BatteryChemistry::Regulated.percent_from_millivolts(3300),
+        None
+    );
+    assert_eq!(BatteryChemistry::Regulated.percent_from_millivolts(0), None);
+}
+
+#[test]
+fn chemistries_round_trip_through_their_values() {
+    for chemistry in [
+        BatteryChemistry::Cr2032,
+        BatteryChemistry::LiPo,
+        BatteryChemistry::Regulated,
+    ] {
+        assert_eq!(BatteryChemistry::try_from(chemistry as u8), Ok(chemistry));
+    }
+
+    assert_eq!(BatteryChemistry::try_from(0xff), Err(0xff));
+}
+
+#[test]
+fn low_battery_has_hysteresis() {
+    let mut low_battery = LowBattery::new();
+
+    assert_eq!(low_battery.update(BATTERY_LOW_THRESHOLD), None);
+    assert_eq!(low_battery.update(BATTERY_LOW_THRESHOLD - 1), Some(true));
+    assert!(low_battery.is_low());
+
+    // Hovering between the thresholds does not flap.
+    assert_eq!(low_battery.update(BATTERY_LOW_THRESHOLD), None);
+    assert_eq!(low_battery.update(BATTERY_RECOVERED_THRESHOLD - 1), None);
+    assert_eq!(low_battery.update(BATTERY_LOW_THRESHOLD - 1), None);
+
+    assert_eq!(low_battery.update(BATTERY_RECOVERED_THRESHOLD), Some(false));
+    assert!(!low_battery.is_low());
+}
@@ -0,0 +1,57 @@
+// SPDX-FileCopyrightText: 2025 Derek Sauer
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use lookpoint_logic::beacon::{BeaconError, EDDYSTONE_URL_MAX_LENGTH, EddystoneUrlFrame};
+
+#[test]
+fn url_scheme_and_expansions_are_compressed() {
+    let frame = EddystoneUrlFrame::new("https://www.example.com/tag", -18).unwrap();
+
+    let mut expected = vec![0x10, -18_i8 as u8, 0x01];
+    expected.extend_from_slice(b"example");
+    expected.push(0x00);
+    expected.extend_from_slice(b"tag");
+    assert_eq!(frame.as_bytes(), expected);
+}
+
+#[test]
+fn expansion_without_slash_is_compressed() {
+    let frame = EddystoneUrlFrame::new("http://example.org", 0).unwrap();
+
+    assert_eq!(&frame.as_bytes()[2..], b"\x02example\x08");
+}
+
+#[test]
+fn url_filling_the_field_is_accepted() {
+    let url = format!("https://{}", "a".repeat(EDDYSTONE_URL_MAX_LENGTH));
+
+    let frame = EddystoneUrlFrame::new(&url, 0).unwrap();
+    assert_eq!(frame.as_bytes().len(), 3 + EDDYSTONE_URL_MAX_LENGTH);
+}
+
+#[test]
+fn url_too_long_is_rejected() {
+    let url = format!("https://{}", "a".repeat(EDDYSTONE_URL_MAX_LENGTH + 1));
+
+    assert_eq!(
+        EddystoneUrlFrame::new(&url, 0).err(),
+        Some(BeaconError::UrlTooLong)
+    );
+}
+
+#[test]
+fn unsupported_scheme_is_rejected() {
+    assert_eq!(
+        EddystoneUrlFrame::new("ftp://example.com", 0).err(),
+        Some(BeaconError::InvalidUrl)
+    );
+}
+
+#[test]
+fn non_printable_character_is_rejected() {
+    assert_eq!(
+        EddystoneUrlFrame::new("https://exa mple.com", 0).err(),
+        Some(BeaconError::InvalidUrl)
+    );
+}
@@ -0,0 +1,93 @@
+// SPDX-FileCopyrightText: 2025 Derek Sauer
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use lookpoint_logic::battery::BatteryChemistry;
+use lookpoint_logic::beacon::{BeaconIdentity, EddystoneUidIdentity, IBeaconIdentity};
+use lookpoint_logic::config::{ALL_SENSORS, DeviceConfig, ENCODED_LENGTH};
+
+fn ibeacon() -> IBeaconIdentity {
+    IBeaconIdentity {
+        uuid:           [0x42; 16],
+        major:          1,
+        minor:          2,
+        measured_power: -59,
+    }
+}
+
+#[test]
+fn round_trips_every_field() {
+    let config = DeviceConfig {
+        beacon:            BeaconIdentity::IBeacon(ibeacon()),
+        enabled_sensors:   0b00101,
+        battery_chemistry: BatteryChemistry::Cr2032,
+    };
+
+    assert_eq!(DeviceConfig::decode(&config.encode()), Some(config));
+}
+
+#[test]
+fn round_trips_an_eddystone_identity() {
+    let config = DeviceConfig {
+        beacon: BeaconIdentity::EddystoneUid(EddystoneUidIdentity {
+            namespace: [0x11; 10],
+            instance:  [0x22; 6],
+            tx_power:  -20,
+        }),
+        ..DeviceConfig::DEFAULT
+    };
+
+    assert_eq!(DeviceConfig::decode(&config.encode()), Some(config));
+}
+
+#[test]
+fn erased_flash_holds_no_configuration() {
+    assert_eq!(DeviceConfig::decode(&[0xff; ENCODED_LENGTH]), None);
+}
+
+#[test]
+fn other_versions_are_discarded() {
+    let mut bytes = DeviceConfig::DEFAULT.encode();
+    bytes[4] = bytes[4].wrapping_add(1);
+
+    assert_eq!(DeviceConfig::decode(&bytes), None);
+}
+
+#[test]
+fn fields_appended_later_read_from_erased_flash() {
+    // A configuration stored before the battery chemistry existed: the
+    // chemistry's byte was never written.
+    let mut bytes = DeviceConfig::DEFAULT.encode();
+    bytes[ENCODED_LENGTH - 1] = 0xff;
+
+    let config = DeviceConfig::decode(&bytes).unwrap();
+    assert_eq!(
+        config.battery_chemistry,
+        DeviceConfig::DEFAULT.battery_chemistry
+    );
+}
+
+#[test]
+fn unknown_sensor_bits_are_ignored() {
+    let config = DeviceConfig {
+        enabled_sensors: 0xff,
+        ..DeviceConfig::DEFAULT
+    };
+
+    let decoded = DeviceConfig::decode(&config.encode()).unwrap();
+    assert_eq!(decoded.enabled_sensors, ALL_SENSORS);
+}
+
+#[test]
+fn invalid_beacon_identities_read_as_unprovisioned() {
+    let config = DeviceConfig {
+        beacon: BeaconIdentity::IBeacon(IBeaconIdentity {
+            uuid: [0; 16],
+            ..ibeacon()
+        }),
+        ..DeviceConfig::DEFAULT
+    };
+
+    let decoded = DeviceConfig::decode(&config.encode()).unwrap();
+    assert_eq!(decoded.beacon, BeaconIdentity::Unprovisioned);
+}
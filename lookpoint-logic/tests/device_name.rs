@@ -0,0 +1,77 @@
+// SPDX-FileCopyrightText: 2025 Derek Sauer
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use lookpoint_logic::device_name::truncate_on_char_boundary;
+
+/// Longest name, in bytes, the tests truncate to.
+const MAX_LENGTH: usize = 19;
+
+#[test]
+fn empty_name_is_unchanged() {
+    assert_eq!(truncate_on_char_boundary("", MAX_LENGTH), "");
+}
+
+#[test]
+fn name_of_exactly_the_limit_is_unchanged() {
+    let name = "Lookpoint Tracker 1";
+    assert_eq!(name.len(), MAX_LENGTH);
+
+    assert_eq!(truncate_on_char_boundary(name, MAX_LENGTH), name);
+}
+
+#[test]
+fn ascii_name_over_the_limit_is_cut_at_the_limit() {
+    assert_eq!(
+        truncate_on_char_boundary("Lookpoint Tracker 12", MAX_LENGTH),
+        "Lookpoint Tracker 1"
+    );
+}
+
+#[test]
+fn emoji_straddling_the_limit_is_dropped() {
+    // 16 bytes, then a 4 byte emoji spanning bytes 16 to 19.
+    let name = "Lookpoint Track 🛰";
+    assert_eq!(name.len(), 20);
+
+    let truncated = truncate_on_char_boundary(name, MAX_LENGTH);
+    assert_eq!(truncated, "Lookpoint Track ");
+    assert_eq!(truncated.len(), 16);
+}
+
+#[test]
+fn emoji_ending_on_the_limit_is_kept() {
+    // 15 bytes, then a 4 byte emoji ending on the limit.
+    let name = "Lookpoint Track🛰";
+    assert_eq!(name.len(), MAX_LENGTH);
+
+    assert_eq!(truncate_on_char_boundary(name, MAX_LENGTH), name);
+}
+
+#[test]
+fn multibyte_name_just_over_the_limit_loses_its_last_character() {
+    // Ten 2 byte characters, one byte over the limit.
+    let name = "éééééééééé";
+    assert_eq!(name.len(), MAX_LENGTH + 1);
+
+    let truncated = truncate_on_char_boundary(name, MAX_LENGTH);
+    assert_eq!(truncated, "ééééééééé");
+    assert_eq!(truncated.len(), MAX_LENGTH - 1);
+}
+
+#[test]
+fn three_byte_characters_are_never_split() {
+    // Seven 3 byte characters, 21 bytes.
+    let name = "ルックポイント";
+
+    for max_length in 0..=name.len() {
+        let truncated = truncate_on_char_boundary(name, max_length);
+        assert!(truncated.len() <= max_length);
+        assert_eq!(truncated.len(), max_length / 3 * 3);
+    }
+}
+
+#[test]
+fn nothing_fits_in_no_room() {
+    assert_eq!(truncate_on_char_boundary("Lookpoint", 0), "");
+}
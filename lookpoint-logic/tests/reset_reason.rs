@@ -0,0 +1,53 @@
+// SPDX-FileCopyrightText: 2025 Derek Sauer
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use lookpoint_logic::reset_reason::ResetReason;
+
+#[test]
+fn no_reason_is_a_power_on_reset() {
+    assert_eq!(ResetReason::from_resetreas(0), ResetReason::PowerOn);
+}
+
+#[test]
+fn each_reason_is_decoded() {
+    let reasons = [
+        (1 << 0, ResetReason::ResetPin),
+        (1 << 1, ResetReason::Watchdog),
+        (1 << 2, ResetReason::SoftReset),
+        (1 << 3, ResetReason::Lockup),
+        (1 << 16, ResetReason::WakeFromSystemOff),
+        (1 << 17, ResetReason::WakeFromSystemOff),
+        (1 << 18, ResetReason::Debugger),
+        (1 << 19, ResetReason::WakeFromSystemOff),
+        (1 << 20, ResetReason::WakeFromSystemOff),
+    ];
+
+    for (resetreas, reason) in reasons {
+        assert_eq!(
+            ResetReason::from_resetreas(resetreas),
+            reason,
+            "{resetreas:#x}"
+        );
+    }
+}
+
+#[test]
+fn accumulated_reasons_report_the_first_by_precedence() {
+    // A watchdog reset while a debugger was attached.
+    assert_eq!(
+        ResetReason::from_resetreas(1 << 1 | 1 << 18),
+        ResetReason::Watchdog
+    );
+
+    // The reset pin pressed after a soft reset went uncleared.
+    assert_eq!(
+        ResetReason::from_resetreas(1 << 0 | 1 << 2),
+        ResetReason::ResetPin
+    );
+}
+
+#[test]
+fn reserved_bits_are_ignored() {
+    assert_eq!(ResetReason::from_resetreas(1 << 8), ResetReason::PowerOn);
+}
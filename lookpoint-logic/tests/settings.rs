@@ -0,0 +1,309 @@
+// SPDX-FileCopyrightText: 2025 Derek Sauer
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use lookpoint_logic::settings::{
+    self, Key, MAX_VALUE_LENGTH, RECORD_SLOTS, Settings, SettingsFull,
+};
+use lookpoint_logic::storage::{MAX_RECORD_LENGTH, crc32};
+
+const DEVICE_NAME: Key<heapless::String<MAX_VALUE_LENGTH>> = Key::new(1);
+const TX_POWER: Key<i8> = Key::new(2);
+const BOOT_COUNT: Key<u32> = Key::new(4);
+const TEMPERATURE_OFFSET: Key<i16> = Key::new(5);
+
+/// Flash holding a page per record slot, as the settings pages do.
+struct MockFlash {
+    pages: [[u8; MAX_RECORD_LENGTH]; RECORD_SLOTS],
+}
+
+impl MockFlash {
+    /// Create erased flash.
+    fn new() -> Self {
+        Self {
+            pages: [[0xff; MAX_RECORD_LENGTH]; RECORD_SLOTS],
+        }
+    }
+
+    /// Erase the page of `slot` and write `record` to it.
+    fn write(&mut self, slot: usize, record: &[u8]) {
+        self.tear(slot, record, record.len());
+    }
+
+    /// Erase the page of `slot` and write only the first `written` bytes of
+    /// `record`, as when a brownout cuts the write short.
+    fn tear(&mut self, slot: usize, record: &[u8], written: usize) {
+        self.pages[slot] = [0xff; MAX_RECORD_LENGTH];
+        self.pages[slot][..written].copy_from_slice(&record[..written]);
+    }
+
+    /// Load the settings, as at boot.
+    fn load(&self) -> Settings {
+        Settings::from_records(&self.pages)
+    }
+
+    /// Set the setting `key` to `value` and write the record to flash, as
+    /// the settings module does.
+    fn set<T: settings::Value>(
+        &mut self,
+        settings: &mut Settings,
+        key: Key<T>,
+        value: &T,
+    ) -> Result<(), SettingsFull> {
+        let (slot, record) = settings.set(key, value)?;
+        self.write(slot, &record);
+        Ok(())
+    }
+}
+
+/// A device name of `name`.
+fn name(name: &str) -> heapless::String<MAX_VALUE_LENGTH> {
+    heapless::String::try_from(name).unwrap()
+}
+
+/// Encode a record numbered `sequence` holding only the boot count `count`,
+/// independently of the crate's encoder.
+fn record(sequence: u32, count: u32) -> Vec<u8> {
+    let mut record = b"LPST".to_vec();
+    record.extend_from_slice(&sequence.to_le_bytes());
+    record.extend_from_slice(&[6, 4, 4]);
+    record.extend_from_slice(&count.to_le_bytes());
+    record.extend_from_slice(&crc32(&record).to_le_bytes());
+    record
+}
+
+#[test]
+fn erased_flash_holds_no_settings() {
+    let settings = MockFlash::new().load();
+
+    assert!(settings.is_empty());
+    assert_eq!(settings.sequence(), 0);
+    assert_eq!(settings.get(BOOT_COUNT), None);
+}
+
+#[test]
+fn records_are_stored_in_the_documented_format() {
+    let mut settings = Settings::new();
+    let (slot, encoded) = settings.set(BOOT_COUNT, &7).unwrap();
+
+    assert_eq!(slot, 1);
+    assert_eq!(encoded.as_slice(), record(1, 7));
+}
+
+#[test]
+fn newest_record_is_loaded() {
+    let mut flash = MockFlash::new();
+    flash.write(1, &record(1, 10));
+    flash.write(0, &record(2, 11));
+
+    let settings = flash.load();
+    assert_eq!(settings.sequence(), 2);
+    assert_eq!(settings.get(BOOT_COUNT), Some(11));
+}
+
+#[test]
+fn torn_write_reverts_to_the_previous_record() {
+    let mut flash = MockFlash::new();
+    flash.write(1, &record(1, 10));
+
+    // Every prefix of the next record, from none to all but its last byte,
+    // is skipped in favour of the intact one.
+    let next = record(2, 11);
+    for written in 0..next.len() {
+        flash.tear(0, &next, written);
+
+        let settings = flash.load();
+        assert_eq!(settings.sequence(), 1, "{written} bytes written");
+        assert_eq!(
+            settings.get(BOOT_COUNT),
+            Some(10),
+            "{written} bytes written"
+        );
+    }
+
+    flash.write(0, &next);
+    assert_eq!(flash.load().get(BOOT_COUNT), Some(11));
+}
+
+#[test]
+fn torn_records_are_reported_unlike_erased_flash() {
+    let mut flash = MockFlash::new();
+    assert!(!settings::is_torn(&flash.pages[0]));
+
+    let next = record(2, 11);
+    flash.tear(0, &next, next.len() - 1);
+    assert!(settings::is_torn(&flash.pages[0]));
+
+    flash.write(0, &next);
+    assert!(!settings::is_torn(&flash.pages[0]));
+}
+
+#[test]
+fn corrupt_record_reverts_to_the_previous_record() {
+    let mut flash = MockFlash::new();
+    flash.write(1, &record(1, 10));
+    flash.write(0, &record(2, 11));
+
+    // A bit flipped in the stored value.
+    flash.pages[0][11] ^= 0x01;
+
+    assert_eq!(flash.load().get(BOOT_COUNT), Some(10));
+}
+
+#[test]
+fn torn_first_record_leaves_the_settings_unset() {
+    let mut flash = MockFlash::new();
+    let first = record(1, 1);
+    flash.tear(1, &first, first.len() / 2);
+
+    let settings = flash.load();
+    assert!(settings.is_empty());
+    assert_eq!(settings.get(BOOT_COUNT), None);
+}
+
+#[test]
+fn sequence_numbers_wrap_around() {
+    let mut flash = MockFlash::new();
+    flash.write(1, &record(u32::MAX, 10));
+    flash.write(0, &record(0, 11));
+
+    // Record 0 follows record u32::MAX.
+    let settings = flash.load();
+    assert_eq!(settings.sequence(), 0);
+    assert_eq!(settings.get(BOOT_COUNT), Some(11));
+
+    // Either way round.
+    let mut flash = MockFlash::new();
+    flash.write(0, &record(u32::MAX - 1, 10));
+    flash.write(1, &record(u32::MAX, 11));
+    assert_eq!(flash.load().get(BOOT_COUNT), Some(11));
+}
+
+#[test]
+fn writes_continue_across_the_wrap() {
+    let mut flash = MockFlash::new();
+    flash.write(1, &record(u32::MAX, 10));
+
+    let mut settings = flash.load();
+    let (slot, encoded) = settings.set(BOOT_COUNT, &11).unwrap();
+
+    // Record 0 goes to the other slot, and is the one loaded next.
+    assert_eq!(slot, 0);
+    flash.write(slot, &encoded);
+
+    let settings = flash.load();
+    assert_eq!(settings.sequence(), 0);
+    assert_eq!(settings.get(BOOT_COUNT), Some(11));
+}
+
+#[test]
+fn set_values_are_read_back() {
+    let mut settings = Settings::new();
+    settings.set(DEVICE_NAME, &name("Lookpoint")).unwrap();
+    settings.set(TX_POWER, &-8).unwrap();
+    settings.set(BOOT_COUNT, &123_456).unwrap();
+    settings.set(TEMPERATURE_OFFSET, &-250).unwrap();
+
+    assert_eq!(settings.get(DEVICE_NAME), Some(name("Lookpoint")));
+    assert_eq!(settings.get(TX_POWER), Some(-8));
+    assert_eq!(settings.get(BOOT_COUNT), Some(123_456));
+    assert_eq!(settings.get(TEMPERATURE_OFFSET), Some(-250));
+}
+
+#[test]
+fn unset_settings_read_as_none() {
+    let mut settings = Settings::new();
+    settings.set(BOOT_COUNT, &1).unwrap();
+
+    assert_eq!(settings.get(DEVICE_NAME), None);
+    assert_eq!(settings.get(TX_POWER), None);
+}
+
+#[test]
+fn setting_again_replaces_the_value() {
+    let mut settings = Settings::new();
+    settings.set(DEVICE_NAME, &name("Lookpoint")).unwrap();
+    settings.set(BOOT_COUNT, &1).unwrap();
+    settings.set(DEVICE_NAME, &name("Tracker")).unwrap();
+
+    assert_eq!(settings.get(DEVICE_NAME), Some(name("Tracker")));
+    assert_eq!(settings.get(BOOT_COUNT), Some(1));
+
+    // The old value's room is reclaimed: key, length and "Tracker", plus the
+    // boot count's key, length and value.
+    assert_eq!(settings.len(), 2 + 7 + 2 + 4);
+}
+
+#[test]
+fn set_values_survive_a_reload_from_flash() {
+    let mut flash = MockFlash::new();
+    let mut settings = flash.load();
+    flash
+        .set(&mut settings, DEVICE_NAME, &name("Lookpoint"))
+        .unwrap();
+    flash.set(&mut settings, TX_POWER, &4).unwrap();
+    flash.set(&mut settings, BOOT_COUNT, &3).unwrap();
+
+    let loaded = flash.load();
+    assert_eq!(loaded.sequence(), settings.sequence());
+    assert_eq!(loaded.get(DEVICE_NAME), Some(name("Lookpoint")));
+    assert_eq!(loaded.get(TX_POWER), Some(4));
+    assert_eq!(loaded.get(BOOT_COUNT), Some(3));
+}
+
+#[test]
+fn records_alternate_between_slots() {
+    let mut settings = Settings::new();
+    let slots: Vec<usize> = (0..4)
+        .map(|count| settings.set(BOOT_COUNT, &count).unwrap().0)
+        .collect();
+
+    assert_eq!(slots, [1, 0, 1, 0]);
+    assert_eq!(settings.sequence(), 4);
+}
+
+#[test]
+fn settings_that_no_longer_fit_are_rejected_unchanged() {
+    let mut flash = MockFlash::new();
+    let mut settings = flash.load();
+
+    // Fill the record with settings of 2 + 32 bytes each, until the next one
+    // no longer fits.
+    let long = name(&"x".repeat(MAX_VALUE_LENGTH));
+    let mut id = 100;
+    let full = loop {
+        match flash.set(&mut settings, Key::new(id), &long) {
+            Ok(()) => id += 1,
+            Err(error) => break error,
+        }
+    };
+    assert_eq!(full, SettingsFull);
+
+    // The failed set neither changed the settings nor wrote a record.
+    let sequence = settings.sequence();
+    assert_eq!(
+        settings.get(Key::<heapless::String<MAX_VALUE_LENGTH>>::new(id)),
+        None
+    );
+    assert_eq!(flash.load().sequence(), sequence);
+
+    // Replacing an existing setting with one of the same length still fits.
+    flash
+        .set(
+            &mut settings,
+            Key::new(100),
+            &name(&"y".repeat(MAX_VALUE_LENGTH)),
+        )
+        .unwrap();
+    assert_eq!(settings.sequence(), sequence.wrapping_add(1));
+}
+
+#[test]
+fn values_of_another_type_read_as_none() {
+    let mut settings = Settings::new();
+    settings.set(BOOT_COUNT, &1).unwrap();
+
+    // The same key read as a type of another length is invalid.
+    assert_eq!(settings.get(Key::<i16>::new(4)), None);
+    assert_eq!(settings.get(Key::<i8>::new(4)), None);
+}
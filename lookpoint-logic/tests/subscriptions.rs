@@ -0,0 +1,72 @@
+// SPDX-FileCopyrightText: 2025 Derek Sauer
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use lookpoint_logic::subscriptions::{Notifying, Subscriptions};
+
+/// Notifying characteristics and the handles of their CCCDs.
+const CHARACTERISTICS: [(Notifying, Option<u16>); 3] = [
+    (Notifying::BatteryLevel, Some(0x0010)),
+    (Notifying::Temperature, Some(0x0014)),
+    (Notifying::Rssi, None),
+];
+
+#[test]
+fn new_table_has_no_subscriptions() {
+    let subscriptions = Subscriptions::new();
+
+    for (characteristic, _) in CHARACTERISTICS {
+        assert!(!subscriptions.is_subscribed(characteristic));
+    }
+}
+
+#[test]
+fn restored_table_holds_the_enabled_notifications() {
+    let subscriptions =
+        Subscriptions::from_cccds(&CHARACTERISTICS, [(0x0010, 0x0001), (0x0014, 0)]);
+
+    assert!(subscriptions.is_subscribed(Notifying::BatteryLevel));
+    assert!(!subscriptions.is_subscribed(Notifying::Temperature));
+    assert!(!subscriptions.is_subscribed(Notifying::Rssi));
+}
+
+#[test]
+fn enabled_indications_alone_do_not_subscribe() {
+    let subscriptions = Subscriptions::from_cccds(&CHARACTERISTICS, [(0x0010, 0x0002)]);
+
+    assert!(!subscriptions.is_subscribed(Notifying::BatteryLevel));
+}
+
+#[test]
+fn cccds_of_other_characteristics_are_ignored() {
+    let subscriptions =
+        Subscriptions::from_cccds(&CHARACTERISTICS, [(0x0020, 0x0001), (0, 0x0001)]);
+
+    for (characteristic, _) in CHARACTERISTICS {
+        assert!(!subscriptions.is_subscribed(characteristic));
+    }
+}
+
+#[test]
+fn restored_subscriptions_follow_later_cccd_writes() {
+    let subscriptions = Subscriptions::from_cccds(&CHARACTERISTICS, [(0x0010, 0x0001)]);
+
+    subscriptions.set(Notifying::BatteryLevel, false);
+    subscriptions.set(Notifying::Temperature, true);
+
+    assert!(!subscriptions.is_subscribed(Notifying::BatteryLevel));
+    assert!(subscriptions.is_subscribed(Notifying::Temperature));
+}
+
+#[test]
+fn subscriptions_survive_a_round_trip_through_their_bits() {
+    let subscriptions = Subscriptions::new();
+    subscriptions.set(Notifying::Link, true);
+    subscriptions.set(Notifying::ConsoleOutput, true);
+
+    let restored = Subscriptions::from_bits(subscriptions.bits());
+
+    assert!(restored.is_subscribed(Notifying::Link));
+    assert!(restored.is_subscribed(Notifying::ConsoleOutput));
+    assert!(!restored.is_subscribed(Notifying::BatteryLevel));
+}
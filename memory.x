@@ -4,8 +4,12 @@
  * SPDX-License-Identifier: GPL-3.0-or-later
  */
 
+/*
+ * The last 32K of flash (8 pages) are reserved for persistent storage, see
+ * `src/storage.rs`.
+ */
 MEMORY
 {
-  FLASH : ORIGIN = 0x00000000, LENGTH = 1024K
+  FLASH : ORIGIN = 0x00000000, LENGTH = 992K
   RAM : ORIGIN = 0x20000000, LENGTH = 256K
 }
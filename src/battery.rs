@@ -0,0 +1,104 @@
+// SPDX-FileCopyrightText: 2025 Derek Sauer
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Board independent battery state, fed by the board measuring the battery
+//! every [`CHECK_INTERVAL`].
+//!
+//! The level is estimated from the latest voltage with the configured
+//! [`BatteryChemistry`] each time it is read, so changing the chemistry takes
+//! effect immediately. A regulated supply has no level.
+//!
+//! Each measured level is stored in [`BATTERY_LEVEL`], notifying subscribed
+//! clients as soon as it changes. A level falling below the low threshold
+//! sets [`StatusFlag::BatteryLow`] in the advertised status.
+
+use core::cell::RefCell;
+use core::sync::atomic::{AtomicU16, Ordering};
+
+use embassy_sync::blocking_mutex::Mutex;
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_time::Duration;
+pub use lookpoint_logic::battery::BatteryChemistry;
+use lookpoint_logic::battery::LowBattery;
+
+use crate::ble::services::battery::BATTERY_LEVEL;
+use crate::ble::status::{self, StatusFlag};
+use crate::config;
+
+/// How often the battery is measured. A cell discharges over weeks, and each
+/// measurement is a single SAADC conversion.
+pub const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Latest measured battery voltage, in millivolts. Zero until measured, a
+/// battery powering the device never reads as such.
+static MILLIVOLTS: AtomicU16 = AtomicU16::new(0);
+
+/// Whether the battery is reported low.
+static LOW_BATTERY: Mutex<CriticalSectionRawMutex, RefCell<LowBatteryAlert>> =
+    Mutex::new(RefCell::new(LowBatteryAlert::new()));
+
+/// Returns the battery level, in percent, estimated from the latest measured
+/// voltage, or `None` if the battery was not measured yet or the device runs
+/// from a regulated supply.
+pub fn level() -> Option<u8> {
+    config::get()
+        .battery_chemistry
+        .percent_from_millivolts(millivolts()?)
+}
+
+/// Returns the latest measured battery voltage, in millivolts, or `None` if
+/// the battery was not measured yet.
+pub fn millivolts() -> Option<u16> {
+    let millivolts = MILLIVOLTS.load(Ordering::Relaxed);
+    (millivolts != 0).then_some(millivolts)
+}
+
+/// Record a newly measured battery voltage, in millivolts, reported in beacon
+/// telemetry and to clients of the Battery service.
+pub fn record_millivolts(millivolts: u16) {
+    MILLIVOLTS.store(millivolts, Ordering::Relaxed);
+
+    let level = level();
+    defmt::debug!("[battery] battery: {} mV, {}%", millivolts, level);
+
+    // A regulated supply is never reported low.
+    let percent = level.unwrap_or(100);
+    LOW_BATTERY.lock(|alert| alert.borrow_mut().update(percent));
+
+    if let Some(level) = level {
+        BATTERY_LEVEL.set(level);
+    }
+}
+
+/// Tracks whether the battery is low, with hysteresis, and reports it in the
+/// advertised status.
+struct LowBatteryAlert {
+    low: LowBattery,
+}
+
+impl LowBatteryAlert {
+    /// Create a new [`LowBatteryAlert`], initially not low.
+    const fn new() -> Self {
+        Self {
+            low: LowBattery::new(),
+        }
+    }
+
+    /// Feed a new battery level, in percent.
+    ///
+    /// Updates the battery low flag of the advertised status when a threshold
+    /// is crossed.
+    fn update(&mut self, percent: u8) {
+        let Some(low) = self.low.update(percent) else {
+            return;
+        };
+        status::set_flag(StatusFlag::BatteryLow, low);
+
+        if low {
+            defmt::warn!("[battery] battery low: {}%", percent);
+        } else {
+            defmt::info!("[battery] battery recovered: {}%", percent);
+        }
+    }
+}
@@ -2,24 +2,82 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+use bt_hci::uuid::BluetoothUuid16;
 use trouble_host::prelude::*;
 
+pub use self::packet_pool::BlePacketPool;
+use crate::liveness::{self, Task};
+
 pub mod advertise;
+pub mod allow_list;
+pub mod beacon;
+#[cfg(not(feature = "beacon_only"))]
+pub mod bonds;
+pub mod connection_params;
+pub mod connections;
+pub mod device_name;
+#[cfg(not(feature = "beacon_only"))]
 pub mod gatt_server;
+pub mod packet_pool;
+#[cfg(not(feature = "beacon_only"))]
+pub mod permissions;
+pub mod privacy;
+// Beacon only builds only use the device information.
+#[cfg_attr(feature = "beacon_only", allow(dead_code))]
 pub mod services;
+pub mod status;
+#[cfg(not(feature = "beacon_only"))]
+pub mod subscriptions;
+
+/// GAP appearance of the device, exposed by the GAP service and advertised so
+/// scanners show a matching icon.
+pub const APPEARANCE: BluetoothUuid16 = appearance::light_fixtures::LIGHT_CONTROLLER;
 
-/// This device can service only one connection.
-const MAX_CONNECTIONS: usize = 1;
+/// Connections served at once. Advertising continues while a connection is
+/// served, so a second client can connect.
+pub const MAX_CONNECTIONS: usize = 2;
 
 /// This device will advertise the same data each advertising window, so
-/// multiple advertising sets are not needed.
+/// multiple advertising sets are not needed. A [`advertise::BeaconRotation`]
+/// reuses the same set, broadcasting the beacon and the device's own
+/// advertisement one after another.
+///
+/// Long range advertising on the LE Coded PHY replaces the legacy
+/// advertisement rather than running alongside it, so it reuses the same set
+/// too. Advertising on both PHYs at once would need a second set, costing the
+/// host a few bytes of state and the controller an advertising set's buffers,
+/// which must then be added to the Softdevice's memory.
 const MAX_ADVERTISING_SETS: usize = 1;
 
 /// Two channels will be required for L2CAP transfers (Signal + ATT).
 const MAX_L2CAP_CHANNELS: usize = 2;
 
+/// Largest ATT MTU negotiated with a client. 247 bytes fills a single 251 byte
+/// link layer data PDU once the data length is extended.
+pub const ATT_MTU: usize = 247;
+
+/// Size of each packet of the [`BlePacketPool`]: an ATT PDU of [`ATT_MTU`]
+/// bytes behind its 4 byte L2CAP header.
+const PACKET_MTU: usize = ATT_MTU + 4;
+
+/// Packets each L2CAP channel may hold at once, across both directions. Raise
+/// it if the packet pool's high water mark reaches its capacity.
+const PACKETS_PER_CHANNEL: usize = 8;
+
+/// Number of packets in the [`BlePacketPool`], enough for every L2CAP channel
+/// of every connection.
+const PACKET_COUNT: usize = MAX_CONNECTIONS * MAX_L2CAP_CHANNELS * PACKETS_PER_CHANNEL;
+
+/// RAM the [`BlePacketPool`]'s buffers may take.
+const PACKET_POOL_RAM_BUDGET: usize = 8 * 1024;
+
+const _: () = assert!(
+    PACKET_MTU * PACKET_COUNT <= PACKET_POOL_RAM_BUDGET,
+    "the packet pool exceeds its RAM budget"
+);
+
 pub type BleResources =
-    HostResources<DefaultPacketPool, MAX_CONNECTIONS, MAX_L2CAP_CHANNELS, MAX_ADVERTISING_SETS>;
+    HostResources<BlePacketPool, MAX_CONNECTIONS, MAX_L2CAP_CHANNELS, MAX_ADVERTISING_SETS>;
 
 /// Background task that pumps the BLE stack's event loop.
 ///
@@ -31,7 +89,9 @@ pub type BleResources =
 /// Any errors that occur in the BLE event loop are likely unrecoverable and
 /// will result in a panic.
 pub async fn ble_background_task<C: Controller, P: PacketPool>(runner: &mut Runner<'_, C, P>) {
-    if let Err(error) = runner.run().await {
+    let result = liveness::monitor(Task::BleRunner, runner.run()).await;
+
+    if let Err(error) = result {
         match error {
             BleHostError::Controller(_) => {
                 defmt::panic!("[ble_task] error occured in the BLE controller.")
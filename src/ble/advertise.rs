@@ -2,56 +2,1420 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+use core::cell::RefCell;
+#[cfg(feature = "beacon_only")]
+use core::convert::Infallible;
+use core::sync::atomic::{AtomicBool, AtomicI8, Ordering};
+
+use bt_hci::cmd::le::{
+    LeAddDeviceToFilterAcceptList, LeAddDeviceToResolvingList, LeClearAdvSets,
+    LeClearFilterAcceptList, LeClearResolvingList, LeEncrypt, LeReadAdvertisingChannelTxPower,
+    LeReadNumberOfSupportedAdvSets, LeSetAddrResolutionEnable, LeSetAdvSetRandomAddr,
+    LeSetExtAdvData, LeSetExtAdvEnable, LeSetExtAdvParams, LeSetExtScanResponseData,
+    LeSetPrivacyMode, LeSetRandomAddr,
+};
+use bt_hci::controller::ControllerCmdSync;
+use bt_hci::param::AdvChannelMap;
+use embassy_futures::select::{Either3, select, select3};
+use embassy_sync::blocking_mutex::Mutex;
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::signal::Signal;
+use embassy_time::{Duration, Instant, Timer, with_timeout};
+pub use lookpoint_logic::advertising::{
+    AdvError, AdvPayload, AdvertisementBuilder, LEGACY_PAYLOAD_LENGTH,
+};
+use rand_chacha::ChaChaRng;
+use rand_core::RngCore;
 use trouble_host::prelude::*;
 
-use super::services::device_information::DeviceInformation;
+use super::beacon::{
+    self, BeaconError, BeaconIdentity, EddystoneUidIdentity, EddystoneUrlFrame, IBeaconIdentity,
+    Telemetry,
+};
+use super::device_name::DeviceName;
+#[cfg(not(feature = "beacon_only"))]
+use super::gatt_server::{AcceptedConnections, GattServer};
+use super::{APPEARANCE, BlePacketPool, allow_list, connections, privacy, status};
+use crate::liveness::{self, Task};
+use crate::{battery, config, indicator, thermal};
+
+/// Advertising interval used while the device is thermally throttled.
+const THROTTLED_INTERVAL: Duration = Duration::from_millis(1000);
+
+/// Transmit power used while the device is thermally throttled.
+const THROTTLED_TX_POWER: TxPower = TxPower::Minus8dBm;
+
+/// [`THROTTLED_TX_POWER`] in dBm, advertised in the TX Power Level.
+const THROTTLED_TX_POWER_LEVEL: i8 = -8;
+
+/// Transmit power advertised in the TX Power Level if the controller does not
+/// report its own, in dBm: the nRF52840's default.
+const DEFAULT_TX_POWER_LEVEL: i8 = 0;
+
+/// Advertising interval of Eddystone beacons. Each frame is broadcast for one
+/// interval.
+const EDDYSTONE_INTERVAL: Duration = Duration::from_millis(1000);
+
+/// Beacon frames broadcast between Eddystone-TLM frames. Scanners locate the
+/// beacon from its identity, and health changes slowly, so telemetry takes a
+/// small share of the airtime.
+const EDDYSTONE_FRAMES_PER_TELEMETRY: u32 = 10;
+
+/// Received signal strength of Eddystone-URL frames at 0 m, in dBm. Typical of
+/// the default 0 dBm transmit power: the strength measured at 1 m plus the
+/// 41 dB lost over that meter.
+const EDDYSTONE_URL_TX_POWER: i8 = -18;
+
+/// Shortest advertising interval allowed by the Bluetooth Core
+/// Specification.
+const MIN_ADVERTISING_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Longest legacy advertising interval allowed by the Bluetooth Core
+/// Specification.
+const MAX_ADVERTISING_INTERVAL: Duration = Duration::from_millis(10_240);
+
+/// Advertising intervals are multiples of 0.625 ms.
+const ADVERTISING_INTERVAL_UNIT_MICROS: u64 = 625;
+
+/// Longest random delay added to the advertising interval when
+/// [`AdvertisingConfig::interval_jitter`] is enabled, in 0.625 ms units: 10 ms.
+const MAX_INTERVAL_JITTER_UNITS: u32 = 16;
+
+/// Random number generator drawing the advertising interval jitter, seeded
+/// from the controller's random number generator when the BLE stack is
+/// initialized.
+static INTERVAL_JITTER_RNG: Mutex<CriticalSectionRawMutex, RefCell<Option<ChaChaRng>>> =
+    Mutex::new(RefCell::new(None));
+
+/// Controller commands used by extended advertising, needed to advertise on
+/// the LE Coded PHY.
+pub trait ExtendedAdvertisingController:
+    Controller
+    + for<'t> ControllerCmdSync<LeSetExtAdvData<'t>>
+    + ControllerCmdSync<LeClearAdvSets>
+    + ControllerCmdSync<LeSetExtAdvParams>
+    + ControllerCmdSync<LeSetAdvSetRandomAddr>
+    + ControllerCmdSync<LeReadNumberOfSupportedAdvSets>
+    + for<'t> ControllerCmdSync<LeSetExtAdvEnable<'t>>
+    + for<'t> ControllerCmdSync<LeSetExtScanResponseData<'t>>
+{
+}
+
+impl<C> ExtendedAdvertisingController for C where
+    C: Controller
+        + for<'t> ControllerCmdSync<LeSetExtAdvData<'t>>
+        + ControllerCmdSync<LeClearAdvSets>
+        + ControllerCmdSync<LeSetExtAdvParams>
+        + ControllerCmdSync<LeSetAdvSetRandomAddr>
+        + ControllerCmdSync<LeReadNumberOfSupportedAdvSets>
+        + for<'t> ControllerCmdSync<LeSetExtAdvEnable<'t>>
+        + for<'t> ControllerCmdSync<LeSetExtScanResponseData<'t>>
+{
+}
+
+/// Commands controlling whether [`advertise_task`] advertises.
+#[derive(Clone, Copy, PartialEq, defmt::Format)]
+pub enum AdvertisingCommand {
+    /// Start advertising, with the limits of the [`AdvertisingConfig`] reset.
+    /// Ignored if already advertising.
+    Start,
+
+    /// Stop advertising. An established connection continues to be served.
+    Stop,
+}
+
+/// Signaled by other tasks, such as a button handler or the thermal manager,
+/// to start or stop advertising.
+static ADVERTISING_CONTROL: Signal<CriticalSectionRawMutex, AdvertisingCommand> = Signal::new();
+
+/// Signaled when [`advertise_task`] stops advertising, because a limit of the
+/// [`AdvertisingConfig`] was reached or [`stop_advertising`] was called. Lets
+/// the caller put the device in a low power state until it calls
+/// [`start_advertising`], for example when a button wakes it.
+pub static ADVERTISING_STOPPED: Signal<CriticalSectionRawMutex, ()> = Signal::new();
+
+/// Whether the device is currently advertising.
+static ADVERTISING: AtomicBool = AtomicBool::new(false);
+
+/// Transmit power of advertisements, in dBm, as last read from the controller.
+static TX_POWER_LEVEL: AtomicI8 = AtomicI8::new(DEFAULT_TX_POWER_LEVEL);
+
+/// Ask [`advertise_task`] to start advertising, for example after it was
+/// stopped by the limits of the [`AdvertisingConfig`].
+pub fn start_advertising() {
+    ADVERTISING_CONTROL.signal(AdvertisingCommand::Start);
+}
+
+/// Ask [`advertise_task`] to stop advertising until [`start_advertising`] is
+/// called.
+pub fn stop_advertising() {
+    ADVERTISING_CONTROL.signal(AdvertisingCommand::Stop);
+}
+
+/// Returns `true` if the device is currently advertising.
+pub fn is_advertising() -> bool {
+    ADVERTISING.load(Ordering::Relaxed)
+}
+
+/// Returns the transmit power the device currently advertises at, in dBm: the
+/// level advertised in the TX Power Level of [`advertise`], lowered while the
+/// device is thermally throttled.
+pub fn tx_power_level() -> i8 {
+    if thermal::is_throttled() {
+        THROTTLED_TX_POWER_LEVEL
+    } else {
+        TX_POWER_LEVEL.load(Ordering::Relaxed)
+    }
+}
+
+/// Signaled to reset the advertising interval backoff to its initial, fast,
+/// interval. For example when user activity suggests a central is nearby.
+pub static RESET_ADVERTISING_BACKOFF: Signal<CriticalSectionRawMutex, ()> = Signal::new();
+
+/// Longest time the advertiser runs before the advertising loop restarts it.
+/// Each iteration of the loop checks in with [`liveness`], and restarting the
+/// advertiser is an event handled by the BLE host's event loop, so both are
+/// shown to make progress at least this often while advertising.
+pub const MAX_ADVERTISING_WINDOW: Duration = Duration::from_secs(60);
+
+/// Tasks that may legitimately make no progress while not advertising.
+const BLE_TASKS: [Task; 2] = [Task::BleRunner, Task::Advertising];
+
+/// Exponential backoff of the advertising interval.
+///
+/// A device advertising for a long time without being connected to is likely
+/// being ignored. Progressively lengthening its advertising interval saves
+/// power, while resetting to the fast interval on a connection or
+/// [`RESET_ADVERTISING_BACKOFF`] lets it connect quickly once approached.
+#[derive(Clone, Copy)]
+pub struct AdvertisingBackoff {
+    /// Advertising interval used when advertising starts or the backoff is
+    /// reset.
+    pub initial_interval: Duration,
+
+    /// The advertising interval is never lengthened past this interval.
+    pub max_interval: Duration,
+
+    /// Time spent advertising at each interval before it is doubled.
+    pub step_duration: Duration,
+}
+
+impl AdvertisingBackoff {
+    /// Returns the interval following `interval`, doubled up to the maximum.
+    fn next_interval(&self, interval: Duration) -> Duration {
+        (interval * 2).min(self.max_interval)
+    }
+}
+
+/// Duty cycled advertising, alternating between a short window of fast
+/// advertising and a longer window of slow advertising, or no advertising at
+/// all, until a central connects.
+///
+/// Centrals scanning during the fast window discover the device quickly, while
+/// the slow window keeps the average current low. A connection or
+/// [`RESET_ADVERTISING_BACKOFF`] restarts the cycle with the fast window.
+#[derive(Clone, Copy)]
+pub struct AdvertisingDutyCycle {
+    /// Time spent advertising at `fast_interval` at the start of each cycle.
+    pub fast_window: Duration,
+
+    /// Advertising interval during the fast window.
+    pub fast_interval: Duration,
+
+    /// Time spent advertising at `slow_interval` after the fast window.
+    pub slow_window: Duration,
+
+    /// Advertising interval during the slow window. `None` pauses advertising
+    /// until the next fast window.
+    pub slow_interval: Option<Duration>,
+}
+
+impl AdvertisingDutyCycle {
+    /// Returns the advertising interval `elapsed` into the cycle, `None` if
+    /// paused, and the time remaining until the next window starts.
+    fn window(&self, elapsed: Duration) -> (Option<Duration>, Duration) {
+        let period = self.fast_window + self.slow_window;
+        let position = Duration::from_ticks(elapsed.as_ticks() % period.as_ticks().max(1));
+
+        if position < self.fast_window {
+            (Some(self.fast_interval), self.fast_window - position)
+        } else {
+            (self.slow_interval, period - position)
+        }
+    }
+}
+
+/// Range of advertising intervals the controller picks from. A longer
+/// interval saves power, at the cost of centrals taking longer to discover and
+/// connect to the device.
+#[derive(Clone, Copy, defmt::Format)]
+pub struct AdvertisingInterval {
+    /// Shortest advertising interval.
+    pub min: Duration,
+
+    /// Longest advertising interval.
+    pub max: Duration,
+}
+
+impl AdvertisingInterval {
+    /// Advertise at exactly `interval`.
+    pub const fn fixed(interval: Duration) -> Self {
+        Self {
+            min: interval,
+            max: interval,
+        }
+    }
+
+    /// Returns the interval delayed by `jitter`.
+    fn jittered(self, jitter: Duration) -> Self {
+        Self {
+            min: self.min + jitter,
+            max: self.max + jitter,
+        }
+    }
+
+    /// Returns the interval clamped to the range allowed by the Bluetooth Core
+    /// Specification, with its minimum no longer than its maximum, and rounded
+    /// down to whole 0.625 ms units. Logs a warning if it had to be clamped.
+    fn validated(self) -> Self {
+        let min = self
+            .min
+            .clamp(MIN_ADVERTISING_INTERVAL, MAX_ADVERTISING_INTERVAL);
+        let max = self
+            .max
+            .clamp(MIN_ADVERTISING_INTERVAL, MAX_ADVERTISING_INTERVAL);
+        if min != self.min || max != self.max {
+            defmt::warn!(
+                "[adv] advertising interval {} to {} ms outside of {} to {} ms, clamped",
+                self.min.as_millis(),
+                self.max.as_millis(),
+                MIN_ADVERTISING_INTERVAL.as_millis(),
+                MAX_ADVERTISING_INTERVAL.as_millis()
+            );
+        }
+
+        if min > max {
+            defmt::warn!(
+                "[adv] minimum advertising interval {} ms above the maximum {} ms, clamped",
+                min.as_millis(),
+                max.as_millis()
+            );
+        }
+
+        // The range's bounds are whole units, rounding down stays within it.
+        let to_units = |interval: Duration| {
+            Duration::from_micros(
+                interval.as_micros() / ADVERTISING_INTERVAL_UNIT_MICROS
+                    * ADVERTISING_INTERVAL_UNIT_MICROS,
+            )
+        };
+
+        Self {
+            min: to_units(min.min(max)),
+            max: to_units(max),
+        }
+    }
+}
+
+/// Set of the three primary advertising channels, 37, 38 and 39, to advertise
+/// on. Combine channels with `|`.
+#[derive(Clone, Copy, PartialEq, defmt::Format)]
+pub struct AdvertisingChannels(u8);
+
+impl AdvertisingChannels {
+    /// Advertise on every channel, as usual.
+    pub const ALL: Self = Self(0b111);
+    /// Advertising channel 37, at 2402 MHz.
+    pub const CHANNEL_37: Self = Self(0b001);
+    /// Advertising channel 38, at 2426 MHz.
+    pub const CHANNEL_38: Self = Self(0b010);
+    /// Advertising channel 39, at 2480 MHz.
+    pub const CHANNEL_39: Self = Self(0b100);
+
+    /// Returns `true` if no channel is enabled.
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Returns `true` if every channel of `other` is enabled.
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Returns the channel map of the advertising parameters.
+    fn channel_map(self) -> AdvChannelMap {
+        AdvChannelMap::default()
+            .enable_channel_37(self.contains(Self::CHANNEL_37))
+            .enable_channel_38(self.contains(Self::CHANNEL_38))
+            .enable_channel_39(self.contains(Self::CHANNEL_39))
+    }
+
+    /// Returns the channels, or every channel if none is enabled. Logs a
+    /// warning if none was.
+    fn validated(self) -> Self {
+        if self.is_empty() {
+            defmt::warn!("[adv] no advertising channel enabled, advertising on every channel");
+            Self::ALL
+        } else {
+            self
+        }
+    }
+}
+
+impl Default for AdvertisingChannels {
+    fn default() -> Self {
+        Self::ALL
+    }
+}
+
+impl core::ops::BitOr for AdvertisingChannels {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+/// Idle behaviour of a unit that nobody has connected to since boot, such as
+/// a freshly flashed unit sitting on a shelf.
+///
+/// The unit advertises normally for `window` after boot. If no central
+/// connects within it, the unit is considered shelved and drops to a slow
+/// advertising interval until a connection is made. Signaling
+/// [`RESET_ADVERTISING_BACKOFF`], for example from a button press, wakes it
+/// and restarts the window. Unlike [`AdvertisingConfig::max_duration`] this
+/// never applies once the unit has been connected to.
+#[derive(Clone, Copy)]
+pub struct ProvisioningTimeout {
+    /// Time spent advertising normally while waiting to be provisioned.
+    pub window: Duration,
+
+    /// Advertising interval used once the window elapsed without a
+    /// connection.
+    pub shelved_interval: Duration,
+}
+
+/// One entry of a [`BeaconRotation`] schedule, setting the PDU type and data
+/// broadcast while it is current.
+#[derive(Clone, Copy, PartialEq, defmt::Format)]
+pub enum RotationEntry {
+    /// The device's own advertisement, connectable (ADV_IND) unless a beacon
+    /// only build. Connections are handed off as without a rotation.
+    Device,
+
+    /// The beacon identity of the device configuration, non-connectable and
+    /// non-scannable (ADV_NONCONN_IND). The device's own advertisement takes
+    /// its place while no identity is provisioned.
+    Beacon,
+}
+
+/// Rotation between the device's own advertisement and its provisioned
+/// beacon identity, so one device serves both a beacon and a connectable
+/// profile over time.
+///
+/// Each entry of `schedule` is broadcast for `cadence` before moving on to the
+/// next one, starting over after the last. Entries are broadcast one after
+/// another from the same advertising set.
+#[derive(Clone, Copy)]
+pub struct BeaconRotation {
+    /// Entries broadcast in turn. An empty schedule is ignored.
+    pub schedule: &'static [RotationEntry],
+
+    /// How long each entry is broadcast.
+    pub cadence: Duration,
+}
+
+/// Limits on how long the device remains discoverable.
+///
+/// Once a limit is reached the device stops advertising, though an established
+/// connection continues to be served, until [`start_advertising`] is called.
+/// This reduces the attack surface of a device that has already been set up.
+#[derive(Clone, Copy)]
+pub struct AdvertisingConfig {
+    /// Stop advertising once this much time has been spent advertising in
+    /// total. `None` advertises indefinitely.
+    pub max_duration: Option<Duration>,
+
+    /// Stop advertising after this many successful connections. `None`
+    /// advertises indefinitely.
+    pub max_connections: Option<u32>,
+
+    /// Stop advertising once this much time has passed without a connection,
+    /// so a device nobody connects to stops draining its battery. Restarts
+    /// whenever a central connects, and does not elapse while a connection is
+    /// active. `None` advertises indefinitely.
+    pub idle_timeout: Option<Duration>,
+
+    /// Advertising interval, unless lengthened by `backoff` or
+    /// `provisioning_timeout`, or replaced by `duty_cycle`. `None` advertises
+    /// at the controller's default interval.
+    pub interval: Option<AdvertisingInterval>,
+
+    /// Alternate between fast and slow advertising until a central connects.
+    /// `None` advertises at `interval` throughout.
+    pub duty_cycle: Option<AdvertisingDutyCycle>,
+
+    /// Lengthen the advertising interval the longer the device advertises
+    /// without being connected to. `None` advertises at `interval`.
+    pub backoff: Option<AdvertisingBackoff>,
+
+    /// Pause advertising while every connection slot is taken, since no
+    /// central could connect, and resume once a connection ends.
+    pub pause_when_full: bool,
+
+    /// Drop to slow advertising if no central connects within a window after
+    /// boot. `None` keeps advertising normally.
+    pub provisioning_timeout: Option<ProvisioningTimeout>,
+
+    /// Only let peers on the [`allow_list`] scan and connect, outside the
+    /// pairing window. Can be changed at runtime with
+    /// [`allow_list::set_enabled`].
+    pub allow_list: bool,
+
+    /// Add a random delay of up to 10 ms to `interval` each time advertising
+    /// starts, so that devices powered up together do not keep advertising in
+    /// lockstep. Has no effect when advertising at the controller's default
+    /// interval.
+    ///
+    /// The delay is drawn once per advertising start, not per advertising
+    /// event: the controller schedules the events at a fixed interval until
+    /// advertising is restarted, which happens at least every
+    /// [`MAX_ADVERTISING_WINDOW`]. Between restarts, only the 0 to 10 ms
+    /// advDelay the controller adds to every advertising event perturbs
+    /// single events around the same interval.
+    pub interval_jitter: bool,
+
+    /// Advertise with extended advertising on the LE Coded PHY, roughly
+    /// quadrupling the range at the cost of airtime, and of centrals without
+    /// coded PHY support no longer seeing the device. Connectable extended
+    /// advertisements have no scan response, so the scan response's content,
+    /// such as the device name, is left out. Ignored by the
+    /// `minimal_controller` build, which only supports the 1M PHY.
+    pub long_range: bool,
+
+    /// Advertise from a resolvable private address, replaced at this
+    /// interval, see [`privacy`]. `None` advertises from the static identity
+    /// address.
+    ///
+    /// The address is set behind the BLE host's back: the host keeps pairing
+    /// from the identity address, and has no way to distribute the IRK to
+    /// peers. Only suitable for devices that are never paired.
+    pub address_rotation: Option<Duration>,
+
+    /// Take turns broadcasting the provisioned beacon identity, see
+    /// [`BeaconRotation`]. The limits above only count the time spent on the
+    /// device's own advertisement. `None` only broadcasts the device's own
+    /// advertisement.
+    pub beacon_rotation: Option<BeaconRotation>,
+
+    /// Primary channels to advertise on, usually
+    /// [`AdvertisingChannels::ALL`]. Restricting advertising to a single
+    /// channel is meant for regulatory testing, and slows down discovery.
+    pub channels: AdvertisingChannels,
+}
+
+/// Reports advertising data that does not fit to the host as
+/// [`Error::InsufficientSpace`].
+fn insufficient_space(_: AdvError) -> Error {
+    Error::InsufficientSpace
+}
+
+/// Seed the random number generator drawing the advertising interval jitter.
+/// Until seeded, no jitter is added.
+pub fn seed_interval_jitter(rng: ChaChaRng) {
+    INTERVAL_JITTER_RNG.lock(|jitter_rng| *jitter_rng.borrow_mut() = Some(rng));
+}
+
+/// Returns a random advertising interval jitter of whole 0.625 ms units, up to
+/// [`MAX_INTERVAL_JITTER_UNITS`].
+fn interval_jitter() -> Duration {
+    let units = INTERVAL_JITTER_RNG.lock(|jitter_rng| {
+        jitter_rng
+            .borrow_mut()
+            .as_mut()
+            .map_or(0, |rng| rng.next_u32() % (MAX_INTERVAL_JITTER_UNITS + 1))
+    });
+
+    Duration::from_micros(u64::from(units) * ADVERTISING_INTERVAL_UNIT_MICROS)
+}
+
+/// A non-connectable and non-scannable advertisement (ADV_NONCONN_IND)
+/// broadcast by a beacon. Scanners read everything from the advertising data,
+/// so no radio time is spent listening for scan requests.
+pub struct BeaconAdvertisement {
+    /// Encoded advertising data.
+    pub adv_data: AdvPayload,
+}
+
+impl BeaconAdvertisement {
+    /// Encode the content accumulated by `builder` into a beacon
+    /// advertisement. There is no scan response, so all of the content must
+    /// fit in the advertising data.
+    pub fn new(builder: &AdvertisementBuilder<'_>) -> Result<Self, AdvError> {
+        Ok(Self {
+            adv_data: builder.build_beacon()?,
+        })
+    }
+
+    /// Build the [`Advertisement`] broadcasting this advertisement.
+    fn advertisement(&self) -> Advertisement<'_> {
+        Advertisement::NonconnectableNonscannableUndirected {
+            adv_data: &self.adv_data,
+        }
+    }
+}
 
 /// Begin advertising and wait for connections.
-pub async fn advertise<'values, 'server, C: Controller>(
-    device_name: &'values str,
-    peripheral_role: &mut Peripheral<'values, C, DefaultPacketPool>,
-    gatt_server: &'server super::gatt_server::GattServer<'values>,
-) -> Result<GattConnection<'values, 'server, DefaultPacketPool>, BleHostError<C::Error>> {
-    let mut advertise_data = [0; 31];
-
-    AdStructure::encode_slice(
-        &[
-            AdStructure::Flags(LE_GENERAL_DISCOVERABLE | BR_EDR_NOT_SUPPORTED),
-            AdStructure::ServiceUuids16(&[DeviceInformation::BLE_UUID16.to_le_bytes()]),
-            AdStructure::CompleteLocalName(device_name.as_bytes()),
-        ],
-        &mut advertise_data[..],
-    )?;
-
-    let advertiser = peripheral_role
-        .advertise(
-            &AdvertisementParameters::default(),
-            Advertisement::ConnectableScannableUndirected {
-                adv_data:  &advertise_data[..],
-                scan_data: &[],
-            },
+///
+/// Advertises within `interval`, or at the controller's default interval if
+/// `None`, accepting scan and connection requests as `filter_policy` allows.
+/// `tx_power_level` is the transmit power the controller advertises at, in
+/// dBm, letting scanners estimate their distance to the device. Advertises on
+/// the LE Coded PHY if `long_range`, see [`AdvertisingConfig::long_range`],
+/// and only on `channels`.
+///
+/// The advertised status is kept current, see [`update_status`].
+#[cfg(not(feature = "beacon_only"))]
+#[allow(clippy::too_many_arguments)]
+pub async fn advertise<'values, 'server, C>(
+    device_name: &str,
+    peripheral_role: &mut Peripheral<'values, C, BlePacketPool>,
+    gatt_server: &'server GattServer<'values>,
+    interval: Option<AdvertisingInterval>,
+    filter_policy: AdvFilterPolicy,
+    tx_power_level: i8,
+    long_range: bool,
+    channels: AdvertisingChannels,
+) -> Result<GattConnection<'values, 'server, BlePacketPool>, BleHostError<C::Error>>
+where
+    C: ExtendedAdvertisingController,
+{
+    let service_uuids = GattServer::advertised_services();
+    let interval = interval.map(AdvertisingInterval::validated);
+
+    // Restart the advertiser with new parameters whenever the device enters or
+    // leaves the thermally throttled state.
+    loop {
+        let throttled = thermal::is_throttled();
+        let (adv_data, scan_data) =
+            status_advertisement(device_name, &service_uuids, tx_power_level, throttled)
+                .map_err(insufficient_space)?;
+        let parameters = advertising_parameters(interval, filter_policy, channels, throttled);
+
+        let advertiser = if long_range {
+            advertise_coded(
+                peripheral_role,
+                parameters,
+                Advertisement::ExtConnectableNonscannableUndirected {
+                    anonymous: false,
+                    adv_data:  &adv_data,
+                },
+            )
+            .await?
+        } else {
+            peripheral_role
+                .advertise(
+                    &parameters,
+                    Advertisement::ConnectableScannableUndirected {
+                        adv_data:  &adv_data,
+                        scan_data: &scan_data,
+                    },
+                )
+                .await?
+        };
+
+        let status_updated = update_status(peripheral_role, long_range, || {
+            status_advertisement(device_name, &service_uuids, tx_power_level, throttled)
+        });
+        match select3(
+            advertiser.accept(),
+            thermal::THROTTLE_CHANGED.wait(),
+            status_updated,
         )
-        .await?;
+        .await
+        {
+            Either3::First(connection) => {
+                return Ok(connection?.with_attribute_server(gatt_server)?);
+            }
+            Either3::Second(_) | Either3::Third(()) => continue,
+        }
+    }
+}
+
+/// Broadcast the same advertisement as [`advertise`], minus the GATT services
+/// of a beacon only build, without accepting connections. Broadcasts until
+/// dropped, only returning if the controller rejects the advertisement.
+#[cfg(feature = "beacon_only")]
+#[allow(clippy::too_many_arguments)]
+pub async fn advertise<'values, C>(
+    device_name: &str,
+    peripheral_role: &mut Peripheral<'values, C, BlePacketPool>,
+    interval: Option<AdvertisingInterval>,
+    filter_policy: AdvFilterPolicy,
+    tx_power_level: i8,
+    long_range: bool,
+    channels: AdvertisingChannels,
+) -> Result<Infallible, BleHostError<C::Error>>
+where
+    C: ExtendedAdvertisingController,
+{
+    let interval = interval.map(AdvertisingInterval::validated);
+
+    // Restart the advertiser with new parameters whenever the device enters or
+    // leaves the thermally throttled state.
+    loop {
+        let throttled = thermal::is_throttled();
+        let (adv_data, scan_data) =
+            status_advertisement(device_name, &[], tx_power_level, throttled)
+                .map_err(insufficient_space)?;
+        let parameters = advertising_parameters(interval, filter_policy, channels, throttled);
+
+        // The advertiser stops advertising when it is dropped.
+        let _advertiser = if long_range {
+            advertise_coded(
+                peripheral_role,
+                parameters,
+                Advertisement::ExtNonconnectableNonscannableUndirected {
+                    anonymous: false,
+                    adv_data:  &adv_data,
+                },
+            )
+            .await?
+        } else {
+            peripheral_role
+                .advertise(
+                    &parameters,
+                    Advertisement::NonconnectableScannableUndirected {
+                        adv_data:  &adv_data,
+                        scan_data: &scan_data,
+                    },
+                )
+                .await?
+        };
+
+        let status_updated = update_status(peripheral_role, long_range, || {
+            status_advertisement(device_name, &[], tx_power_level, throttled)
+        });
+        select(thermal::THROTTLE_CHANGED.wait(), status_updated).await;
+    }
+}
+
+/// Keep the status flags of the running advertisement current, replacing its
+/// advertising data as they change, see [`status::STATUS_FLAGS_CHANGED`].
+/// Updating in place spares stopping and restarting the advertiser for every
+/// change.
+///
+/// Returns once the advertiser must be restarted instead: when advertising on
+/// the LE Coded PHY if `long_range`, whose extended advertising data the
+/// legacy update cannot replace, or if the controller rejects the update.
+/// `encode` encodes the advertisement with the current flags.
+async fn update_status<C: Controller>(
+    peripheral_role: &mut Peripheral<'_, C, BlePacketPool>,
+    long_range: bool,
+    encode: impl Fn() -> Result<(AdvPayload, AdvPayload), AdvError>,
+) {
+    loop {
+        status::STATUS_FLAGS_CHANGED.wait().await;
+        if long_range {
+            return;
+        }
+
+        let Ok((adv_data, _)) = encode() else {
+            return;
+        };
+
+        // Only the advertising data is replaced: the host leaves the scan
+        // response untouched when given none. The kind of advertisement only
+        // tells the host it is a legacy one.
+        let advertisement = Advertisement::ConnectableScannableUndirected {
+            adv_data:  &adv_data,
+            scan_data: &[],
+        };
+        if let Err(error) = peripheral_role.update_adv_data(advertisement).await {
+            defmt::warn!("[adv] failed to update the advertised status: {}", error);
+            return;
+        }
+
+        defmt::debug!(
+            "[adv] advertised status updated: {}",
+            status::status_flags()
+        );
+    }
+}
+
+/// Encode the advertising data and scan response advertising the device and
+/// its status.
+fn status_advertisement(
+    device_name: &str,
+    service_uuids: &[[u8; 2]],
+    tx_power_level: i8,
+    throttled: bool,
+) -> Result<(AdvPayload, AdvPayload), AdvError> {
+    let status_flags = [status::status_flags()];
+
+    AdvertisementBuilder::new()
+        .flags(LE_GENERAL_DISCOVERABLE | BR_EDR_NOT_SUPPORTED)
+        .service_uuids16(service_uuids)
+        .manufacturer_data(status::COMPANY_IDENTIFIER, &status_flags)
+        .tx_power_level(if throttled {
+            THROTTLED_TX_POWER_LEVEL
+        } else {
+            tx_power_level
+        })
+        .appearance(APPEARANCE.to_le_bytes())
+        .local_name(device_name)
+        .build()
+}
+
+/// Returns the parameters of an advertiser advertising within `interval` on
+/// `channels` as `filter_policy` allows, slowed down and quieted while
+/// `throttled`.
+fn advertising_parameters(
+    interval: Option<AdvertisingInterval>,
+    filter_policy: AdvFilterPolicy,
+    channels: AdvertisingChannels,
+    throttled: bool,
+) -> AdvertisementParameters {
+    let mut parameters = AdvertisementParameters {
+        filter_policy,
+        channel_map: Some(channels.channel_map()),
+        ..Default::default()
+    };
+    if let Some(interval) = interval {
+        parameters.interval_min = interval.min;
+        parameters.interval_max = interval.max;
+    }
+
+    if throttled {
+        parameters.interval_min = parameters.interval_min.max(THROTTLED_INTERVAL);
+        parameters.interval_max = parameters.interval_max.max(THROTTLED_INTERVAL);
+        parameters.tx_power = THROTTLED_TX_POWER;
+    }
+
+    parameters
+}
+
+/// Start advertising `advertisement`, an extended advertisement, with
+/// `parameters` on the LE Coded PHY. Both the primary advertisements and the
+/// auxiliary packets carrying the advertising data use the coded PHY, so the
+/// device is only found by centrals scanning it.
+async fn advertise_coded<'values, C: ExtendedAdvertisingController>(
+    peripheral_role: &mut Peripheral<'values, C, BlePacketPool>,
+    parameters: AdvertisementParameters,
+    advertisement: Advertisement<'_>,
+) -> Result<Advertiser<'values, C, BlePacketPool>, BleHostError<C::Error>> {
+    let sets = [AdvertisementSet {
+        params: AdvertisementParameters {
+            primary_phy: PhyKind::LeCoded,
+            secondary_phy: PhyKind::LeCoded,
+            ..parameters
+        },
+        data:   advertisement,
+    }];
+    let mut handles = AdvertisementSet::handles(&sets);
+
+    peripheral_role.advertise_ext(&sets, &mut handles).await
+}
+
+/// Broadcast an iBeacon, turning the device into a positioning beacon that
+/// centrals cannot connect to. Broadcasts until dropped, only returning if the
+/// controller rejects the advertisement.
+///
+/// `uuid` is the proximity UUID shared by a deployment's beacons, `major` and
+/// `minor` identify the beacon within it, and `tx_power` is the received
+/// signal strength at 1 m, in dBm, that scanners estimate distance from.
+///
+/// The advertising data holds the Flags followed by the fixed 25 byte iBeacon
+/// manufacturer specific data. Unlike the rest of BLE, the iBeacon fields are
+/// big endian:
+///
+/// | Offset | Length | Field                                 |
+/// |--------|--------|---------------------------------------|
+/// | 0      | 2      | Apple's company identifier, `0x004c`  |
+/// | 2      | 1      | iBeacon type, `0x02`                  |
+/// | 3      | 1      | Length of the remaining bytes, `0x15` |
+/// | 4      | 16     | Proximity UUID                        |
+/// | 20     | 2      | Major                                 |
+/// | 22     | 2      | Minor                                 |
+/// | 24     | 1      | TX power, as a signed byte            |
+pub async fn advertise_ibeacon<'values, C: Controller>(
+    peripheral_role: &mut Peripheral<'values, C, BlePacketPool>,
+    uuid: [u8; 16],
+    major: u16,
+    minor: u16,
+    tx_power: i8,
+) -> Result<(), BleHostError<C::Error>> {
+    let identity = IBeaconIdentity {
+        uuid,
+        major,
+        minor,
+        measured_power: tx_power,
+    };
+    let beacon = beacon::beacon_advertisement(&BeaconIdentity::IBeacon(identity))
+        .map_err(insufficient_space)?
+        // UNWRAP: Infallible. A provisioned identity always has an
+        // advertisement.
+        .unwrap();
+
+    defmt::info!("[adv] broadcasting iBeacon {}", identity);
+    broadcast(peripheral_role, &beacon).await
+}
 
-    let connection = advertiser
-        .accept()
-        .await?
-        .with_attribute_server(gatt_server)?;
+/// Errors broadcasting an Eddystone-URL beacon.
+#[derive(Debug, defmt::Format)]
+pub enum EddystoneUrlError<E> {
+    /// The URL cannot be encoded in an Eddystone-URL frame,
+    /// [`BeaconError::UrlTooLong`] if it does not fit.
+    InvalidUrl(BeaconError),
 
-    Ok(connection)
+    /// The host failed to advertise.
+    Host(BleHostError<E>),
+}
+
+impl<E> From<BleHostError<E>> for EddystoneUrlError<E> {
+    fn from(error: BleHostError<E>) -> Self {
+        Self::Host(error)
+    }
+}
+
+/// Broadcast an Eddystone-URL beacon pointing scanners to `url`, such as an
+/// asset's page. Centrals cannot connect to it. Broadcasts until dropped, only
+/// returning if `url` cannot be encoded or the controller rejects the
+/// advertisement.
+///
+/// The URL must start with `http://` or `https://`, and fit in 17 bytes once
+/// its scheme and common domain suffixes such as `.com/` are compressed to a
+/// byte each, [`BeaconError::UrlTooLong`] otherwise. Longer URLs must go
+/// through a URL shortener.
+///
+/// # Remarks
+///
+/// To verify the beacon, scan with the Physical Web app, or any other
+/// Eddystone aware scanner such as nRF Connect, within a few meters of the
+/// device. It shows up with the decoded URL, which must match `url` exactly.
+pub async fn advertise_eddystone_url<'values, C: Controller>(
+    peripheral_role: &mut Peripheral<'values, C, BlePacketPool>,
+    url: &str,
+) -> Result<(), EddystoneUrlError<C::Error>> {
+    let frame = EddystoneUrlFrame::new(url, EDDYSTONE_URL_TX_POWER)
+        .map_err(EddystoneUrlError::InvalidUrl)?;
+    let beacon = beacon::url_advertisement(&frame)
+        .map_err(|error| BleHostError::from(insufficient_space(error)))?;
+
+    defmt::info!("[adv] broadcasting Eddystone-URL {}", url);
+    broadcast(peripheral_role, &beacon).await?;
+    Ok(())
+}
+
+/// Broadcast an Eddystone-UID beacon of `identity`, interleaved with
+/// Eddystone-TLM frames. Centrals cannot connect to it. Broadcasts until
+/// dropped, only returning if the controller rejects the advertisement.
+pub async fn advertise_eddystone_uid<'values, C: Controller>(
+    peripheral_role: &mut Peripheral<'values, C, BlePacketPool>,
+    identity: &EddystoneUidIdentity,
+) -> Result<(), BleHostError<C::Error>> {
+    let beacon = beacon::beacon_advertisement(&BeaconIdentity::EddystoneUid(*identity))
+        .map_err(insufficient_space)?
+        // UNWRAP: Infallible. A provisioned identity always has an
+        // advertisement.
+        .unwrap();
+
+    defmt::info!("[adv] broadcasting Eddystone-UID {}", identity);
+    broadcast_with_telemetry(peripheral_role, &beacon).await
+}
+
+/// Broadcast the non-connectable Eddystone `beacon`, interleaved with an
+/// Eddystone-TLM frame of the device's health after every
+/// [`EDDYSTONE_FRAMES_PER_TELEMETRY`] beacon frames, each for one advertising
+/// interval, until dropped. Only returns if the controller rejects an
+/// advertisement.
+async fn broadcast_with_telemetry<'values, C: Controller>(
+    peripheral_role: &mut Peripheral<'values, C, BlePacketPool>,
+    beacon: &BeaconAdvertisement,
+) -> Result<(), BleHostError<C::Error>> {
+    let mut advertising_count: u32 = 0;
+
+    // The advertiser restarts for every frame, well within
+    // `MAX_ADVERTISING_WINDOW`.
+    liveness::register(Task::Advertising);
+
+    loop {
+        liveness::check_in(Task::Advertising);
+
+        let interval = if thermal::is_throttled() {
+            EDDYSTONE_INTERVAL.max(THROTTLED_INTERVAL)
+        } else {
+            EDDYSTONE_INTERVAL
+        };
+        let mut parameters = AdvertisementParameters {
+            interval_min: interval,
+            interval_max: interval,
+            ..Default::default()
+        };
+        if thermal::is_throttled() {
+            parameters.tx_power = THROTTLED_TX_POWER;
+        }
+
+        let telemetry = beacon::telemetry_advertisement(&Telemetry {
+            battery_millivolts: battery::millivolts(),
+            temperature: thermal::temperature(),
+            advertising_count,
+            uptime: Duration::from_millis(Instant::now().as_millis()),
+        })
+        .map_err(insufficient_space)?;
+
+        let frames = [(beacon, EDDYSTONE_FRAMES_PER_TELEMETRY), (&telemetry, 1)];
+        for (frame, count) in frames {
+            // The first advertising event starts right away, dropping the
+            // advertiser `count` intervals later stops it before the next one.
+            let _advertiser = peripheral_role
+                .advertise(&parameters, frame.advertisement())
+                .await?;
+            Timer::after(interval * count).await;
+
+            advertising_count = advertising_count.wrapping_add(count);
+        }
+    }
+}
+
+/// Broadcast the non-connectable `beacon` until dropped, only returning if the
+/// controller rejects it.
+async fn broadcast<'values, C: Controller>(
+    peripheral_role: &mut Peripheral<'values, C, BlePacketPool>,
+    beacon: &BeaconAdvertisement,
+) -> Result<(), BleHostError<C::Error>> {
+    liveness::register(Task::Advertising);
+
+    // Restart the advertiser with new parameters whenever the device enters or
+    // leaves the thermally throttled state, and at least once per
+    // `MAX_ADVERTISING_WINDOW` so that this loop and the BLE host's event loop
+    // show they make progress.
+    loop {
+        liveness::check_in(Task::Advertising);
+
+        let parameters = advertising_parameters(
+            None,
+            AdvFilterPolicy::Unfiltered,
+            AdvertisingChannels::ALL,
+            thermal::is_throttled(),
+        );
+
+        // The advertiser stops advertising when it is dropped.
+        let _advertiser = peripheral_role
+            .advertise(&parameters, beacon.advertisement())
+            .await?;
+
+        select(
+            thermal::THROTTLE_CHANGED.wait(),
+            Timer::after(MAX_ADVERTISING_WINDOW),
+        )
+        .await;
+    }
 }
 
 /// BLE advertisement task.
-/// Continually advertises until a connection is established. The connection is
-/// then handed off to the GATT server for processing.
-pub async fn advertise_task<'values, C: Controller>(
+/// Continually advertises, handing each established connection off to
+/// `accepted` for [`GattServer::serve_connections`] to serve while advertising
+/// continues. Advertising pauses while every connection slot is taken if
+/// configured to. Beacon only builds never accept a connection and have no
+/// GATT server.
+///
+/// Advertising stops once one of the limits of `config` is reached or
+/// [`stop_advertising`] is called, and resumes when [`start_advertising`] is
+/// called, with the limits reset.
+pub async fn advertise_task<'values, #[cfg(not(feature = "beacon_only"))] 'server, C>(
     device_name: &'values str,
-    peripheral_role: &mut Peripheral<'values, C, DefaultPacketPool>,
-    gatt_server: &super::gatt_server::GattServer<'values>,
-) {
+    stack: &Stack<'values, C, BlePacketPool>,
+    peripheral_role: &mut Peripheral<'values, C, BlePacketPool>,
+    #[cfg(not(feature = "beacon_only"))] gatt_server: &'server GattServer<'values>,
+    #[cfg(not(feature = "beacon_only"))] accepted: &AcceptedConnections<'values, 'server>,
+    config: &AdvertisingConfig,
+) where
+    C: ExtendedAdvertisingController
+        + ControllerCmdSync<LeClearFilterAcceptList>
+        + ControllerCmdSync<LeAddDeviceToFilterAcceptList>
+        + ControllerCmdSync<LeClearResolvingList>
+        + ControllerCmdSync<LeAddDeviceToResolvingList>
+        + ControllerCmdSync<LeSetAddrResolutionEnable>
+        + ControllerCmdSync<LeSetPrivacyMode>
+        + ControllerCmdSync<LeReadAdvertisingChannelTxPower>
+        + ControllerCmdSync<LeEncrypt>
+        + ControllerCmdSync<LeSetRandomAddr>,
+{
+    liveness::register(Task::Advertising);
+
+    // Loaded even if not enforced, as it may be enforced at runtime.
+    allow_list::load(config.allow_list);
+
+    let tx_power_level = read_tx_power_level(stack).await;
+
+    let long_range = config.long_range && !cfg!(feature = "minimal_controller");
+    if config.long_range && !long_range {
+        defmt::warn!("[adv] the minimal controller has no coded PHY, advertising on the 1M PHY");
+    }
+
+    let channels = config.channels.validated();
+
+    let initial_interval = config.backoff.map(|backoff| backoff.initial_interval);
+    let mut provisioning_started = Instant::now();
+    let mut shelved = false;
+
     loop {
-        if let Ok(connection) = advertise(device_name, peripheral_role, gatt_server).await {
-            gatt_server.gatt_server_task(&connection).await;
+        // Shown throughout, including between advertising windows and while
+        // a duty cycle pauses advertising.
+        indicator::set_advertising(true);
+
+        let mut time_advertised = Duration::from_ticks(0);
+        let mut connection_count: u32 = 0;
+        let mut interval = initial_interval;
+        let mut step_started = Instant::now();
+        let mut idle_since = Instant::now();
+        let mut duty_cycle_started = Instant::now();
+        let mut rotation_index: usize = 0;
+        let mut entry_started = Instant::now();
+
+        loop {
+            liveness::check_in(Task::Advertising);
+
+            if config.pause_when_full && connections::slots_full() {
+                defmt::info!("[adv] connection slots full, advertising paused");
+                liveness::idle(&BLE_TASKS, connections::wait_slot_free()).await;
+                defmt::info!("[adv] connection slot freed, advertising resumed");
+            }
+
+            let duty_cycle_window = config
+                .duty_cycle
+                .map(|duty_cycle| duty_cycle.window(duty_cycle_started.elapsed()));
+
+            // Sit out the paused part of the duty cycle without advertising.
+            if let Some((None, paused_remaining)) = duty_cycle_window {
+                defmt::debug!(
+                    "[adv] duty cycle paused for {} ms",
+                    paused_remaining.as_millis()
+                );
+                let paused = select3(
+                    Timer::after(paused_remaining),
+                    RESET_ADVERTISING_BACKOFF.wait(),
+                    ADVERTISING_CONTROL.wait(),
+                );
+                match liveness::idle(&BLE_TASKS, paused).await {
+                    Either3::First(()) | Either3::Third(AdvertisingCommand::Start) => {}
+                    Either3::Second(()) => {
+                        defmt::debug!("[adv] advertising interval backoff reset");
+                        interval = initial_interval;
+                        step_started = Instant::now();
+                        provisioning_started = Instant::now();
+                        duty_cycle_started = Instant::now();
+                    }
+                    Either3::Third(AdvertisingCommand::Stop) => {
+                        defmt::info!("[adv] advertising stop requested");
+                        break;
+                    }
+                }
+                continue;
+            }
+
+            let rotation = config
+                .beacon_rotation
+                .filter(|rotation| !rotation.schedule.is_empty());
+            if let Some(rotation) = rotation {
+                if entry_started.elapsed() >= rotation.cadence {
+                    rotation_index = (rotation_index + 1) % rotation.schedule.len();
+                    entry_started = Instant::now();
+                }
+            }
+            let entry_remaining = rotation.map(|rotation| {
+                rotation
+                    .cadence
+                    .checked_sub(entry_started.elapsed())
+                    .unwrap_or(Duration::from_ticks(0))
+            });
+
+            // Broadcast the beacon entry of the rotation until it ends.
+            let beacon = rotation
+                .filter(|rotation| rotation.schedule[rotation_index] == RotationEntry::Beacon)
+                .and_then(|_| rotation_beacon());
+            if let (Some(beacon), Some(entry_remaining)) = (beacon, entry_remaining) {
+                let broadcasting = select3(
+                    with_timeout(entry_remaining, broadcast(peripheral_role, &beacon)),
+                    RESET_ADVERTISING_BACKOFF.wait(),
+                    ADVERTISING_CONTROL.wait(),
+                );
+                match broadcasting.await {
+                    Either3::First(Ok(Err(error))) => {
+                        defmt::warn!("[adv] failed to broadcast the beacon: {}", error);
+                        Timer::after(entry_remaining).await;
+                    }
+                    Either3::First(_) | Either3::Third(AdvertisingCommand::Start) => {}
+                    Either3::Second(()) => {
+                        defmt::debug!("[adv] advertising interval backoff reset");
+                        interval = initial_interval;
+                        step_started = Instant::now();
+                        provisioning_started = Instant::now();
+                        duty_cycle_started = Instant::now();
+                    }
+                    Either3::Third(AdvertisingCommand::Stop) => {
+                        defmt::info!("[adv] advertising stop requested");
+                        break;
+                    }
+                }
+                continue;
+            }
+
+            // Advertise until a central connects, the current backoff step
+            // elapses, the rotation moves on, or the maximum advertising
+            // duration is reached.
+            let remaining = config.max_duration.map(|max_duration| {
+                max_duration
+                    .checked_sub(time_advertised)
+                    .unwrap_or(Duration::from_ticks(0))
+            });
+            let step_remaining = config.backoff.map(|backoff| {
+                backoff
+                    .step_duration
+                    .checked_sub(step_started.elapsed())
+                    .unwrap_or(Duration::from_ticks(0))
+            });
+            let idle_remaining = config.idle_timeout.map(|idle_timeout| {
+                idle_timeout
+                    .checked_sub(idle_since.elapsed())
+                    .unwrap_or(Duration::from_ticks(0))
+            });
+
+            // Until a central connects, the provisioning window also bounds
+            // how long to advertise before dropping to the shelved interval.
+            let provisioning_timeout = config
+                .provisioning_timeout
+                .filter(|_| !connections::connected_since_boot());
+            let provisioning_remaining = provisioning_timeout.map(|timeout| {
+                timeout
+                    .window
+                    .checked_sub(provisioning_started.elapsed())
+                    .unwrap_or(Duration::from_ticks(0))
+            });
+            let now_shelved = provisioning_remaining == Some(Duration::from_ticks(0));
+            if now_shelved && !shelved {
+                defmt::info!("[adv] not provisioned since boot, dropping to slow advertising");
+            }
+            shelved = now_shelved;
+
+            // Not advertising, so peers that bonded since may be added.
+            if let Err(error) = allow_list::sync_controller_lists(stack).await {
+                defmt::warn!(
+                    "[adv] failed to update the controller's allow list: {}",
+                    error
+                );
+            }
+
+            // Filtering resumes once the pairing window closes.
+            let use_allow_list = allow_list::is_enabled();
+            let filter_policy = if use_allow_list {
+                allow_list::filter_policy()
+            } else {
+                AdvFilterPolicy::Unfiltered
+            };
+            let pairing_window_remaining = allow_list::pairing_window_remaining()
+                .filter(|_| use_allow_list && !allow_list::is_empty());
+
+            let window = [
+                remaining,
+                step_remaining,
+                idle_remaining,
+                provisioning_remaining.filter(|_| !shelved),
+                pairing_window_remaining,
+                duty_cycle_window.map(|(_, window_remaining)| window_remaining),
+                entry_remaining,
+            ]
+            .into_iter()
+            .flatten()
+            .fold(MAX_ADVERTISING_WINDOW, Duration::min);
+            let advertised_interval = match provisioning_timeout {
+                Some(timeout) if shelved => Some(AdvertisingInterval::fixed(
+                    interval.map_or(timeout.shelved_interval, |interval| {
+                        interval.max(timeout.shelved_interval)
+                    }),
+                )),
+                _ => interval
+                    .map(AdvertisingInterval::fixed)
+                    .or(match duty_cycle_window {
+                        Some((Some(duty_cycle_interval), _)) => {
+                            Some(AdvertisingInterval::fixed(duty_cycle_interval))
+                        }
+                        _ => config.interval,
+                    }),
+            };
+            // The controller has no per-event interval jitter beyond advDelay,
+            // so a new delay is drawn each time round this loop, which runs at
+            // least every `MAX_ADVERTISING_WINDOW`.
+            let advertised_interval = if config.interval_jitter {
+                advertised_interval.map(|interval| interval.jittered(interval_jitter()))
+            } else {
+                advertised_interval
+            };
+
+            // A client may have renamed the device since advertising started.
+            let renamed = DeviceName::renamed();
+            let advertised_name = renamed.as_deref().unwrap_or(device_name);
+
+            if let Some(rotation) = config.address_rotation {
+                if let Err(error) = privacy::rotate_if_due(stack, rotation).await {
+                    defmt::warn!("[adv] failed to rotate the private address: {}", error);
+                }
+            }
+
+            #[cfg(not(feature = "beacon_only"))]
+            let advertiser = advertise(
+                advertised_name,
+                peripheral_role,
+                gatt_server,
+                advertised_interval,
+                filter_policy,
+                tx_power_level,
+                long_range,
+                channels,
+            );
+            #[cfg(feature = "beacon_only")]
+            let advertiser = advertise(
+                advertised_name,
+                peripheral_role,
+                advertised_interval,
+                filter_policy,
+                tx_power_level,
+                long_range,
+                channels,
+            );
+
+            let advertising_started = Instant::now();
+            let advertising = select3(
+                advertiser,
+                RESET_ADVERTISING_BACKOFF.wait(),
+                ADVERTISING_CONTROL.wait(),
+            );
+            ADVERTISING.store(true, Ordering::Relaxed);
+            let outcome = with_timeout(window, advertising).await.ok();
+            ADVERTISING.store(false, Ordering::Relaxed);
+            time_advertised += advertising_started.elapsed();
+
+            match outcome {
+                #[cfg(not(feature = "beacon_only"))]
+                Some(Either3::First(Ok(connection))) => {
+                    interval = initial_interval;
+                    step_started = Instant::now();
+                    connection_count = connection_count.saturating_add(1);
+                    idle_since = Instant::now();
+                    duty_cycle_started = Instant::now();
+                    connections::connected();
+
+                    // Never waits: the channel holds a connection per slot.
+                    accepted.send(connection).await;
+
+                    if config
+                        .max_connections
+                        .is_some_and(|max_connections| connection_count >= max_connections)
+                    {
+                        defmt::info!("[adv] maximum connection count reached");
+                        break;
+                    }
+                }
+                #[cfg(feature = "beacon_only")]
+                Some(Either3::First(Ok(never))) => match never {},
+                Some(Either3::First(Err(_))) => {}
+                Some(Either3::Second(())) => {
+                    defmt::debug!("[adv] advertising interval backoff reset");
+                    interval = initial_interval;
+                    step_started = Instant::now();
+                    provisioning_started = Instant::now();
+                    duty_cycle_started = Instant::now();
+                }
+                Some(Either3::Third(AdvertisingCommand::Start)) => {}
+                Some(Either3::Third(AdvertisingCommand::Stop)) => {
+                    defmt::info!("[adv] advertising stop requested");
+                    break;
+                }
+                None => {
+                    if config
+                        .max_duration
+                        .is_some_and(|max_duration| time_advertised >= max_duration)
+                    {
+                        defmt::info!("[adv] maximum advertising duration reached");
+                        break;
+                    }
+
+                    if config
+                        .idle_timeout
+                        .is_some_and(|idle_timeout| idle_since.elapsed() >= idle_timeout)
+                    {
+                        if connections::active_connections() == 0 {
+                            defmt::info!("[adv] no connection before the idle timeout");
+                            break;
+                        }
+
+                        idle_since = Instant::now();
+                    }
+
+                    // Other windows may have ended before the backoff step.
+                    let backoff = config
+                        .backoff
+                        .filter(|backoff| step_started.elapsed() >= backoff.step_duration);
+                    if let (Some(backoff), Some(current)) = (backoff, interval) {
+                        step_started = Instant::now();
+                        let next = backoff.next_interval(current);
+                        defmt::debug!(
+                            "[adv] advertising interval backed off to {} ms",
+                            next.as_millis()
+                        );
+                        interval = Some(next);
+                    }
+                }
+            }
+        }
+
+        defmt::info!("[adv] advertising stopped, waiting to be started");
+        indicator::set_advertising(false);
+        ADVERTISING_STOPPED.signal(());
+        liveness::idle(&BLE_TASKS, async {
+            while ADVERTISING_CONTROL.wait().await != AdvertisingCommand::Start {}
+        })
+        .await;
+        defmt::info!("[adv] advertising started");
+    }
+}
+
+/// Returns the advertisement of the beacon identity of the device
+/// configuration, broadcast by the [`RotationEntry::Beacon`] entries of a
+/// rotation, or `None` if no identity is provisioned or it does not fit.
+fn rotation_beacon() -> Option<BeaconAdvertisement> {
+    match beacon::beacon_advertisement(&config::get().beacon) {
+        Ok(beacon) => beacon,
+        Err(error) => {
+            defmt::warn!("[adv] the beacon identity does not fit: {}", error);
+            None
         }
     }
 }
+
+/// Returns the transmit power of advertisements, in dBm, as reported by the
+/// controller, or [`DEFAULT_TX_POWER_LEVEL`] if it cannot be read. Also
+/// reported by [`tx_power_level`].
+async fn read_tx_power_level<C>(stack: &Stack<'_, C, BlePacketPool>) -> i8
+where
+    C: Controller + ControllerCmdSync<LeReadAdvertisingChannelTxPower>,
+{
+    let tx_power_level = match stack.command(LeReadAdvertisingChannelTxPower::new()).await {
+        Ok(tx_power_level) => {
+            defmt::debug!("[adv] advertising at {} dBm", tx_power_level);
+            tx_power_level
+        }
+        Err(error) => {
+            defmt::warn!(
+                "[adv] failed to read the advertising transmit power, assuming {} dBm: {}",
+                DEFAULT_TX_POWER_LEVEL,
+                error
+            );
+            DEFAULT_TX_POWER_LEVEL
+        }
+    };
+
+    TX_POWER_LEVEL.store(tx_power_level, Ordering::Relaxed);
+    tx_power_level
+}
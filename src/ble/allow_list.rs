@@ -0,0 +1,354 @@
+// SPDX-FileCopyrightText: 2025 Derek Sauer
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Peers allowed to connect to a locked down device, enforced by the
+//! controller's filter accept list.
+//!
+//! Peers that bond are added to the allow list by their identity address, and
+//! the list is persisted to flash. Any peer may connect and bond while the
+//! pairing window is open, or while no peer is allowed yet. Outside the
+//! pairing window, advertising only accepts scan and connection requests from
+//! peers on the list.
+//!
+//! The controller's filter accept list and resolving list are rebuilt from the
+//! allow list and the [`bonds`] before advertising next starts, as neither may
+//! change while advertising. Peers that distributed their identity resolving
+//! key go on the resolving list, so the controller recognizes them behind
+//! their resolvable private addresses.
+//!
+//! Enforcing the allow list can be turned on and off at runtime, and the list
+//! cleared, from the control point. A cleared list lets any peer connect again
+//! until one is allowed.
+
+use core::cell::{Cell, RefCell};
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use bt_hci::cmd::le::{
+    LeAddDeviceToFilterAcceptList, LeAddDeviceToResolvingList, LeClearFilterAcceptList,
+    LeClearResolvingList, LeSetAddrResolutionEnable, LeSetPrivacyMode,
+};
+use bt_hci::controller::ControllerCmdSync;
+use bt_hci::param::{AddrKind, BdAddr, PrivacyMode};
+use embassy_sync::blocking_mutex::Mutex;
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_time::{Duration, Instant};
+use trouble_host::prelude::*;
+
+use super::advertise::RESET_ADVERTISING_BACKOFF;
+use super::{BlePacketPool, bonds};
+use crate::storage::{self, WritePriority};
+
+/// Storage page holding the allow list.
+const ALLOW_LIST_PAGE: u32 = 1;
+
+/// Marks a page holding an allow list. Erased flash reads as all ones and
+/// never matches.
+const ALLOW_LIST_MAGIC: [u8; 4] = *b"LPAL";
+
+/// Most peers on the allow list, one per bond. The oldest peer is dropped to
+/// make room for a new one.
+pub const MAX_PEERS: usize = bonds::MAX_BONDS;
+
+/// Length of an encoded peer: its address type followed by its address.
+const PEER_LENGTH: usize = 7;
+
+/// Offset of the peer count in the encoded allow list, following the magic.
+const COUNT_OFFSET: usize = ALLOW_LIST_MAGIC.len();
+
+/// Offset of the first peer in the encoded allow list.
+const PEERS_OFFSET: usize = COUNT_OFFSET + 1;
+
+/// Length of the encoded allow list.
+const ENCODED_LENGTH: usize = PEERS_OFFSET + MAX_PEERS * PEER_LENGTH;
+
+/// Peers allowed to connect.
+static PEERS: Mutex<CriticalSectionRawMutex, RefCell<heapless::Vec<Address, MAX_PEERS>>> =
+    Mutex::new(RefCell::new(heapless::Vec::new()));
+
+/// Whether advertising enforces the allow list.
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Whether the controller's filter accept list and resolving list no longer
+/// match the allow list.
+static STALE: AtomicBool = AtomicBool::new(true);
+
+/// When the pairing window closes, or `None` if it is closed.
+static PAIRING_WINDOW_END: Mutex<CriticalSectionRawMutex, Cell<Option<Instant>>> =
+    Mutex::new(Cell::new(None));
+
+/// Encode `peers` for storage.
+fn encode(peers: &[Address]) -> [u8; ENCODED_LENGTH] {
+    let mut bytes = [0; ENCODED_LENGTH];
+    bytes[..COUNT_OFFSET].copy_from_slice(&ALLOW_LIST_MAGIC);
+    bytes[COUNT_OFFSET] = peers.len() as u8;
+
+    for (peer, encoded) in peers
+        .iter()
+        .zip(bytes[PEERS_OFFSET..].chunks_exact_mut(PEER_LENGTH))
+    {
+        encoded[0] = peer.kind.as_raw();
+        encoded[1..].copy_from_slice(peer.addr.raw());
+    }
+
+    bytes
+}
+
+/// Decode a stored allow list, or `None` if none was stored.
+fn decode(bytes: &[u8; ENCODED_LENGTH]) -> Option<heapless::Vec<Address, MAX_PEERS>> {
+    if bytes[..COUNT_OFFSET] != ALLOW_LIST_MAGIC {
+        return None;
+    }
+
+    let count = usize::from(bytes[COUNT_OFFSET]).min(MAX_PEERS);
+    let peers = bytes[PEERS_OFFSET..]
+        .chunks_exact(PEER_LENGTH)
+        .take(count)
+        .map(|encoded| Address {
+            kind: AddrKind::new(encoded[0]),
+            // UNWRAP: Infallible. The chunk holds exactly a 6 byte address.
+            addr: BdAddr::new(encoded[1..].try_into().unwrap()),
+        })
+        .collect();
+
+    Some(peers)
+}
+
+/// Load the allow list from flash, enforcing it if `enabled`. No peer is
+/// allowed if none was stored.
+pub fn load(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+
+    let mut bytes = [0; ENCODED_LENGTH];
+    let peers = match storage::read(ALLOW_LIST_PAGE, &mut bytes) {
+        Ok(()) => decode(&bytes).unwrap_or_default(),
+        Err(error) => {
+            defmt::error!("[allow_list] failed to read the allow list: {}", error);
+            heapless::Vec::new()
+        }
+    };
+
+    defmt::info!("[allow_list] {} peers allowed", peers.len());
+    PEERS.lock(|allowed| *allowed.borrow_mut() = peers);
+    STALE.store(true, Ordering::Relaxed);
+}
+
+/// Enforce the allow list, or let any peer connect, restarting advertising
+/// with the matching filter.
+pub fn set_enabled(enabled: bool) {
+    if ENABLED.swap(enabled, Ordering::Relaxed) != enabled {
+        defmt::info!(
+            "[allow_list] allow list {}",
+            if enabled { "enforced" } else { "disabled" }
+        );
+        RESET_ADVERTISING_BACKOFF.signal(());
+    }
+}
+
+/// Returns `true` if advertising enforces the allow list.
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Forget every allowed peer and persist the empty list, letting any peer
+/// connect until one is allowed again.
+pub fn clear() {
+    PEERS.lock(|peers| peers.borrow_mut().clear());
+    defmt::info!("[allow_list] allow list cleared");
+
+    if let Err(error) = storage::try_write(ALLOW_LIST_PAGE, &encode(&[]), WritePriority::Deferred) {
+        defmt::error!("[allow_list] failed to persist the allow list: {}", error);
+    }
+    STALE.store(true, Ordering::Relaxed);
+
+    // Restart advertising without the filter.
+    RESET_ADVERTISING_BACKOFF.signal(());
+}
+
+/// Returns `true` if no peer is allowed yet.
+pub fn is_empty() -> bool {
+    PEERS.lock(|peers| peers.borrow().is_empty())
+}
+
+/// Returns `true` if `address` is on the allow list.
+pub fn is_allowed(address: &Address) -> bool {
+    PEERS.lock(|peers| peers.borrow().contains(address))
+}
+
+/// Open the pairing window for `duration`, letting any peer connect and be
+/// added to the allow list. For example when a button is pressed.
+pub fn open_pairing_window(duration: Duration) {
+    defmt::info!(
+        "[allow_list] pairing window open for {} s",
+        duration.as_secs()
+    );
+    PAIRING_WINDOW_END.lock(|end| end.set(Some(Instant::now() + duration)));
+
+    // Restart advertising without the filter.
+    RESET_ADVERTISING_BACKOFF.signal(());
+}
+
+/// Returns how long the pairing window remains open, or `None` if it is
+/// closed.
+pub fn pairing_window_remaining() -> Option<Duration> {
+    PAIRING_WINDOW_END.lock(|end| {
+        end.get()
+            .and_then(|end| end.checked_duration_since(Instant::now()))
+            .filter(|remaining| *remaining > Duration::from_ticks(0))
+    })
+}
+
+/// Returns `true` if new peers may connect and bond: the pairing window is
+/// open, or no peer is allowed yet.
+fn is_accepting_new_peers() -> bool {
+    is_empty() || pairing_window_remaining().is_some()
+}
+
+/// Returns the advertising filter policy enforcing the allow list.
+pub fn filter_policy() -> AdvFilterPolicy {
+    if is_accepting_new_peers() {
+        AdvFilterPolicy::Unfiltered
+    } else {
+        AdvFilterPolicy::FilterConnAndScan
+    }
+}
+
+/// Returns the identity address of the peer that established `bond` while
+/// connected from `connection_address`.
+///
+/// The security manager keeps the identity address but not its type. A peer
+/// that did not distribute an identity uses its connection address, whose
+/// type is known. Otherwise a random static address is told from a public one
+/// by its two most significant bits, both set.
+fn identity_address(bond: &BondInformation, connection_address: Address) -> Address {
+    let addr = bond.identity.bd_addr;
+    let kind = if addr == connection_address.addr {
+        connection_address.kind
+    } else if addr.raw()[5] & 0xc0 == 0xc0 {
+        AddrKind::RANDOM
+    } else {
+        AddrKind::PUBLIC
+    };
+
+    Address { kind, addr }
+}
+
+/// Allow the peer that just bonded, connected from `connection_address`, to
+/// connect by its identity address, and persist the allow list. The oldest
+/// peer is dropped if full. The controller's lists are rebuilt before
+/// advertising next starts.
+pub fn add_bonded_peer(bond: &BondInformation, connection_address: Address) {
+    let address = identity_address(bond, connection_address);
+    if is_allowed(&address) {
+        // The peer may have bonded again with a new identity resolving key.
+        STALE.store(true, Ordering::Relaxed);
+        return;
+    }
+
+    let encoded = PEERS.lock(|peers| {
+        let mut peers = peers.borrow_mut();
+        if peers.is_full() {
+            peers.remove(0);
+        }
+
+        // UNWRAP: Infallible. Room was made above.
+        peers.push(address).unwrap();
+        encode(&peers)
+    });
+
+    defmt::info!("[allow_list] peer {} allowed", address.addr);
+    if let Err(error) = storage::try_write(ALLOW_LIST_PAGE, &encoded, WritePriority::Deferred) {
+        defmt::error!("[allow_list] failed to persist the allow list: {}", error);
+    }
+    STALE.store(true, Ordering::Relaxed);
+}
+
+/// Rebuild the controller's filter accept list from the allowed peers, and its
+/// resolving list from their identity resolving keys, if either changed since
+/// last rebuilt. Must only be called while not advertising.
+///
+/// The controller refuses the changes while an advertising set it was just
+/// told to stop is still running. They are retried the next time.
+pub async fn sync_controller_lists<C>(
+    stack: &Stack<'_, C, BlePacketPool>,
+) -> Result<(), BleHostError<C::Error>>
+where
+    C: Controller
+        + ControllerCmdSync<LeClearFilterAcceptList>
+        + ControllerCmdSync<LeAddDeviceToFilterAcceptList>
+        + ControllerCmdSync<LeClearResolvingList>
+        + ControllerCmdSync<LeAddDeviceToResolvingList>
+        + ControllerCmdSync<LeSetAddrResolutionEnable>
+        + ControllerCmdSync<LeSetPrivacyMode>,
+{
+    if !STALE.swap(false, Ordering::Relaxed) {
+        return Ok(());
+    }
+
+    let result = rebuild_controller_lists(stack).await;
+    if result.is_err() {
+        STALE.store(true, Ordering::Relaxed);
+    }
+
+    result
+}
+
+/// Rebuild the controller's filter accept list and resolving list, see
+/// [`sync_controller_lists`].
+async fn rebuild_controller_lists<C>(
+    stack: &Stack<'_, C, BlePacketPool>,
+) -> Result<(), BleHostError<C::Error>>
+where
+    C: Controller
+        + ControllerCmdSync<LeClearFilterAcceptList>
+        + ControllerCmdSync<LeAddDeviceToFilterAcceptList>
+        + ControllerCmdSync<LeClearResolvingList>
+        + ControllerCmdSync<LeAddDeviceToResolvingList>
+        + ControllerCmdSync<LeSetAddrResolutionEnable>
+        + ControllerCmdSync<LeSetPrivacyMode>,
+{
+    stack.command(LeSetAddrResolutionEnable::new(false)).await?;
+    stack.command(LeClearFilterAcceptList::new()).await?;
+    stack.command(LeClearResolvingList::new()).await?;
+
+    let peers = PEERS.lock(|peers| peers.borrow().clone());
+    let mut resolvable = 0;
+    for peer in &peers {
+        stack
+            .command(LeAddDeviceToFilterAcceptList::new(peer.kind, peer.addr))
+            .await?;
+
+        let Some(irk) = bonds::identity_resolving_key(&peer.addr) else {
+            continue;
+        };
+
+        // The device keeps its identity address, so it has no local key.
+        stack
+            .command(LeAddDeviceToResolvingList::new(
+                peer.kind,
+                peer.addr,
+                irk.to_le_bytes(),
+                [0; 16],
+            ))
+            .await?;
+
+        // Also accept the peer connecting from its identity address.
+        stack
+            .command(LeSetPrivacyMode::new(
+                peer.kind,
+                peer.addr,
+                PrivacyMode::Device,
+            ))
+            .await?;
+        resolvable += 1;
+    }
+
+    stack.command(LeSetAddrResolutionEnable::new(true)).await?;
+    defmt::debug!(
+        "[allow_list] controller lists rebuilt: {} peers, {} resolvable",
+        peers.len(),
+        resolvable
+    );
+
+    Ok(())
+}
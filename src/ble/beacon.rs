@@ -0,0 +1,134 @@
+// SPDX-FileCopyrightText: 2025 Derek Sauer
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Beacon identities and the advertisements broadcasting them.
+//!
+//! A beacon's identity is provisioned at runtime and persisted in the
+//! [`DeviceConfig`](crate::config::DeviceConfig), so identical firmware can be
+//! flashed to every unit.
+
+use embassy_time::Duration;
+pub use lookpoint_logic::beacon::{
+    BeaconError, BeaconIdentity, EddystoneUidIdentity, EddystoneUrlFrame, IBeaconIdentity,
+};
+use trouble_host::prelude::*;
+
+use super::advertise::{AdvError, AdvertisementBuilder, BeaconAdvertisement};
+
+/// Apple's company identifier, carried by the manufacturer data of iBeacons.
+const IBEACON_COMPANY_IDENTIFIER: u16 = 0x004c;
+
+/// 16-bit UUID of the Eddystone service, in little endian byte order.
+const EDDYSTONE_UUID16: [u8; 2] = 0xfeaa_u16.to_le_bytes();
+
+/// Frame type of an Eddystone-TLM frame.
+const EDDYSTONE_TLM_FRAME: u8 = 0x20;
+
+/// Version of the unencrypted Eddystone-TLM frame.
+const EDDYSTONE_TLM_VERSION: u8 = 0x00;
+
+/// Beacon temperature of an Eddystone-TLM frame when it is not known.
+const EDDYSTONE_TLM_TEMPERATURE_UNKNOWN: i16 = i16::MIN;
+
+/// Health of a beacon, broadcast in Eddystone-TLM frames so a fleet can be
+/// monitored without connecting to each beacon.
+pub struct Telemetry {
+    /// Battery voltage, in millivolts, if measured.
+    pub battery_millivolts: Option<u16>,
+
+    /// Die temperature, in hundredths of a degree Celsius, if measured.
+    pub temperature: Option<i32>,
+
+    /// Advertising frames broadcast since boot.
+    pub advertising_count: u32,
+
+    /// Time since boot.
+    pub uptime: Duration,
+}
+
+impl Telemetry {
+    /// Returns the unencrypted Eddystone-TLM frame carried in the service
+    /// data. Unlike the rest of BLE, its fields are big endian:
+    ///
+    /// | Offset | Length | Field                                         |
+    /// |--------|--------|-----------------------------------------------|
+    /// | 0      | 1      | Frame type, `0x20`                            |
+    /// | 1      | 1      | Version, `0x00`                               |
+    /// | 2      | 2      | Battery voltage in mV, 0 if unknown           |
+    /// | 4      | 2      | Temperature in 8.8 fixed point °C             |
+    /// | 6      | 4      | Advertising frames broadcast since boot       |
+    /// | 10     | 4      | Time since boot in tenths of a second         |
+    fn service_data(&self) -> [u8; 14] {
+        // Signed 8.8 fixed point, clamped out of the value reserved for
+        // unknown temperatures.
+        let temperature = self
+            .temperature
+            .map_or(EDDYSTONE_TLM_TEMPERATURE_UNKNOWN, |centi| {
+                (centi * 256 / 100).clamp(i32::from(i16::MIN) + 1, i32::from(i16::MAX)) as i16
+            });
+        let uptime = (self.uptime.as_millis() / 100) as u32;
+
+        let mut frame = [0; 14];
+        frame[0] = EDDYSTONE_TLM_FRAME;
+        frame[1] = EDDYSTONE_TLM_VERSION;
+        frame[2..4].copy_from_slice(&self.battery_millivolts.unwrap_or(0).to_be_bytes());
+        frame[4..6].copy_from_slice(&temperature.to_be_bytes());
+        frame[6..10].copy_from_slice(&self.advertising_count.to_be_bytes());
+        frame[10..14].copy_from_slice(&uptime.to_be_bytes());
+        frame
+    }
+}
+
+/// Build the advertisement broadcasting `identity`, or `None` if no identity is
+/// provisioned.
+pub fn beacon_advertisement(
+    identity: &BeaconIdentity,
+) -> Result<Option<BeaconAdvertisement>, AdvError> {
+    let flags = LE_GENERAL_DISCOVERABLE | BR_EDR_NOT_SUPPORTED;
+
+    match identity {
+        BeaconIdentity::Unprovisioned => Ok(None),
+        BeaconIdentity::IBeacon(identity) => {
+            let payload = identity.manufacturer_payload();
+            let builder = AdvertisementBuilder::new()
+                .flags(flags)
+                .manufacturer_data(IBEACON_COMPANY_IDENTIFIER, &payload);
+
+            BeaconAdvertisement::new(&builder).map(Some)
+        }
+        BeaconIdentity::EddystoneUid(identity) => {
+            let service_uuids = [EDDYSTONE_UUID16];
+            let frame = identity.service_data();
+            let builder = AdvertisementBuilder::new()
+                .flags(flags)
+                .service_uuids16(&service_uuids)
+                .service_data16(EDDYSTONE_UUID16, &frame);
+
+            BeaconAdvertisement::new(&builder).map(Some)
+        }
+    }
+}
+
+/// Build an Eddystone-URL advertisement broadcasting the encoded `frame`.
+pub fn url_advertisement(frame: &EddystoneUrlFrame) -> Result<BeaconAdvertisement, AdvError> {
+    let service_uuids = [EDDYSTONE_UUID16];
+    let builder = AdvertisementBuilder::new()
+        .flags(LE_GENERAL_DISCOVERABLE | BR_EDR_NOT_SUPPORTED)
+        .service_uuids16(&service_uuids)
+        .service_data16(EDDYSTONE_UUID16, frame.as_bytes());
+
+    BeaconAdvertisement::new(&builder)
+}
+
+/// Build an Eddystone-TLM advertisement broadcasting `telemetry`.
+pub fn telemetry_advertisement(telemetry: &Telemetry) -> Result<BeaconAdvertisement, AdvError> {
+    let service_uuids = [EDDYSTONE_UUID16];
+    let frame = telemetry.service_data();
+    let builder = AdvertisementBuilder::new()
+        .flags(LE_GENERAL_DISCOVERABLE | BR_EDR_NOT_SUPPORTED)
+        .service_uuids16(&service_uuids)
+        .service_data16(EDDYSTONE_UUID16, &frame);
+
+    BeaconAdvertisement::new(&builder)
+}
@@ -0,0 +1,296 @@
+// SPDX-FileCopyrightText: 2025 Derek Sauer
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Bonds with paired peers, persisted to flash so a previously paired peer
+//! reconnects without pairing again after a reset.
+//!
+//! Each bond holds the peer's identity address, its identity resolving key if
+//! it distributed one, and the long term key encrypting the link. The peer's
+//! notification subscriptions are stored with it, as the host forgets them on
+//! reset. Every stored bond carries a CRC, so a bond corrupted in flash is
+//! discarded rather than handed to the security manager.
+//!
+//! At most [`MAX_BONDS`] bonds are kept. The least recently connected peer is
+//! dropped to make room for a new one.
+
+use core::cell::RefCell;
+
+use bt_hci::param::BdAddr;
+use embassy_sync::blocking_mutex::Mutex;
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use trouble_host::prelude::*;
+
+use super::BlePacketPool;
+use super::subscriptions::Subscriptions;
+use crate::storage::{self, MAX_RECORD_LENGTH, WritePriority};
+
+/// Storage page holding the bonds.
+const BONDS_PAGE: u32 = 2;
+
+/// Marks a page holding bonds. Erased flash reads as all ones and never
+/// matches. Changed from `LPBD` when the subscriptions were added, so bonds in
+/// the earlier format are dropped and their peers pair again.
+const BONDS_MAGIC: [u8; 4] = *b"LPB2";
+
+/// Most bonds kept. The least recently connected peer is dropped to make room
+/// for a new one.
+pub const MAX_BONDS: usize = 4;
+
+/// Length of an encoded bond: the peer's address, a flag and the identity
+/// resolving key, the long term key, the security level, the subscriptions,
+/// and the CRC of all of them.
+const BOND_LENGTH: usize = 6 + 1 + 16 + 16 + 1 + 1 + 4;
+
+/// Offset of the CRC in an encoded bond.
+const CRC_OFFSET: usize = BOND_LENGTH - 4;
+
+/// Offset of the bond count in the encoded bonds, following the magic.
+const COUNT_OFFSET: usize = BONDS_MAGIC.len();
+
+/// Offset of the first bond in the encoded bonds.
+const BONDS_OFFSET: usize = COUNT_OFFSET + 1;
+
+/// Length of the encoded bonds.
+const ENCODED_LENGTH: usize = BONDS_OFFSET + MAX_BONDS * BOND_LENGTH;
+
+const _: () = assert!(
+    ENCODED_LENGTH <= MAX_RECORD_LENGTH,
+    "the bonds do not fit in a record"
+);
+
+/// A bond with a peer and the peer's subscriptions.
+#[derive(Clone)]
+struct StoredBond {
+    /// The bond, handed to the host's security manager.
+    bond:          BondInformation,
+    /// The peer's subscriptions, see [`Subscriptions::bits`].
+    subscriptions: u8,
+}
+
+/// Bonds, from the least to the most recently connected peer.
+static BONDS: Mutex<CriticalSectionRawMutex, RefCell<heapless::Vec<StoredBond, MAX_BONDS>>> =
+    Mutex::new(RefCell::new(heapless::Vec::new()));
+
+/// Encode `stored` for storage.
+fn encode_bond(stored: &StoredBond, encoded: &mut [u8]) {
+    let bond = &stored.bond;
+    encoded[..6].copy_from_slice(bond.identity.bd_addr.raw());
+    if let Some(irk) = bond.identity.irk {
+        encoded[6] = 1;
+        encoded[7..23].copy_from_slice(&irk.to_le_bytes());
+    }
+    encoded[23..39].copy_from_slice(&bond.ltk.to_le_bytes());
+    encoded[39] = match bond.security_level {
+        SecurityLevel::NoEncryption => 0,
+        SecurityLevel::Encrypted => 1,
+        SecurityLevel::EncryptedAuthenticated => 2,
+    };
+    encoded[40] = stored.subscriptions;
+
+    let crc = storage::crc32(&encoded[..CRC_OFFSET]);
+    encoded[CRC_OFFSET..BOND_LENGTH].copy_from_slice(&crc.to_le_bytes());
+}
+
+/// Decode a stored bond, or `None` if it is corrupt.
+fn decode_bond(encoded: &[u8]) -> Option<StoredBond> {
+    // UNWRAP: Infallible. The CRC is 4 bytes long.
+    let crc = u32::from_le_bytes(encoded[CRC_OFFSET..BOND_LENGTH].try_into().unwrap());
+    if crc != storage::crc32(&encoded[..CRC_OFFSET]) {
+        return None;
+    }
+
+    let security_level = match encoded[39] {
+        0 => SecurityLevel::NoEncryption,
+        1 => SecurityLevel::Encrypted,
+        2 => SecurityLevel::EncryptedAuthenticated,
+        _ => return None,
+    };
+
+    // UNWRAP: Infallible. The slices hold exactly an address and two keys.
+    let irk = (encoded[6] != 0)
+        .then(|| IdentityResolvingKey::from_le_bytes(encoded[7..23].try_into().unwrap()));
+
+    let bond = BondInformation {
+        identity: Identity {
+            bd_addr: BdAddr::new(encoded[..6].try_into().unwrap()),
+            irk,
+        },
+        ltk: LongTermKey::from_le_bytes(encoded[23..39].try_into().unwrap()),
+        security_level,
+        is_bonded: true,
+    };
+
+    Some(StoredBond {
+        bond,
+        subscriptions: encoded[40],
+    })
+}
+
+/// Encode `bonds` for storage.
+fn encode(bonds: &[StoredBond]) -> [u8; ENCODED_LENGTH] {
+    let mut bytes = [0; ENCODED_LENGTH];
+    bytes[..COUNT_OFFSET].copy_from_slice(&BONDS_MAGIC);
+    bytes[COUNT_OFFSET] = bonds.len() as u8;
+
+    for (bond, encoded) in bonds
+        .iter()
+        .zip(bytes[BONDS_OFFSET..].chunks_exact_mut(BOND_LENGTH))
+    {
+        encode_bond(bond, encoded);
+    }
+
+    bytes
+}
+
+/// Decode stored bonds, or `None` if none were stored. Corrupt bonds are
+/// discarded.
+fn decode(bytes: &[u8; ENCODED_LENGTH]) -> Option<heapless::Vec<StoredBond, MAX_BONDS>> {
+    if bytes[..COUNT_OFFSET] != BONDS_MAGIC {
+        return None;
+    }
+
+    let count = usize::from(bytes[COUNT_OFFSET]).min(MAX_BONDS);
+    let bonds = bytes[BONDS_OFFSET..]
+        .chunks_exact(BOND_LENGTH)
+        .take(count)
+        .filter_map(|encoded| {
+            let bond = decode_bond(encoded);
+            if bond.is_none() {
+                defmt::warn!("[bonds] discarded a corrupt bond");
+            }
+            bond
+        })
+        .collect();
+
+    Some(bonds)
+}
+
+/// Persist the bonds.
+fn persist(bonds: &[StoredBond]) {
+    if let Err(error) = storage::try_write(BONDS_PAGE, &encode(bonds), WritePriority::Deferred) {
+        defmt::error!("[bonds] failed to persist the bonds: {}", error);
+    }
+}
+
+/// Load the bonds from flash and hand them to the host's security manager.
+/// Must be called before advertising starts.
+pub fn load<C: Controller>(stack: &Stack<'_, C, BlePacketPool>) {
+    let mut bytes = [0; ENCODED_LENGTH];
+    let bonds = match storage::read(BONDS_PAGE, &mut bytes) {
+        Ok(()) => decode(&bytes).unwrap_or_default(),
+        Err(error) => {
+            defmt::error!("[bonds] failed to read the bonds: {}", error);
+            heapless::Vec::new()
+        }
+    };
+
+    for stored in &bonds {
+        if let Err(error) = stack.add_bond_information(stored.bond.clone()) {
+            defmt::error!(
+                "[bonds] failed to restore the bond with {}: {}",
+                stored.bond.identity.bd_addr,
+                error
+            );
+        }
+    }
+
+    defmt::info!("[bonds] {} bonds restored", bonds.len());
+    BONDS.lock(|stored| *stored.borrow_mut() = bonds);
+}
+
+/// Store the bond established with a peer, along with its current
+/// `subscriptions`, replacing any earlier bond with it. Drops the least
+/// recently connected peer's bond if full.
+pub fn store(bond: BondInformation, subscriptions: &Subscriptions) {
+    let bonds = BONDS.lock(|bonds| {
+        let mut bonds = bonds.borrow_mut();
+        bonds.retain(|stored| stored.bond.identity.bd_addr != bond.identity.bd_addr);
+        if bonds.is_full() {
+            let dropped = bonds.remove(0);
+            defmt::info!(
+                "[bonds] bond with {} dropped",
+                dropped.bond.identity.bd_addr
+            );
+        }
+
+        defmt::info!("[bonds] bond with {} stored", bond.identity.bd_addr);
+        let stored = StoredBond {
+            bond,
+            subscriptions: subscriptions.bits(),
+        };
+        // UNWRAP: Infallible. Room was made above.
+        bonds.push(stored).unwrap();
+        bonds.clone()
+    });
+
+    persist(&bonds);
+}
+
+/// Returns the subscriptions stored with the bond with the peer at `address`,
+/// or `None` if it is not bonded.
+pub fn subscriptions(address: &BdAddr) -> Option<Subscriptions> {
+    BONDS.lock(|bonds| {
+        bonds
+            .borrow()
+            .iter()
+            .find(|stored| stored.bond.identity.bd_addr == *address)
+            .map(|stored| Subscriptions::from_bits(stored.subscriptions))
+    })
+}
+
+/// Store `subscriptions` with the bond with the peer at `address`, if any, so
+/// they are restored when it reconnects after a reset.
+pub fn set_subscriptions(address: &BdAddr, subscriptions: &Subscriptions) {
+    let bonds = BONDS.lock(|bonds| {
+        let mut bonds = bonds.borrow_mut();
+        let stored = bonds
+            .iter_mut()
+            .find(|stored| stored.bond.identity.bd_addr == *address)?;
+        if stored.subscriptions == subscriptions.bits() {
+            return None;
+        }
+
+        stored.subscriptions = subscriptions.bits();
+        Some(bonds.clone())
+    });
+
+    if let Some(bonds) = bonds {
+        persist(&bonds);
+    }
+}
+
+/// Returns the identity resolving key distributed by the bonded peer with the
+/// identity `address`, if any.
+pub fn identity_resolving_key(address: &BdAddr) -> Option<IdentityResolvingKey> {
+    BONDS.lock(|bonds| {
+        bonds
+            .borrow()
+            .iter()
+            .find(|stored| stored.bond.identity.bd_addr == *address)
+            .and_then(|stored| stored.bond.identity.irk)
+    })
+}
+
+/// Mark the bond with the peer at `address`, if any, as the most recently
+/// connected, so it is the last to be dropped.
+pub fn touch(address: &BdAddr) {
+    let bonds = BONDS.lock(|bonds| {
+        let mut bonds = bonds.borrow_mut();
+        let index = bonds
+            .iter()
+            .position(|stored| stored.bond.identity.bd_addr == *address)?;
+        if index + 1 == bonds.len() {
+            return None;
+        }
+
+        let bond = bonds.remove(index);
+        // UNWRAP: Infallible. The bond was just removed.
+        bonds.push(bond).unwrap();
+        Some(bonds.clone())
+    });
+
+    if let Some(bonds) = bonds {
+        persist(&bonds);
+    }
+}
@@ -0,0 +1,229 @@
+// SPDX-FileCopyrightText: 2025 Derek Sauer
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Connection parameters and PHY preferred by the firmware.
+//!
+//! The same parameters are requested from the central once connected and
+//! encoded as the GAP Peripheral Preferred Connection Parameters (PPCP)
+//! characteristic value, so both negotiation paths agree.
+
+use embassy_time::Duration;
+use trouble_host::prelude::*;
+
+/// Configuration applied to each connection once established.
+///
+/// The `minimal_controller` build does not support PHY updates and stays on
+/// the 1M PHY.
+pub const CONNECTION_CONFIG: ConnectionConfig = ConnectionConfig {
+    parameters:         PREFERRED_CONNECTION_PARAMETERS,
+    // Long enough for a central to discover the services at the short
+    // interval it connected with.
+    parameters_delay:   Duration::from_secs(5),
+    phy:                if cfg!(feature = "minimal_controller") {
+        PhyPreference::Le1M
+    } else {
+        PhyPreference::Le2M
+    },
+    require_encryption: false,
+    pairing:            PairingMode::JustWorks,
+};
+
+/// Configuration applied to each connection once established.
+#[derive(Clone, Copy)]
+pub struct ConnectionConfig {
+    /// Connection parameters requested from the central.
+    pub parameters: PreferredConnectionParameters,
+
+    /// Time after connecting before [`parameters`](Self::parameters) are
+    /// requested, once the link is stable.
+    pub parameters_delay: Duration,
+
+    /// PHY requested from the central.
+    pub phy: PhyPreference,
+
+    /// Only accept writes to the control point over an encrypted link, so
+    /// clients must pair before commanding the device. Renaming the device,
+    /// setting its clock, and its console require an encrypted link
+    /// regardless.
+    pub require_encryption: bool,
+
+    /// How the device pairs with a client.
+    pub pairing: PairingMode,
+}
+
+/// How the device pairs with a client, trading convenience for protection
+/// against man-in-the-middle attacks.
+///
+/// The tracker has neither a display nor a keyboard, so passkeys are either
+/// fixed at build time, printed on the unit's label, or logged over the debug
+/// probe during development.
+#[derive(Clone, Copy, defmt::Format)]
+pub enum PairingMode {
+    /// Pair without user interaction. The link is encrypted but not protected
+    /// against a man-in-the-middle.
+    JustWorks,
+
+    /// The device enters this fixed 6 digit passkey when asked. The central's
+    /// user is prompted to enter the same passkey, read from the unit's label.
+    /// Pairing fails with a central displaying a passkey of its own instead,
+    /// as the passkeys differ.
+    FixedPasskey(u32),
+
+    /// The device generates a random passkey, logged as it has no display,
+    /// and the central's user is prompted to enter it.
+    DisplayedPasskey,
+}
+
+impl PairingMode {
+    /// Returns the IO capabilities the device pairs with.
+    pub fn io_capabilities(self) -> IoCapabilities {
+        match self {
+            Self::JustWorks => IoCapabilities::NoInputNoOutput,
+            Self::FixedPasskey(_) => IoCapabilities::KeyboardOnly,
+            Self::DisplayedPasskey => IoCapabilities::DisplayOnly,
+        }
+    }
+}
+
+/// PHY a deployment prefers, trading range for throughput.
+#[derive(Clone, Copy, PartialEq, defmt::Format)]
+#[repr(u8)]
+pub enum PhyPreference {
+    /// 1 Mbps PHY, supported by every central. Connections start on it, so no
+    /// update is requested.
+    Le1M  = 0,
+
+    /// 2 Mbps PHY. Shorter transmissions save power at the expense of range.
+    Le2M  = 1,
+
+    /// Coded PHY. Roughly quadruples range at the expense of throughput and
+    /// power.
+    Coded = 2,
+}
+
+impl PhyPreference {
+    /// Returns the PHY to request, or `None` if connections already use it.
+    pub fn requested_phy(self) -> Option<PhyKind> {
+        match self {
+            Self::Le1M => None,
+            Self::Le2M => Some(PhyKind::Le2M),
+            Self::Coded => Some(PhyKind::LeCoded),
+        }
+    }
+
+    /// Returns `true` if a connection on `phy` honours this preference.
+    pub fn is_satisfied_by(self, phy: PhyKind) -> bool {
+        match self {
+            Self::Le1M => matches!(phy, PhyKind::Le1M),
+            Self::Le2M => matches!(phy, PhyKind::Le2M),
+            Self::Coded => matches!(phy, PhyKind::LeCoded | PhyKind::LeCodedS2),
+        }
+    }
+}
+
+/// Connection parameters preferred by this device.
+///
+/// The device exchanges little data, so a moderate interval with some
+/// peripheral latency lets the radio sleep between connection events.
+pub const PREFERRED_CONNECTION_PARAMETERS: PreferredConnectionParameters =
+    PreferredConnectionParameters {
+        min_interval:        Duration::from_millis(30),
+        max_interval:        Duration::from_millis(50),
+        peripheral_latency:  4,
+        supervision_timeout: Duration::from_secs(4),
+    };
+
+// Served as is in the PPCP characteristic, which centrals may apply without
+// checking it.
+const _: () = assert!(
+    PREFERRED_CONNECTION_PARAMETERS.is_valid(),
+    "the preferred connection parameters are outside of the ranges the Core Specification allows"
+);
+
+/// Connection parameters a peripheral prefers the central to use.
+#[derive(Clone, Copy)]
+pub struct PreferredConnectionParameters {
+    /// Minimum connection interval, between 7.5 ms and 4 s.
+    pub min_interval: Duration,
+
+    /// Maximum connection interval, between 7.5 ms and 4 s.
+    pub max_interval: Duration,
+
+    /// Number of connection events the peripheral may skip.
+    pub peripheral_latency: u16,
+
+    /// Time without a connection event after which the connection is
+    /// considered lost, between 100 ms and 32 s.
+    pub supervision_timeout: Duration,
+}
+
+impl PreferredConnectionParameters {
+    /// Returns `true` if a connection interval of `interval` is within the
+    /// preferred range.
+    pub fn is_satisfied_by(&self, interval: Duration) -> bool {
+        (self.min_interval..=self.max_interval).contains(&interval)
+    }
+
+    /// Returns the parameters for a connection parameter update request.
+    pub fn connect_params(&self) -> ConnectParams {
+        ConnectParams {
+            min_connection_interval: self.min_interval,
+            max_connection_interval: self.max_interval,
+            max_latency: self.peripheral_latency,
+            supervision_timeout: self.supervision_timeout,
+            ..Default::default()
+        }
+    }
+
+    /// Returns `true` if the parameters are within the ranges the Core
+    /// Specification allows: intervals from 7.5 ms to 4 s, the minimum not
+    /// above the maximum, a peripheral latency of at most 499 connection
+    /// events, and a supervision timeout from 100 ms to 32 s. The timeout
+    /// must also be longer than twice the time the peripheral may go without
+    /// answering, `(1 + latency) * max_interval`.
+    pub const fn is_valid(&self) -> bool {
+        let min_interval = self.min_interval.as_micros() / 1250;
+        let max_interval = self.max_interval.as_micros() / 1250;
+        let latency = self.peripheral_latency as u64;
+        let timeout = self.supervision_timeout.as_millis() / 10;
+
+        6 <= min_interval
+            && min_interval <= max_interval
+            && max_interval <= 3200
+            && latency <= 499
+            && 10 <= timeout
+            && timeout <= 3200
+            && self.supervision_timeout.as_micros()
+                > (1 + latency) * self.max_interval.as_micros() * 2
+    }
+
+    /// Returns the parameters encoded as the value of the PPCP characteristic,
+    /// four little endian `u16`:
+    ///
+    /// | Offset | Length | Field                                          |
+    /// |--------|--------|------------------------------------------------|
+    /// | 0      | 2      | Minimum connection interval, in 1.25 ms units  |
+    /// | 2      | 2      | Maximum connection interval, in 1.25 ms units  |
+    /// | 4      | 2      | Peripheral latency, in connection events       |
+    /// | 6      | 2      | Supervision timeout, in 10 ms units            |
+    ///
+    /// Intervals that are not whole units are rounded down.
+    pub const fn ppcp_value(&self) -> [u8; 8] {
+        let min_interval = ((self.min_interval.as_micros() / 1250) as u16).to_le_bytes();
+        let max_interval = ((self.max_interval.as_micros() / 1250) as u16).to_le_bytes();
+        let latency = self.peripheral_latency.to_le_bytes();
+        let timeout = ((self.supervision_timeout.as_millis() / 10) as u16).to_le_bytes();
+
+        [
+            min_interval[0],
+            min_interval[1],
+            max_interval[0],
+            max_interval[1],
+            latency[0],
+            latency[1],
+            timeout[0],
+            timeout[1],
+        ]
+    }
+}
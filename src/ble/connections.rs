@@ -0,0 +1,89 @@
+// SPDX-FileCopyrightText: 2025 Derek Sauer
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Tracks active connections and their activity so other parts of the firmware,
+//! such as deferred flash writes, can avoid disturbing them, and advertising
+//! can pause while every connection slot is taken.
+
+use core::cell::Cell;
+use core::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+
+use embassy_sync::blocking_mutex::Mutex;
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::signal::Signal;
+use embassy_time::{Duration, Instant};
+
+use super::MAX_CONNECTIONS;
+use crate::indicator;
+
+/// Number of currently active connections.
+static ACTIVE_CONNECTIONS: AtomicU8 = AtomicU8::new(0);
+
+/// Whether any central has connected since boot.
+static CONNECTED_SINCE_BOOT: AtomicBool = AtomicBool::new(false);
+
+/// Signaled whenever a connection ends, freeing its slot.
+static SLOT_FREED: Signal<CriticalSectionRawMutex, ()> = Signal::new();
+
+/// Time of the last GATT event on any connection.
+static LAST_ACTIVITY: Mutex<CriticalSectionRawMutex, Cell<Instant>> =
+    Mutex::new(Cell::new(Instant::from_ticks(0)));
+
+/// Record that a connection was established.
+pub fn connected() {
+    ACTIVE_CONNECTIONS.fetch_add(1, Ordering::Relaxed);
+    CONNECTED_SINCE_BOOT.store(true, Ordering::Relaxed);
+    record_activity();
+    indicator::set_connected(true);
+
+    if slots_full() {
+        defmt::debug!(
+            "[connections] all {} connection slots taken",
+            MAX_CONNECTIONS
+        );
+    }
+}
+
+/// Record that a connection ended.
+pub fn disconnected() {
+    ACTIVE_CONNECTIONS.fetch_sub(1, Ordering::Relaxed);
+    SLOT_FREED.signal(());
+    indicator::set_connected(active_connections() > 0);
+}
+
+/// Returns the number of currently active connections.
+pub fn active_connections() -> u8 {
+    ACTIVE_CONNECTIONS.load(Ordering::Relaxed)
+}
+
+/// Returns `true` if any central has connected since boot.
+pub fn connected_since_boot() -> bool {
+    CONNECTED_SINCE_BOOT.load(Ordering::Relaxed)
+}
+
+/// Returns `true` if every connection slot is taken, so no central can
+/// connect.
+pub fn slots_full() -> bool {
+    usize::from(active_connections()) >= MAX_CONNECTIONS
+}
+
+/// Wait until a connection slot is free. Returns immediately if one already
+/// is.
+pub async fn wait_slot_free() {
+    while slots_full() {
+        SLOT_FREED.wait().await;
+    }
+}
+
+/// Record activity on a connection.
+pub fn record_activity() {
+    LAST_ACTIVITY.lock(|last_activity| last_activity.set(Instant::now()));
+}
+
+/// Returns `true` if no connection is active, or if there has been no activity
+/// on any connection for at least `quiet_period`.
+pub fn is_quiet(quiet_period: Duration) -> bool {
+    active_connections() == 0
+        || LAST_ACTIVITY.lock(|last_activity| last_activity.get().elapsed() >= quiet_period)
+}
@@ -0,0 +1,158 @@
+// SPDX-FileCopyrightText: 2025 Derek Sauer
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use core::cell::RefCell;
+use core::ops::Deref;
+
+use embassy_sync::blocking_mutex::Mutex;
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use lookpoint_logic::device_name::truncate_on_char_boundary;
+
+use super::advertise::LEGACY_PAYLOAD_LENGTH;
+use crate::boards::NAME_PLACEMENT;
+use crate::settings;
+
+/// Longest device name, in bytes, the GAP service accepts.
+pub const MAX_GAP_DEVICE_NAME_LENGTH: usize = 22;
+
+/// Where the local name is advertised, which bounds how long it can be.
+#[derive(Clone, Copy, PartialEq, defmt::Format)]
+pub enum NamePlacement {
+    /// In the advertising data, alongside the other AD structures: Flags (3
+    /// bytes), the 16-bit service UUID list (4 bytes), and the manufacturer
+    /// specific status data (5 bytes).
+    AdvertisingData,
+
+    /// Alone in the scan response, sent to centrals that scan actively.
+    ScanResponse,
+
+    /// In extended advertising data, which has room for any name.
+    ExtendedAdvertising,
+}
+
+impl NamePlacement {
+    /// Returns the longest local name, in bytes, that fits in this placement
+    /// after the name's own 2 byte header, and that the GAP service accepts.
+    pub const fn max_length(self) -> usize {
+        let max_length = match self {
+            NamePlacement::AdvertisingData => LEGACY_PAYLOAD_LENGTH - 3 - 4 - 5 - 2,
+            NamePlacement::ScanResponse => LEGACY_PAYLOAD_LENGTH - 2,
+            NamePlacement::ExtendedAdvertising => MAX_GAP_DEVICE_NAME_LENGTH,
+        };
+
+        if max_length < MAX_GAP_DEVICE_NAME_LENGTH {
+            max_length
+        } else {
+            MAX_GAP_DEVICE_NAME_LENGTH
+        }
+    }
+}
+
+/// Longest local name, in bytes, for where the board advertises it.
+pub const MAX_LOCAL_NAME_LENGTH: usize = NAME_PLACEMENT.max_length();
+
+// A device name always fits in the settings.
+const _: () = assert!(MAX_LOCAL_NAME_LENGTH <= settings::MAX_VALUE_LENGTH);
+
+/// Name a client renamed the device to since boot, advertised in place of the
+/// name the device booted with.
+static RENAMED: Mutex<CriticalSectionRawMutex, RefCell<Option<DeviceName>>> =
+    Mutex::new(RefCell::new(None));
+
+/// Errors renaming the device.
+#[cfg_attr(feature = "beacon_only", allow(dead_code))]
+#[derive(Clone, Copy, PartialEq, defmt::Format)]
+pub enum RenameError {
+    /// The name is empty.
+    Empty,
+
+    /// The name is not valid UTF-8.
+    InvalidUtf8,
+}
+
+/// Name of the device, advertised as its local name and served as the GAP
+/// device name.
+///
+/// Names longer than [`MAX_LOCAL_NAME_LENGTH`] bytes are truncated on a UTF-8
+/// character boundary.
+#[derive(Clone)]
+pub struct DeviceName(heapless::String<MAX_LOCAL_NAME_LENGTH>);
+
+impl DeviceName {
+    /// Create a new [`DeviceName`], truncating `name` if it is too long to be
+    /// advertised.
+    pub fn new(name: &str) -> Self {
+        let truncated = truncate_on_char_boundary(name, MAX_LOCAL_NAME_LENGTH);
+
+        if truncated.len() < name.len() {
+            defmt::warn!(
+                "[ble] device name \"{}\" is longer than {} bytes, truncated to \"{}\"",
+                name,
+                MAX_LOCAL_NAME_LENGTH,
+                truncated
+            );
+        }
+
+        let mut device_name = heapless::String::new();
+        // UNWRAP: Infallible. The truncated name fits in the string's capacity.
+        device_name.push_str(truncated).unwrap();
+
+        Self(device_name)
+    }
+
+    /// Load the device name saved in the settings, or use `default` if none
+    /// was saved or the saved name is invalid. Either is truncated as by
+    /// [`DeviceName::new`].
+    pub fn load(default: &str) -> Self {
+        match settings::get(settings::DEVICE_NAME) {
+            Some(name) if !name.is_empty() => Self::new(&name),
+            _ => {
+                defmt::info!("[ble] no valid device name saved, using \"{}\"", default);
+                Self::new(default)
+            }
+        }
+    }
+
+    /// Rename the device to `name`, as written by a client, truncated as by
+    /// [`DeviceName::new`]. The name is saved in the settings and advertised
+    /// from the next advertising session on.
+    #[cfg_attr(feature = "beacon_only", allow(dead_code))]
+    pub fn rename(name: &[u8]) -> Result<Self, RenameError> {
+        let name = core::str::from_utf8(name).map_err(|_| RenameError::InvalidUtf8)?;
+        if name.is_empty() {
+            return Err(RenameError::Empty);
+        }
+
+        let device_name = Self::new(name);
+        defmt::info!("[ble] device renamed to \"{}\"", device_name.as_str());
+
+        let mut saved = heapless::String::new();
+        // UNWRAP: Infallible. A device name always fits in the settings.
+        saved.push_str(&device_name).unwrap();
+        if let Err(error) = settings::try_set(settings::DEVICE_NAME, &saved) {
+            defmt::error!("[ble] failed to save the device name: {}", error);
+        }
+
+        RENAMED.lock(|renamed| *renamed.borrow_mut() = Some(device_name.clone()));
+        Ok(device_name)
+    }
+
+    /// Returns the name a client renamed the device to since boot, if any.
+    pub fn renamed() -> Option<Self> {
+        RENAMED.lock(|renamed| renamed.borrow().clone())
+    }
+
+    /// Returns the device name as a string slice.
+    pub fn as_str(&self) -> &str {
+        self.0.as_str()
+    }
+}
+
+impl Deref for DeviceName {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        self.as_str()
+    }
+}
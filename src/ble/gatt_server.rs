@@ -2,63 +2,1348 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+use core::cell::Cell;
+use core::ops::Deref;
+
+use bt_hci::cmd::status::ReadRssi;
+use bt_hci::controller::ControllerCmdSync;
+use bt_hci::param::Status;
+use bt_hci::uuid::BluetoothUuid16;
+use embassy_futures::join::{join, join_array};
+use embassy_futures::select::{Either, select, select4};
+use embassy_sync::blocking_mutex::raw::NoopRawMutex;
+use embassy_sync::channel::Channel;
+use embassy_time::{Duration, Ticker, Timer};
 use trouble_host::prelude::*;
 
+use super::connection_params::{CONNECTION_CONFIG, PairingMode};
+use super::device_name::DeviceName;
+use super::permissions::{Permissions, Security};
+use super::services::battery::{BATTERY_LEVEL, BatteryService, DEFAULT_BATTERY_LEVEL};
+use super::services::control::{ControlService, DEVICE_NAME_LENGTH, DeviceNameValue};
+use super::services::current_time::{CURRENT_TIME_LENGTH, CurrentTimeService};
 use super::services::device_information::DeviceInformation;
+use super::services::environmental_sensing::EnvironmentalSensing;
+use super::services::gap::GapService;
+use super::services::immediate_alert::ImmediateAlertService;
+use super::services::link::{DEFAULT_DATA_LENGTH, Link, LinkService, RSSI_UNAVAILABLE};
+use super::services::link_loss::LinkLossService;
+use super::services::motion::MotionService;
+use super::services::nus::{Console, ConsoleCommand, NUS_LENGTH, NusService, NusValue};
+use super::services::tx_power::TxPowerService;
+use super::subscriptions::{Notifying, Subscriptions};
+use super::{
+    BlePacketPool, MAX_CONNECTIONS, advertise, allow_list, bonds, connections, packet_pool,
+};
+use crate::indicator::{self, AlertLevel};
+use crate::sensors::{self, Sensor};
+use crate::thermal::TemperatureAlert;
+use crate::{battery, config, motion, system, thermal, wall_clock};
+
+/// How often subscribed clients are notified of the stationary time.
+const STATIONARY_TIME_NOTIFY_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How often the RSSI of a connection is read. Often enough for a phone to
+/// show its user getting closer to the device, subscribed clients are only
+/// notified when it changed.
+const RSSI_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Attributes added to the attribute table by all registered services,
+/// including the GAP service. Sizes the attribute table so it is always large
+/// enough: every service added to [`GattServer`] must be added here too.
+pub const TOTAL_ATTRIBUTES: usize = GapService::ATTRIBUTE_COUNT
+    + DeviceInformation::ATTRIBUTE_COUNT
+    + ControlService::ATTRIBUTE_COUNT
+    + MotionService::ATTRIBUTE_COUNT
+    + LinkService::ATTRIBUTE_COUNT
+    + BatteryService::ATTRIBUTE_COUNT
+    + EnvironmentalSensing::ATTRIBUTE_COUNT
+    + ImmediateAlertService::ATTRIBUTE_COUNT
+    + LinkLossService::ATTRIBUTE_COUNT
+    + TxPowerService::ATTRIBUTE_COUNT
+    + NusService::ATTRIBUTE_COUNT
+    + CurrentTimeService::ATTRIBUTE_COUNT;
+
+/// Client Characteristic Configuration Descriptors (CCCD) added to the
+/// attribute table by all registered services. Sizes the CCCD table like
+/// [`TOTAL_ATTRIBUTES`].
+pub const TOTAL_CCCDS: usize = GapService::CCCD_COUNT
+    + DeviceInformation::CCCD_COUNT
+    + ControlService::CCCD_COUNT
+    + MotionService::CCCD_COUNT
+    + LinkService::CCCD_COUNT
+    + BatteryService::CCCD_COUNT
+    + EnvironmentalSensing::CCCD_COUNT
+    + ImmediateAlertService::CCCD_COUNT
+    + LinkLossService::CCCD_COUNT
+    + TxPowerService::CCCD_COUNT
+    + NusService::CCCD_COUNT
+    + CurrentTimeService::CCCD_COUNT;
+
+/// Most SIG-adopted services advertised by [`GattServer::advertised_services`].
+pub const MAX_ADVERTISED_SERVICES: usize = 4;
+
+/// 16-bit UUIDs of the SIG-adopted services registered with the [`GattServer`]
+/// that scanners filter on, with the sensor each one depends on, if any.
+/// Advertised so Find Me and Proximity clients, and apps looking for the
+/// battery level, find the device. The Device Information, Environmental
+/// Sensing, and Current Time services are found once connected. Vendor
+/// services have 128-bit UUIDs that would crowd out the rest of the
+/// advertising data, so they are not advertised either.
+const ADVERTISED_SERVICES: [(BluetoothUuid16, Option<Sensor>); 4] = [
+    (ImmediateAlertService::BLE_UUID16, None),
+    (LinkLossService::BLE_UUID16, None),
+    (TxPowerService::BLE_UUID16, None),
+    (BatteryService::BLE_UUID16, None),
+];
+
+// Services past the room in the advertising data would silently be left out.
+const _: () = assert!(
+    ADVERTISED_SERVICES.len() <= MAX_ADVERTISED_SERVICES,
+    "more services are listed than fit in the advertising data"
+);
+
+/// Connections accepted by the advertiser, waiting to be served by
+/// [`GattServer::serve_connections`].
+pub type AcceptedConnections<'values, 'server> =
+    Channel<NoopRawMutex, GattConnection<'values, 'server, BlePacketPool>, MAX_CONNECTIONS>;
+
+/// Errors notifying a client of a characteristic's value.
+#[derive(defmt::Format)]
+pub enum NotifyError {
+    /// The value is outside of the characteristic's range.
+    InvalidValue,
+
+    /// The host failed to send the notification.
+    Host(Error),
+}
+
+impl From<Error> for NotifyError {
+    fn from(error: Error) -> Self {
+        Self::Host(error)
+    }
+}
+
+/// Errors starting the [`GattServer`].
+#[derive(Clone, Copy, Debug, defmt::Format)]
+pub enum GattServerError {
+    /// The GAP service could not be added, such as for a device name too
+    /// long for it. Holds the reason.
+    ConfigInvalid(&'static str),
+}
+
+/// The BLE host's attribute server, sized for every service of the
+/// [`GattServer`].
+type BleAttributeServer<'values> = AttributeServer<
+    'values,
+    NoopRawMutex,
+    BlePacketPool,
+    TOTAL_ATTRIBUTES,
+    TOTAL_CCCDS,
+    MAX_CONNECTIONS,
+>;
+
+/// Serves the attribute table holding the GAP service followed by each of the
+/// firmware's services, in the order of its fields.
+///
+/// The table is built here rather than with the BLE host's `gatt_server`
+/// macro, whose GAP service cannot serve the preferred connection parameters.
+/// Dereferences to the host's attribute server.
+pub struct GattServer<'values> {
+    server: BleAttributeServer<'values>,
 
-#[gatt_server]
-pub struct GattServer {
+    pub gap:                GapService,
     pub device_information: DeviceInformation,
+    pub control:            ControlService,
+    pub motion:             MotionService,
+    pub link:               LinkService,
+    pub battery:            BatteryService,
+    pub environmental:      EnvironmentalSensing,
+    pub immediate_alert:    ImmediateAlertService,
+    pub link_loss:          LinkLossService,
+    pub tx_power:           TxPowerService,
+    pub nus:                NusService,
+    pub current_time:       CurrentTimeService,
+}
+
+impl<'values> Deref for GattServer<'values> {
+    type Target = BleAttributeServer<'values>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.server
+    }
 }
 
 impl<'values> GattServer<'values> {
     /// Start the Gatt server.
-    pub fn start(device_name: &'values str) -> Result<Self, &'static str> {
-        let gap_config = GapConfig::Peripheral(PeripheralConfig {
-            name:       device_name,
-            appearance: &appearance::light_fixtures::LIGHT_CONTROLLER,
+    pub fn start(device_name: &'values str) -> Result<Self, GattServerError> {
+        let mut table = AttributeTable::new();
+        let gap =
+            GapService::new(&mut table, device_name).map_err(GattServerError::ConfigInvalid)?;
+        let device_information = DeviceInformation::new(&mut table);
+        let control = ControlService::new(&mut table);
+        let motion = MotionService::new(&mut table);
+        let link = LinkService::new(&mut table);
+        let battery = BatteryService::new(&mut table);
+        let environmental = EnvironmentalSensing::new(&mut table);
+        let immediate_alert = ImmediateAlertService::new(&mut table);
+        let link_loss = LinkLossService::new(&mut table);
+        let tx_power = TxPowerService::new(&mut table);
+        let nus = NusService::new(&mut table);
+        let current_time = CurrentTimeService::new(&mut table);
+
+        let gatt_server = GattServer {
+            server: AttributeServer::new(table),
+            gap,
+            device_information,
+            control,
+            motion,
+            link,
+            battery,
+            environmental,
+            immediate_alert,
+            link_loss,
+            tx_power,
+            nus,
+            current_time,
+        };
+
+        let configuration = ControlService::encode_configuration(device_name, &config::get());
+        if let Err(error) = gatt_server
+            .control
+            .configuration
+            .set(&gatt_server, &configuration)
+        {
+            defmt::warn!("[gatt] failed to set the configuration: {}", error);
+        }
+
+        gatt_server.set_device_name(device_name);
+
+        if let Err(error) = gatt_server
+            .battery
+            .level
+            .set(&gatt_server, &DEFAULT_BATTERY_LEVEL)
+        {
+            defmt::warn!("[gatt] failed to set the battery level: {}", error);
+        }
+
+        let used_attributes = gatt_server.table().iterate(|mut attributes| {
+            let mut count = 0;
+            while attributes.next().is_some() {
+                count += 1;
+            }
+            count
         });
+        defmt::info!(
+            "[gatt] attribute table: {} of {} attributes used",
+            used_attributes,
+            TOTAL_ATTRIBUTES
+        );
 
-        GattServer::new_with_config(gap_config)
+        Ok(gatt_server)
+    }
+
+    /// Serve up to [`MAX_CONNECTIONS`] connections at once, each one taken
+    /// from `accepted` as the advertiser accepts it. Runs alongside the
+    /// advertiser.
+    pub async fn serve_connections<'gatt_server, C>(
+        &'gatt_server self,
+        stack: &Stack<'_, C, BlePacketPool>,
+        accepted: &AcceptedConnections<'values, 'gatt_server>,
+    ) where
+        C: Controller + ControllerCmdSync<ReadRssi>,
+    {
+        join_array(core::array::from_fn::<_, MAX_CONNECTIONS, _>(|_| async {
+            loop {
+                let connection = accepted.receive().await;
+                self.gatt_server_task(stack, &connection).await;
+            }
+        }))
+        .await;
     }
 
     /// Process GATT events during connection intervals.
-    pub async fn gatt_server_task<'gatt_server>(
+    ///
+    /// The connection must have been recorded by [`connections::connected`]
+    /// when it was accepted, so the advertiser sees its slot taken right away.
+    pub async fn gatt_server_task<'gatt_server, C>(
         &self,
-        connection: &GattConnection<'values, 'gatt_server, DefaultPacketPool>,
+        stack: &Stack<'_, C, BlePacketPool>,
+        connection: &GattConnection<'values, 'gatt_server, BlePacketPool>,
+    ) where
+        C: Controller + ControllerCmdSync<ReadRssi>,
+    {
+        // The outcome is reported by a PHY updated event.
+        if let Some(phy) = CONNECTION_CONFIG.phy.requested_phy() {
+            defmt::debug!("[gatt] requesting the {} PHY", CONNECTION_CONFIG.phy);
+            if let Err(error) = connection.raw().set_phy(stack, phy).await {
+                defmt::warn!(
+                    "[gatt] failed to request the {} PHY: {}",
+                    CONNECTION_CONFIG.phy,
+                    error
+                );
+            }
+        }
+
+        // Ask the client to pair, encrypting the link. The outcome is reported
+        // by a pairing complete or failed event.
+        if let Err(error) = connection.raw().request_security() {
+            defmt::warn!("[gatt] failed to request pairing: {}", error);
+        }
+
+        // Notifications stop when the connection ends. A bonded client's
+        // subscriptions are kept for its next connection.
+        let subscriptions = self.restore_subscriptions(connection);
+        // Last RSSI read for this connection, answered to its client's reads.
+        let rssi = Cell::new(RSSI_UNAVAILABLE);
+        select4(
+            self.process_events(connection, &subscriptions, &rssi),
+            self.notify_task(connection, &subscriptions),
+            self.battery_notify_task(connection, &subscriptions),
+            select(
+                self.temperature_notify_task(connection, &subscriptions),
+                // The request completes early, the RSSI is read until the
+                // connection ends.
+                join(
+                    Self::request_connection_params(stack, connection),
+                    self.rssi_task(stack, connection, &subscriptions, &rssi),
+                ),
+            ),
+        )
+        .await;
+
+        connections::disconnected();
+        packet_pool::log_usage();
+
+        defmt::debug!(
+            "[gatt] connection event finished for handle: {}",
+            connection.raw().handle().raw()
+        );
+    }
+
+    /// Returns the 16-bit UUIDs, in little endian byte order, of the
+    /// SIG-adopted services to advertise: those whose sensor is enabled.
+    pub fn advertised_services() -> heapless::Vec<[u8; 2], MAX_ADVERTISED_SERVICES> {
+        ADVERTISED_SERVICES
+            .iter()
+            .filter(|(_, sensor)| sensor.is_none_or(sensors::is_enabled))
+            .map(|(uuid, _)| uuid.to_le_bytes())
+            .collect()
+    }
+
+    /// Process GATT events until the connection ends. `rssi` is kept current
+    /// by [`Self::rssi_task`].
+    async fn process_events<'gatt_server>(
+        &self,
+        connection: &GattConnection<'values, 'gatt_server, BlePacketPool>,
+        subscriptions: &Subscriptions,
+        rssi: &Cell<i8>,
     ) {
+        let peer_address = connection.raw().peer_address();
+        bonds::touch(&peer_address.addr);
+
+        // Connections start on the 1M PHY with the default data length.
+        let mut link = Link {
+            att_mtu:       connection.raw().att_mtu(),
+            tx_phy:        PhyKind::Le1M,
+            rx_phy:        PhyKind::Le1M,
+            conn_interval: None,
+            max_tx_octets: DEFAULT_DATA_LENGTH,
+            max_rx_octets: DEFAULT_DATA_LENGTH,
+        };
+        self.update_link(connection, subscriptions, link).await;
+
+        // Console input of the client, which may end mid line.
+        let mut console = Console::new();
+
         loop {
-            match connection.next().await {
+            let event = connection.next().await;
+            connections::record_activity();
+
+            match event {
                 GattConnectionEvent::Disconnected { reason } => {
                     defmt::debug!("[gatt] disconnected, ATT code: {}", reason);
+                    self.on_disconnected(reason);
                     break;
                 }
+                GattConnectionEvent::ConnectionParamsUpdated {
+                    conn_interval,
+                    peripheral_latency,
+                    supervision_timeout,
+                } => {
+                    defmt::info!(
+                        "[gatt] connection parameters updated, peer: {}, interval: {} us, \
+                         latency: {} events, supervision timeout: {} ms",
+                        peer_address,
+                        conn_interval.as_micros(),
+                        peripheral_latency,
+                        supervision_timeout.as_millis()
+                    );
+
+                    link.conn_interval = Some(conn_interval);
+
+                    if !CONNECTION_CONFIG.parameters.is_satisfied_by(conn_interval) {
+                        defmt::info!(
+                            "[gatt] connection interval not preferred, peer: {}, preferred: {} to \
+                             {} us",
+                            peer_address,
+                            CONNECTION_CONFIG.parameters.min_interval.as_micros(),
+                            CONNECTION_CONFIG.parameters.max_interval.as_micros()
+                        );
+                    }
+                }
+                GattConnectionEvent::PhyUpdated { tx_phy, rx_phy } => {
+                    defmt::info!(
+                        "[gatt] PHY updated, peer: {}, TX PHY: {}, RX PHY: {}",
+                        peer_address,
+                        tx_phy,
+                        rx_phy
+                    );
+
+                    link.tx_phy = tx_phy;
+                    link.rx_phy = rx_phy;
+                    self.update_link(connection, subscriptions, link).await;
+
+                    let preference = CONNECTION_CONFIG.phy;
+                    if !preference.is_satisfied_by(tx_phy) || !preference.is_satisfied_by(rx_phy) {
+                        defmt::warn!(
+                            "[gatt] PHY fell back from the preferred {} PHY, peer: {}",
+                            preference,
+                            peer_address
+                        );
+                    }
+                }
+                GattConnectionEvent::PairingComplete {
+                    security_level,
+                    bond,
+                } => {
+                    defmt::info!(
+                        "[gatt] paired, peer: {}, security level: {}, bonded: {}",
+                        peer_address,
+                        security_level,
+                        bond.is_some()
+                    );
+
+                    if let Some(bond) = bond {
+                        allow_list::add_bonded_peer(&bond, peer_address);
+                        bonds::store(bond, subscriptions);
+                    }
+                }
+                GattConnectionEvent::PassKeyDisplay(passkey) => {
+                    defmt::info!("[gatt] pairing passkey: {}", passkey);
+                }
+                GattConnectionEvent::PassKeyInput => {
+                    let result = match CONNECTION_CONFIG.pairing {
+                        PairingMode::FixedPasskey(passkey) => {
+                            connection.raw().pass_key_input(passkey)
+                        }
+                        PairingMode::JustWorks | PairingMode::DisplayedPasskey => {
+                            connection.raw().pass_key_cancel()
+                        }
+                    };
+
+                    if let Err(error) = result {
+                        defmt::warn!("[gatt] failed to answer the passkey request: {}", error);
+                    }
+                }
+                // Numeric comparison needs a display and a yes/no input.
+                GattConnectionEvent::PassKeyConfirm(_) => {
+                    if let Err(error) = connection.raw().pass_key_cancel() {
+                        defmt::warn!("[gatt] failed to cancel the passkey comparison: {}", error);
+                    }
+                }
+                GattConnectionEvent::PairingFailed(error) => {
+                    defmt::warn!(
+                        "[gatt] pairing failed, peer: {}, disconnecting: {}",
+                        peer_address,
+                        error
+                    );
+                    connection.raw().disconnect();
+                }
+                GattConnectionEvent::DataLengthUpdated {
+                    max_tx_octets,
+                    max_tx_time,
+                    max_rx_octets,
+                    max_rx_time,
+                } => {
+                    defmt::debug!(
+                        "[gatt] data length updated, peer: {}, TX: {} bytes in {} us, RX: {} \
+                         bytes in {} us",
+                        peer_address,
+                        max_tx_octets,
+                        max_tx_time,
+                        max_rx_octets,
+                        max_rx_time
+                    );
+
+                    link.max_tx_octets = max_tx_octets;
+                    link.max_rx_octets = max_rx_octets;
+                }
                 GattConnectionEvent::Gatt { event } => {
+                    let mut cccd_write = None;
+                    let mut console_input = None;
+                    let mut denied = self.check_access(connection, &event);
+
                     match &event {
+                        // Denied requests are answered without being acted on.
+                        GattEvent::Read(_) | GattEvent::Write(_) if denied.is_some() => {}
                         GattEvent::Read(read_event) => {
                             defmt::debug!("[gatt] read event for handle: {}", &read_event.handle());
+                            self.on_read(read_event.handle(), &link, rssi.get());
                         }
                         GattEvent::Write(write_event) => {
                             defmt::debug!(
                                 "[gatt] write event for handle: {}",
                                 &write_event.handle()
                             );
+                            cccd_write =
+                                self.on_cccd_write(write_event.handle(), write_event.data());
+                            if cccd_write.is_none() {
+                                denied = self.on_write(
+                                    connection,
+                                    write_event.handle(),
+                                    write_event.data(),
+                                );
+                            }
+                            if write_event.handle() == self.nus.rx.handle {
+                                console_input = NusValue::from_slice(write_event.data()).ok();
+                            }
+                        }
+                        // Requests the attribute table answers on its own, such
+                        // as service discovery and the ATT MTU exchange.
+                        GattEvent::Other(other_event) => {
+                            defmt::debug!(
+                                "[gatt] other event: {:?}",
+                                other_event.payload().incoming()
+                            );
                         }
-                        GattEvent::Other(_other_event) => {}
                     };
 
-                    match event.accept() {
-                        Ok(reply) => reply.send().await,
-                        Err(err) => defmt::warn!("[gatt] error sending response: {:?}", err),
+                    let reply = match denied {
+                        Some(error) => event.reject(error),
+                        None => event.accept(),
+                    };
+                    let accepted = match reply {
+                        Ok(reply) => {
+                            reply.send().await;
+                            denied.is_none()
+                        }
+                        Err(err) => {
+                            defmt::warn!("[gatt] error sending response: {:?}", err);
+                            false
+                        }
+                    };
+
+                    if let (true, Some(input)) = (accepted, console_input) {
+                        self.on_console_input(connection, subscriptions, &mut console, &input)
+                            .await;
+                    }
+
+                    // Notifications are only sent once the CCCD write enabling
+                    // them has been accepted.
+                    if let (true, Some((characteristic, subscribed))) = (accepted, cccd_write) {
+                        subscriptions.set(characteristic, subscribed);
+                        bonds::set_subscriptions(&peer_address.addr, subscriptions);
+                        if subscribed {
+                            self.on_subscribe(connection, subscriptions, characteristic, link)
+                                .await;
+                        }
                     }
                 }
                 _ => {}
             }
+
+            // The ATT MTU changes without an event of its own once the client
+            // exchanges it.
+            let att_mtu = connection.raw().att_mtu();
+            if att_mtu != link.att_mtu {
+                link.att_mtu = att_mtu;
+                self.update_link(connection, subscriptions, link).await;
+            }
+        }
+    }
+
+    /// Notify the `connection`'s client of the new state of its link if
+    /// subscribed. Reads are answered from the state of the reading
+    /// connection, see [`Self::on_read`].
+    async fn update_link<'gatt_server>(
+        &self,
+        connection: &GattConnection<'values, 'gatt_server, BlePacketPool>,
+        subscriptions: &Subscriptions,
+        link: Link,
+    ) {
+        let value = link.value();
+        if !subscriptions.is_subscribed(Notifying::Link) {
+            return;
+        }
+
+        if let Err(error) = self.link.link.notify(connection, &value).await {
+            defmt::debug!("[gatt] failed to notify the link: {}", error);
+        }
+    }
+
+    /// Returns the security required to access the attribute at `handle`.
+    ///
+    /// Every characteristic of the vendor, Battery, Immediate Alert, Link
+    /// Loss, Tx Power, Nordic UART, and Current Time services declares its
+    /// permissions here. Other attributes, such as the GAP and Device
+    /// Information services and the CCCDs, are open. The control point
+    /// requires an encrypted link if [`CONNECTION_CONFIG`] requires
+    /// encryption. Renaming the device, setting the clock, and the console
+    /// always require one.
+    fn permissions(&self, handle: u16) -> Permissions {
+        let control_point = if CONNECTION_CONFIG.require_encryption {
+            Permissions::WRITE_ENCRYPTED
+        } else {
+            Permissions::OPEN
+        };
+
+        let table = [
+            (self.control.control_point.handle, control_point),
+            (self.control.enabled_sensors.handle, Permissions::OPEN),
+            (self.control.configuration.handle, Permissions::OPEN),
+            (self.control.capabilities.handle, Permissions::OPEN),
+            // The name is persisted and advertised, a passer-by must not be
+            // able to change it.
+            (
+                self.control.device_name.handle,
+                Permissions::WRITE_ENCRYPTED,
+            ),
+            (self.motion.stationary_time.handle, Permissions::OPEN),
+            (self.motion.last_motion.handle, Permissions::OPEN),
+            (self.link.link.handle, Permissions::OPEN),
+            (self.link.rssi.handle, Permissions::OPEN),
+            (self.battery.level.handle, Permissions::OPEN),
+            (self.environmental.temperature.handle, Permissions::OPEN),
+            // Finding a lost device should not require pairing with it.
+            (self.immediate_alert.alert_level.handle, Permissions::OPEN),
+            (self.link_loss.alert_level.handle, Permissions::OPEN),
+            (self.tx_power.level.handle, Permissions::OPEN),
+            // The console can reset the device.
+            (self.nus.rx.handle, Permissions::WRITE_ENCRYPTED),
+            (self.nus.tx.handle, Permissions::OPEN),
+            // Timestamps are only as trustworthy as whoever set the clock.
+            (
+                self.current_time.current_time.handle,
+                Permissions::WRITE_ENCRYPTED,
+            ),
+        ];
+
+        table
+            .into_iter()
+            .find(|&(characteristic, _)| characteristic == handle)
+            .map_or(Permissions::OPEN, |(_, permissions)| permissions)
+    }
+
+    /// Returns the ATT error to answer `event` with if the security level of
+    /// the `connection` does not meet the security required to access the
+    /// attribute, or `None` if access is allowed.
+    fn check_access<'gatt_server>(
+        &self,
+        connection: &GattConnection<'values, 'gatt_server, BlePacketPool>,
+        event: &GattEvent<'values, 'gatt_server, BlePacketPool>,
+    ) -> Option<AttErrorCode> {
+        let (handle, required) = match event {
+            GattEvent::Read(read_event) => (
+                read_event.handle(),
+                self.permissions(read_event.handle()).read,
+            ),
+            GattEvent::Write(write_event) => (
+                write_event.handle(),
+                self.permissions(write_event.handle()).write,
+            ),
+            GattEvent::Other(_) => return None,
+        };
+
+        if required == Security::None {
+            return None;
+        }
+
+        let level = connection
+            .raw()
+            .security_level()
+            .unwrap_or(SecurityLevel::NoEncryption);
+        if required.is_met_by(level) {
+            return None;
         }
 
+        defmt::warn!(
+            "[gatt] denied access to handle: {}, requires {} security",
+            handle,
+            required
+        );
+        Some(required.att_error())
+    }
+
+    /// Refresh the value of characteristics computed on demand before a client
+    /// reads them.
+    ///
+    /// The link and RSSI characteristics are shared by every connection, so
+    /// they are set to the reading connection's `link` and `rssi` just before
+    /// its read is answered.
+    fn on_read(&self, handle: u16, link: &Link, rssi: i8) {
+        if handle == self.link.link.handle {
+            if let Err(error) = self.link.link.set(self, &link.value()) {
+                defmt::warn!("[gatt] failed to refresh the link: {}", error);
+            }
+        } else if handle == self.link.rssi.handle {
+            if let Err(error) = self.link.rssi.set(self, &rssi) {
+                defmt::warn!("[gatt] failed to refresh the RSSI: {}", error);
+            }
+        } else if handle == self.motion.stationary_time.handle && sensors::is_enabled(Sensor::Imu) {
+            let value = MotionService::stationary_time_value();
+            if let Err(error) = self.motion.stationary_time.set(self, &value) {
+                defmt::warn!("[gatt] failed to refresh the stationary time: {}", error);
+            }
+        } else if handle == self.motion.last_motion.handle {
+            // The wall clock may have been set since the motion was detected.
+            let value = MotionService::last_motion_value();
+            if let Err(error) = self.motion.last_motion.set(self, &value) {
+                defmt::warn!("[gatt] failed to refresh the last motion: {}", error);
+            }
+        } else if handle == self.control.configuration.handle {
+            self.refresh_configuration();
+        } else if handle == self.control.device_name.handle {
+            // The written name may have been truncated.
+            if let Some(device_name) = DeviceName::renamed() {
+                self.set_device_name(&device_name);
+            }
+        } else if handle == self.control.enabled_sensors.handle {
+            let value = sensors::enabled_mask();
+            if let Err(error) = self.control.enabled_sensors.set(self, &value) {
+                defmt::warn!("[gatt] failed to refresh the enabled sensors: {}", error);
+            }
+        } else if handle == self.battery.level.handle {
+            // Until the battery is first measured, or on a regulated supply,
+            // the default level is kept.
+            let Some(level) = battery::level() else {
+                return;
+            };
+
+            if let Err(error) = self.battery.level.set(self, &level) {
+                defmt::warn!("[gatt] failed to refresh the battery level: {}", error);
+            }
+        } else if handle == self.environmental.temperature.handle {
+            let value = EnvironmentalSensing::temperature_value();
+            if let Err(error) = self.environmental.temperature.set(self, &value) {
+                defmt::warn!("[gatt] failed to refresh the temperature: {}", error);
+            }
+        } else if handle == self.current_time.current_time.handle {
+            let value = CurrentTimeService::current_time_value();
+            if let Err(error) = self.current_time.current_time.set(self, &value) {
+                defmt::warn!("[gatt] failed to refresh the current time: {}", error);
+            }
+        } else if handle == self.tx_power.level.handle {
+            // Read from the same source as the advertised TX Power Level, so
+            // it follows the transmit power as it changes.
+            let level = advertise::tx_power_level();
+            if let Err(error) = self.tx_power.level.set(self, &level) {
+                defmt::warn!("[gatt] failed to refresh the transmit power: {}", error);
+            }
+        }
+    }
+
+    /// Set the device name characteristic to `device_name`.
+    fn set_device_name(&self, device_name: &str) {
+        // UNWRAP: Infallible. A device name is shorter than the characteristic.
+        let value = DeviceNameValue::from_slice(device_name.as_bytes()).unwrap();
+        if let Err(error) = self.control.device_name.set(self, &value) {
+            defmt::warn!("[gatt] failed to set the device name: {}", error);
+        }
+    }
+
+    /// Refresh the configuration characteristic from the current device
+    /// configuration. Unless a client renamed the device, the device name is
+    /// kept from the value set at startup.
+    fn refresh_configuration(&self) {
+        let renamed = DeviceName::renamed();
+        let result = self.control.configuration.get(self).and_then(|current| {
+            let device_name = renamed
+                .as_deref()
+                .unwrap_or(ControlService::configuration_device_name(&current));
+            let value = ControlService::encode_configuration(device_name, &config::get());
+            self.control.configuration.set(self, &value)
+        });
+
+        if let Err(error) = result {
+            defmt::warn!("[gatt] failed to refresh the configuration: {}", error);
+        }
+    }
+
+    /// Act on values written by a client to a characteristic. Accepting the
+    /// write commits the value to the attribute table.
+    ///
+    /// Returns the ATT error to reject the write with if the value is invalid
+    /// or the characteristic is not writable, or `None` to accept it.
+    fn on_write<'gatt_server>(
+        &self,
+        connection: &GattConnection<'values, 'gatt_server, BlePacketPool>,
+        handle: u16,
+        data: &[u8],
+    ) -> Option<AttErrorCode> {
+        if handle == self.control.control_point.handle {
+            let security_level = connection
+                .raw()
+                .security_level()
+                .unwrap_or(SecurityLevel::NoEncryption);
+            return self.control.process_command(data, security_level).err();
+        }
+
+        if handle == self.control.device_name.handle {
+            if data.len() > DEVICE_NAME_LENGTH {
+                defmt::warn!("[gatt] device name of {} bytes is too long", data.len());
+                return Some(AttErrorCode::INVALID_ATTRIBUTE_VALUE_LENGTH);
+            }
+
+            return match DeviceName::rename(data) {
+                Ok(_) => None,
+                Err(error) => {
+                    defmt::warn!("[gatt] invalid device name: {}", error);
+                    Some(AttErrorCode::VALUE_NOT_ALLOWED)
+                }
+            };
+        }
+
+        if handle == self.immediate_alert.alert_level.handle {
+            let &[value] = data else {
+                defmt::warn!("[gatt] alert level of {} bytes", data.len());
+                return Some(AttErrorCode::INVALID_ATTRIBUTE_VALUE_LENGTH);
+            };
+
+            return match AlertLevel::try_from(value) {
+                Ok(level) => {
+                    defmt::info!("[gatt] immediate alert: {}", level);
+                    indicator::alert(level);
+                    None
+                }
+                Err(value) => {
+                    defmt::warn!("[gatt] invalid alert level: {}", value);
+                    Some(AttErrorCode::VALUE_NOT_ALLOWED)
+                }
+            };
+        }
+
+        if handle == self.link_loss.alert_level.handle {
+            let &[value] = data else {
+                defmt::warn!("[gatt] link loss alert level of {} bytes", data.len());
+                return Some(AttErrorCode::INVALID_ATTRIBUTE_VALUE_LENGTH);
+            };
+
+            // Stored by the attribute table once the write is accepted.
+            return match AlertLevel::try_from(value) {
+                Ok(level) => {
+                    defmt::info!("[gatt] link loss alert level: {}", level);
+                    None
+                }
+                Err(value) => {
+                    defmt::warn!("[gatt] invalid link loss alert level: {}", value);
+                    Some(AttErrorCode::VALUE_NOT_ALLOWED)
+                }
+            };
+        }
+
+        if handle == self.current_time.current_time.handle {
+            if data.len() != CURRENT_TIME_LENGTH {
+                defmt::warn!("[gatt] current time of {} bytes", data.len());
+                return Some(AttErrorCode::INVALID_ATTRIBUTE_VALUE_LENGTH);
+            }
+
+            return match CurrentTimeService::decode(data) {
+                Some(now) => {
+                    wall_clock::set(now);
+                    None
+                }
+                None => {
+                    defmt::warn!("[gatt] invalid current time");
+                    Some(AttErrorCode::VALUE_NOT_ALLOWED)
+                }
+            };
+        }
+
+        if handle == self.nus.rx.handle {
+            if data.len() > NUS_LENGTH {
+                defmt::warn!("[gatt] console input of {} bytes is too long", data.len());
+                return Some(AttErrorCode::INVALID_ATTRIBUTE_VALUE_LENGTH);
+            }
+
+            // Processed once the write is accepted, see `on_console_input`.
+            return None;
+        }
+
+        // Every writable characteristic is handled above, and CCCD writes
+        // never reach here. Whatever else a client writes must not change.
+        defmt::warn!("[gatt] write to read only handle: {}", handle);
+        Some(AttErrorCode::WRITE_NOT_PERMITTED)
+    }
+
+    /// Alert at the Link Loss Service's alert level if the connection was
+    /// lost, rather than ended on purpose.
+    ///
+    /// A connection that times out, because the central stopped answering,
+    /// is lost: most likely the phone was carried out of range. A central
+    /// that disconnects, for example because the app was closed, reports
+    /// another reason and raises no alert.
+    fn on_disconnected(&self, reason: Status) {
+        if reason != Status::CONN_TIMEOUT {
+            return;
+        }
+
+        let level = self
+            .link_loss
+            .alert_level
+            .get(self)
+            .ok()
+            .and_then(|value| AlertLevel::try_from(value).ok())
+            .unwrap_or(AlertLevel::NoAlert);
+
+        if level != AlertLevel::NoAlert {
+            defmt::warn!("[gatt] connection lost, alerting: {}", level);
+            indicator::alert(level);
+        }
+    }
+
+    /// Feed `input` written to the Nordic UART Service's RX characteristic to
+    /// the `console`, answering each complete command line.
+    async fn on_console_input<'gatt_server>(
+        &self,
+        connection: &GattConnection<'values, 'gatt_server, BlePacketPool>,
+        subscriptions: &Subscriptions,
+        console: &mut Console,
+        input: &[u8],
+    ) {
+        for &byte in input {
+            let Some((command, response)) = console.push(byte) else {
+                continue;
+            };
+
+            defmt::info!("[gatt] console command: {}", command);
+            self.console_write(connection, subscriptions, response.as_bytes())
+                .await;
+
+            if command == Some(ConsoleCommand::Reset) {
+                system::reset().await;
+            }
+        }
+    }
+
+    /// Notify a subscribed client of console `output`, split into as many
+    /// notifications as the ATT MTU requires.
+    async fn console_write<'gatt_server>(
+        &self,
+        connection: &GattConnection<'values, 'gatt_server, BlePacketPool>,
+        subscriptions: &Subscriptions,
+        output: &[u8],
+    ) {
+        if !subscriptions.is_subscribed(Notifying::ConsoleOutput) {
+            return;
+        }
+
+        // A notification carries the ATT MTU minus its 3 byte header.
+        let payload_length = usize::from(connection.raw().att_mtu())
+            .saturating_sub(3)
+            .clamp(1, NUS_LENGTH);
+
+        for chunk in output.chunks(payload_length) {
+            // UNWRAP: Infallible. A chunk is at most NUS_LENGTH bytes.
+            let value = NusValue::from_slice(chunk).unwrap();
+            if let Err(error) = self.nus.tx.notify(connection, &value).await {
+                defmt::warn!("[gatt] failed to notify console output: {}", error);
+                return;
+            }
+        }
+    }
+
+    /// Returns each notifying characteristic with the handle of its CCCD.
+    fn notifying_characteristics(&self) -> [(Notifying, Option<u16>); 7] {
+        [
+            (
+                Notifying::StationaryTime,
+                self.motion.stationary_time.cccd_handle,
+            ),
+            (Notifying::LastMotion, self.motion.last_motion.cccd_handle),
+            (Notifying::Link, self.link.link.cccd_handle),
+            (Notifying::Rssi, self.link.rssi.cccd_handle),
+            (Notifying::BatteryLevel, self.battery.level.cccd_handle),
+            (
+                Notifying::Temperature,
+                self.environmental.temperature.cccd_handle,
+            ),
+            (Notifying::ConsoleOutput, self.nus.tx.cccd_handle),
+        ]
+    }
+
+    /// Returns the subscriptions of the `connection`'s client.
+    ///
+    /// The host keeps the CCCD values written by each client across its
+    /// connections. A bonded client's subscriptions are restored from them,
+    /// as the Core Specification requires. Those of any other client are
+    /// cleared, so it starts with no subscriptions.
+    ///
+    /// The host forgets the CCCD values on reset, so if it has none enabled
+    /// for a bonded client, those stored with its bond are restored into the
+    /// host first, see [`bonds::set_subscriptions`].
+    fn restore_subscriptions<'gatt_server>(
+        &self,
+        connection: &GattConnection<'values, 'gatt_server, BlePacketPool>,
+    ) -> Subscriptions {
+        let Some(cccds) = self.get_cccd_table(connection.raw()) else {
+            return Subscriptions::new();
+        };
+
+        let peer_address = connection.raw().peer_address();
+        let Some(stored) = bonds::subscriptions(&peer_address.addr) else {
+            let cleared = core::array::from_fn(|index| (cccds.inner()[index].0, CCCD::from(0)));
+            self.set_cccd_table(connection.raw(), CccdTable::new(cleared));
+            return Subscriptions::new();
+        };
+
+        let characteristics = self.notifying_characteristics();
+        let cccds = if cccds.inner().iter().all(|(_, cccd)| cccd.raw() == 0) {
+            let restored = CccdTable::new(core::array::from_fn(|index| {
+                let handle = cccds.inner()[index].0;
+                let subscribed = characteristics
+                    .iter()
+                    .any(|&(characteristic, cccd_handle)| {
+                        cccd_handle == Some(handle) && stored.is_subscribed(characteristic)
+                    });
+                (handle, CCCD::from(u16::from(subscribed)))
+            }));
+            self.set_cccd_table(connection.raw(), restored.clone());
+            restored
+        } else {
+            cccds
+        };
+
+        let subscriptions = Subscriptions::from_cccds(
+            &characteristics,
+            cccds
+                .inner()
+                .iter()
+                .map(|(handle, cccd)| (*handle, cccd.raw())),
+        );
         defmt::debug!(
-            "[gatt] connection event finished for handle: {}",
-            connection.raw().handle().raw()
+            "[gatt] subscriptions of bonded peer {} restored",
+            peer_address
+        );
+        subscriptions
+    }
+
+    /// Returns the notifying characteristic whose CCCD is at `handle`, and
+    /// whether `data` subscribes to its notifications.
+    fn on_cccd_write(&self, handle: u16, data: &[u8]) -> Option<(Notifying, bool)> {
+        let (characteristic, _) = self
+            .notifying_characteristics()
+            .into_iter()
+            .find(|&(_, cccd_handle)| cccd_handle == Some(handle))?;
+
+        // Bit 0 of the CCCD value enables notifications.
+        let subscribed = data.first().is_some_and(|flags| flags & 0x01 != 0);
+        defmt::info!(
+            "[gatt] client {} notifications of the {}",
+            if subscribed {
+                "subscribed to"
+            } else {
+                "unsubscribed from"
+            },
+            characteristic
         );
+
+        Some((characteristic, subscribed))
+    }
+
+    /// Notify a client that just subscribed to `characteristic` of its current
+    /// value, rather than leaving it waiting for the next change.
+    async fn on_subscribe<'gatt_server>(
+        &self,
+        connection: &GattConnection<'values, 'gatt_server, BlePacketPool>,
+        subscriptions: &Subscriptions,
+        characteristic: Notifying,
+        link: Link,
+    ) {
+        match characteristic {
+            Notifying::Link => self.update_link(connection, subscriptions, link).await,
+            Notifying::StationaryTime => {
+                if sensors::is_enabled(Sensor::Imu) {
+                    self.notify_stationary_time(connection).await;
+                }
+            }
+            Notifying::LastMotion => self.notify_last_motion(connection).await,
+            Notifying::BatteryLevel => {
+                self.notify_current_battery_level(connection).await;
+            }
+            Notifying::Temperature => {
+                self.notify_current_temperature(connection).await;
+            }
+            // The console only speaks when spoken to.
+            Notifying::ConsoleOutput => {}
+            // Notified once the RSSI is next read, see `rssi_task`.
+            Notifying::Rssi => {}
+        }
+    }
+
+    /// Notify the client of the current stationary time.
+    async fn notify_stationary_time<'gatt_server>(
+        &self,
+        connection: &GattConnection<'values, 'gatt_server, BlePacketPool>,
+    ) {
+        let value = MotionService::stationary_time_value();
+        if let Err(error) = self.motion.stationary_time.notify(connection, &value).await {
+            defmt::warn!("[gatt] failed to notify the stationary time: {}", error);
+        }
+    }
+
+    /// Notify the client of when motion was last detected.
+    async fn notify_last_motion<'gatt_server>(
+        &self,
+        connection: &GattConnection<'values, 'gatt_server, BlePacketPool>,
+    ) {
+        let value = MotionService::last_motion_value();
+        if let Err(error) = self.motion.last_motion.notify(connection, &value).await {
+            defmt::warn!("[gatt] failed to notify the last motion: {}", error);
+        }
+    }
+
+    /// Notify the client of the `connection` of a battery `level`, in percent
+    /// from 0 to 100.
+    pub async fn notify_battery<'gatt_server>(
+        &self,
+        connection: &GattConnection<'values, 'gatt_server, BlePacketPool>,
+        level: u8,
+    ) -> Result<(), NotifyError> {
+        if level > 100 {
+            return Err(NotifyError::InvalidValue);
+        }
+
+        self.battery.level.notify(connection, &level).await?;
+        Ok(())
+    }
+
+    /// Notify the client of the `connection` of a temperature, in hundredths
+    /// of a degree Celsius.
+    pub async fn notify_temperature<'gatt_server>(
+        &self,
+        connection: &GattConnection<'values, 'gatt_server, BlePacketPool>,
+        centi_celsius: i16,
+    ) -> Result<(), NotifyError> {
+        self.environmental
+            .temperature
+            .notify(connection, &centi_celsius)
+            .await?;
+        Ok(())
+    }
+
+    /// Notify the client of the current battery level, returning the level
+    /// notified. Nothing is notified before the battery is first measured.
+    async fn notify_current_battery_level<'gatt_server>(
+        &self,
+        connection: &GattConnection<'values, 'gatt_server, BlePacketPool>,
+    ) -> Option<u8> {
+        let level = battery::level()?;
+        match self.notify_battery(connection, level).await {
+            Ok(()) => Some(level),
+            Err(error) => {
+                defmt::warn!("[gatt] failed to notify the battery level: {}", error);
+                None
+            }
+        }
+    }
+
+    /// Notify the client of the current die temperature, returning the value
+    /// notified.
+    async fn notify_current_temperature<'gatt_server>(
+        &self,
+        connection: &GattConnection<'values, 'gatt_server, BlePacketPool>,
+    ) -> Option<i16> {
+        let value = EnvironmentalSensing::temperature_value();
+        match self.notify_temperature(connection, value).await {
+            Ok(()) => Some(value),
+            Err(error) => {
+                defmt::warn!("[gatt] failed to notify the temperature: {}", error);
+                None
+            }
+        }
+    }
+
+    /// Periodically notify subscribed clients of the stationary time, and of
+    /// the last motion and reset stationary time as soon as motion is
+    /// detected.
+    async fn notify_task<'gatt_server>(
+        &self,
+        connection: &GattConnection<'values, 'gatt_server, BlePacketPool>,
+        subscriptions: &Subscriptions,
+    ) {
+        let mut ticker = Ticker::every(STATIONARY_TIME_NOTIFY_INTERVAL);
+
+        // UNWRAP: Infallible. Each connection takes one receiver, and one is
+        // reserved for every connection.
+        let mut motion_detected = motion::MOTION_DETECTED.receiver().unwrap();
+        // Only motion detected during the connection is notified.
+        motion_detected.try_changed();
+
+        loop {
+            let moved = match select(ticker.next(), motion_detected.changed()).await {
+                Either::First(()) => false,
+                Either::Second(_) => true,
+            };
+
+            if moved && subscriptions.is_subscribed(Notifying::LastMotion) {
+                self.notify_last_motion(connection).await;
+            }
+
+            // The stationary time stays static while motion sensing is
+            // disabled, and only subscribed clients are notified of it.
+            if !sensors::is_enabled(Sensor::Imu)
+                || !subscriptions.is_subscribed(Notifying::StationaryTime)
+            {
+                continue;
+            }
+
+            self.notify_stationary_time(connection).await;
+        }
+    }
+
+    /// Request the preferred connection parameters from the central once the
+    /// connection is stable.
+    ///
+    /// Centrals generally pick a short interval suited to service discovery,
+    /// costing power for the rest of the connection. The central may refuse
+    /// the request, and the interval it settles on is reported by a
+    /// connection parameters updated event.
+    async fn request_connection_params<'gatt_server, C: Controller>(
+        stack: &Stack<'_, C, BlePacketPool>,
+        connection: &GattConnection<'values, 'gatt_server, BlePacketPool>,
+    ) {
+        Timer::after(CONNECTION_CONFIG.parameters_delay).await;
+
+        let parameters = CONNECTION_CONFIG.parameters;
+        match connection
+            .raw()
+            .update_connection_params(stack, &parameters.connect_params())
+            .await
+        {
+            Ok(()) => defmt::info!(
+                "[gatt] preferred connection parameters accepted, interval: {} to {} us, latency: \
+                 {} events, supervision timeout: {} ms",
+                parameters.min_interval.as_micros(),
+                parameters.max_interval.as_micros(),
+                parameters.peripheral_latency,
+                parameters.supervision_timeout.as_millis()
+            ),
+            Err(error) => {
+                defmt::warn!("[gatt] preferred connection parameters rejected: {}", error)
+            }
+        }
+    }
+
+    /// Returns the RSSI of `connection`, in dBm, as last measured by the
+    /// controller, or `None` if the controller has no measurement to report
+    /// yet.
+    pub async fn read_rssi<'gatt_server, C>(
+        stack: &Stack<'_, C, BlePacketPool>,
+        connection: &GattConnection<'values, 'gatt_server, BlePacketPool>,
+    ) -> Result<Option<i8>, BleHostError<C::Error>>
+    where
+        C: Controller + ControllerCmdSync<ReadRssi>,
+    {
+        let rssi = connection.raw().rssi(stack).await?;
+        Ok((rssi != RSSI_UNAVAILABLE).then_some(rssi))
+    }
+
+    /// Read the RSSI of the connection every [`RSSI_INTERVAL`] into `current`,
+    /// which its client's reads are answered from, and notify the client when
+    /// it changed.
+    async fn rssi_task<'gatt_server, C>(
+        &self,
+        stack: &Stack<'_, C, BlePacketPool>,
+        connection: &GattConnection<'values, 'gatt_server, BlePacketPool>,
+        subscriptions: &Subscriptions,
+        current: &Cell<i8>,
+    ) where
+        C: Controller + ControllerCmdSync<ReadRssi>,
+    {
+        let mut ticker = Ticker::every(RSSI_INTERVAL);
+        let mut notified = None;
+
+        loop {
+            ticker.next().await;
+
+            let rssi = match Self::read_rssi(stack, connection).await {
+                Ok(rssi) => rssi.unwrap_or(RSSI_UNAVAILABLE),
+                Err(error) => {
+                    defmt::debug!("[gatt] failed to read the RSSI: {}", error);
+                    continue;
+                }
+            };
+
+            current.set(rssi);
+
+            if !subscriptions.is_subscribed(Notifying::Rssi) {
+                // Notify the client of the current RSSI if it subscribes
+                // again.
+                notified = None;
+                continue;
+            }
+
+            if notified == Some(rssi) {
+                continue;
+            }
+
+            match self.link.rssi.notify(connection, &rssi).await {
+                Ok(()) => notified = Some(rssi),
+                Err(error) => defmt::debug!("[gatt] failed to notify the RSSI: {}", error),
+            }
+        }
+    }
+
+    /// Notify a subscribed client of the battery level each time it changes,
+    /// see [`BATTERY_LEVEL`].
+    async fn battery_notify_task<'gatt_server>(
+        &self,
+        connection: &GattConnection<'values, 'gatt_server, BlePacketPool>,
+        subscriptions: &Subscriptions,
+    ) {
+        // UNWRAP: Infallible. Each connection observes the level once, and an
+        // observer is reserved for every connection.
+        let mut level = BATTERY_LEVEL.observe().unwrap();
+        level
+            .notify_changes(&self.battery.level, connection, || {
+                subscriptions.is_subscribed(Notifying::BatteryLevel)
+            })
+            .await
+    }
+
+    /// Notify a subscribed client of the die temperature each time it is
+    /// measured, only when it changed since last notified or a temperature
+    /// alert was raised or cleared.
+    async fn temperature_notify_task<'gatt_server>(
+        &self,
+        connection: &GattConnection<'values, 'gatt_server, BlePacketPool>,
+        subscriptions: &Subscriptions,
+    ) {
+        let mut ticker = Ticker::every(thermal::CHECK_INTERVAL);
+        let mut notified = None;
+        let mut notified_alert = TemperatureAlert::None;
+
+        loop {
+            ticker.next().await;
+
+            if !subscriptions.is_subscribed(Notifying::Temperature) {
+                // Notify the client of the current temperature if it
+                // subscribes again.
+                notified = None;
+                continue;
+            }
+
+            let alert = thermal::alert();
+            if thermal::temperature().is_none()
+                || (Some(EnvironmentalSensing::temperature_value()) == notified
+                    && alert == notified_alert)
+            {
+                continue;
+            }
+
+            notified = self.notify_current_temperature(connection).await;
+            notified_alert = alert;
+        }
     }
 }
@@ -0,0 +1,87 @@
+// SPDX-FileCopyrightText: 2025 Derek Sauer
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Packet pool of the BLE host, sized by [`PACKET_MTU`] and [`PACKET_COUNT`]
+//! rather than the host's defaults, and tracking how many packets are in use so
+//! the sizing can be tuned.
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use trouble_host::packet_pool::{PacketRef, StaticPacketPool};
+use trouble_host::prelude::*;
+
+use super::{PACKET_COUNT, PACKET_MTU};
+
+/// Buffers of the packet pool.
+static POOL: StaticPacketPool<CriticalSectionRawMutex, PACKET_MTU, PACKET_COUNT> =
+    StaticPacketPool::new();
+
+/// Number of packets currently allocated.
+static IN_USE: AtomicUsize = AtomicUsize::new(0);
+
+/// Most packets allocated at once since boot.
+static HIGH_WATER_MARK: AtomicUsize = AtomicUsize::new(0);
+
+/// Packet pool used by the BLE host and controller.
+pub struct BlePacketPool;
+
+impl PacketPool for BlePacketPool {
+    type Packet = TrackedPacket;
+
+    const MTU: usize = PACKET_MTU;
+
+    fn allocate() -> Option<Self::Packet> {
+        let packet = POOL.alloc()?;
+
+        let in_use = IN_USE.fetch_add(1, Ordering::Relaxed) + 1;
+        HIGH_WATER_MARK.fetch_max(in_use, Ordering::Relaxed);
+
+        Some(TrackedPacket(packet))
+    }
+
+    fn capacity() -> usize {
+        PACKET_COUNT
+    }
+}
+
+/// Packet of the [`BlePacketPool`], counted as in use until dropped.
+pub struct TrackedPacket(PacketRef<PACKET_MTU>);
+
+impl AsRef<[u8]> for TrackedPacket {
+    fn as_ref(&self) -> &[u8] {
+        self.0.as_ref()
+    }
+}
+
+impl AsMut<[u8]> for TrackedPacket {
+    fn as_mut(&mut self) -> &mut [u8] {
+        self.0.as_mut()
+    }
+}
+
+impl Packet for TrackedPacket {}
+
+impl Drop for TrackedPacket {
+    fn drop(&mut self) {
+        IN_USE.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Log how many packets of the pool are in use, and the most that have been
+/// at once. A high water mark reaching the pool's capacity means transfers
+/// stalled waiting for a free packet.
+pub fn log_usage() {
+    let high_water_mark = HIGH_WATER_MARK.load(Ordering::Relaxed);
+    defmt::debug!(
+        "[ble] packet pool: {} of {} packets in use, high water mark: {}",
+        IN_USE.load(Ordering::Relaxed),
+        PACKET_COUNT,
+        high_water_mark
+    );
+
+    if high_water_mark >= PACKET_COUNT {
+        defmt::warn!("[ble] packet pool exhausted, consider raising its packet count");
+    }
+}
@@ -0,0 +1,66 @@
+// SPDX-FileCopyrightText: 2025 Derek Sauer
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Security a connection must have established to access characteristics.
+//!
+//! Each characteristic declares the security required to read and write it,
+//! and the GATT server enforces it against the connection's current security
+//! level before acting on a request.
+
+use trouble_host::prelude::*;
+
+/// Security a connection must have established to access a characteristic.
+#[derive(Clone, Copy, PartialEq, defmt::Format)]
+pub enum Security {
+    /// Any connection.
+    None,
+
+    /// An encrypted connection, such as one paired with Just Works.
+    Encrypted,
+
+    /// An encrypted connection paired with protection against
+    /// man-in-the-middle attacks, such as passkey entry.
+    Authenticated,
+}
+
+impl Security {
+    /// Returns `true` if a connection at `level` meets this requirement.
+    pub fn is_met_by(self, level: SecurityLevel) -> bool {
+        match self {
+            Security::None => true,
+            Security::Encrypted => !matches!(level, SecurityLevel::NoEncryption),
+            Security::Authenticated => matches!(level, SecurityLevel::EncryptedAuthenticated),
+        }
+    }
+
+    /// ATT error answering a request on a connection that does not meet this
+    /// requirement. Prompts the client to pair, or pair again with stronger
+    /// security.
+    pub fn att_error(self) -> AttErrorCode {
+        match self {
+            Security::Authenticated => AttErrorCode::INSUFFICIENT_AUTHENTICATION,
+            Security::None | Security::Encrypted => AttErrorCode::INSUFFICIENT_ENCRYPTION,
+        }
+    }
+}
+
+/// Security required to read and write a characteristic.
+#[derive(Clone, Copy)]
+pub struct Permissions {
+    pub read:  Security,
+    pub write: Security,
+}
+
+impl Permissions {
+    /// Readable and writable on any connection.
+    pub const OPEN: Self = Self {
+        read:  Security::None,
+        write: Security::None,
+    };
+    /// Readable on any connection, writable only over an encrypted link.
+    pub const WRITE_ENCRYPTED: Self = Self {
+        read:  Security::None,
+        write: Security::Encrypted,
+    };
+}
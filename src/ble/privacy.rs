@@ -0,0 +1,108 @@
+// SPDX-FileCopyrightText: 2025 Derek Sauer
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Resolvable private addresses, keeping scanners from tracking the device by
+//! its address.
+//!
+//! While enabled, the device advertises from a resolvable private address
+//! (RPA) generated from its identity resolving key (IRK), replaced every
+//! [`AdvertisingConfig::address_rotation`](super::advertise::AdvertisingConfig::address_rotation).
+//! A peer holding the IRK resolves each new address to the device, anyone else
+//! sees an unrelated address each time.
+//!
+//! The address only changes between advertising sessions, as the controller
+//! refuses a new random address while advertising. Long range advertising uses
+//! extended advertising sets, which keep the static identity address.
+//!
+//! The allow list filters the addresses of peers, not the device's own, so it
+//! is unaffected. Peers can only recognize the device across rotations if they
+//! received its IRK while bonding, which the BLE host does not support: it is
+//! only told of the identity address when the stack is built, and pairs from
+//! that address whatever is advertised. Rotation is therefore left disabled
+//! on devices that are paired.
+
+use core::cell::{Cell, RefCell};
+
+use bt_hci::cmd::le::{LeEncrypt, LeSetRandomAddr};
+use bt_hci::controller::ControllerCmdSync;
+use bt_hci::param::BdAddr;
+use embassy_sync::blocking_mutex::Mutex;
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_time::{Duration, Instant};
+use rand_chacha::ChaChaRng;
+use rand_core::RngCore;
+use trouble_host::prelude::*;
+
+use super::BlePacketPool;
+
+/// Identity resolving key of the device, and the random number generator
+/// drawing the random part of each address, once initialized.
+static KEYS: Mutex<CriticalSectionRawMutex, RefCell<Option<([u8; 16], ChaChaRng)>>> =
+    Mutex::new(RefCell::new(None));
+
+/// When the current resolvable private address was generated, or `None` if
+/// the device still uses its identity address.
+static ROTATED_AT: Mutex<CriticalSectionRawMutex, Cell<Option<Instant>>> =
+    Mutex::new(Cell::new(None));
+
+/// Set the device's identity resolving key, in little endian byte order, and
+/// seed the random number generator drawing the random part of each address.
+pub fn init(irk: [u8; 16], rng: ChaChaRng) {
+    KEYS.lock(|keys| *keys.borrow_mut() = Some((irk, rng)));
+}
+
+/// Switch to a new resolvable private address if the current one is older
+/// than `rotation`, or the device still uses its identity address. Must only
+/// be called while not advertising.
+pub async fn rotate_if_due<C>(
+    stack: &Stack<'_, C, BlePacketPool>,
+    rotation: Duration,
+) -> Result<(), BleHostError<C::Error>>
+where
+    C: Controller + ControllerCmdSync<LeEncrypt> + ControllerCmdSync<LeSetRandomAddr>,
+{
+    let due = ROTATED_AT.lock(|rotated_at| {
+        rotated_at
+            .get()
+            .is_none_or(|rotated_at| rotated_at.elapsed() >= rotation)
+    });
+    if !due {
+        return Ok(());
+    }
+
+    let Some((irk, prand)) = KEYS.lock(|keys| {
+        keys.borrow_mut()
+            .as_mut()
+            .map(|(irk, rng)| (*irk, random_part(rng)))
+    }) else {
+        defmt::warn!("[privacy] no identity resolving key, keeping the identity address");
+        return Ok(());
+    };
+
+    // The hash is the lowest 24 bits of the IRK encrypting the random part,
+    // zero padded to 128 bits.
+    let mut plaintext = [0; 16];
+    plaintext[..3].copy_from_slice(&prand);
+    let hash = stack.command(LeEncrypt::new(irk, plaintext)).await?;
+
+    let mut address = [0; 6];
+    address[..3].copy_from_slice(&hash[..3]);
+    address[3..].copy_from_slice(&prand);
+    let address = BdAddr::new(address);
+
+    stack.command(LeSetRandomAddr::new(address)).await?;
+    ROTATED_AT.lock(|rotated_at| rotated_at.set(Some(Instant::now())));
+    defmt::debug!("[privacy] advertising from {}", address);
+
+    Ok(())
+}
+
+/// Returns the little endian random part of a resolvable private address: 22
+/// random bits below the `0b01` marking the address as resolvable.
+fn random_part(rng: &mut ChaChaRng) -> [u8; 3] {
+    let mut prand = [0; 3];
+    rng.fill_bytes(&mut prand);
+    prand[2] = (prand[2] & 0x3f) | 0x40;
+    prand
+}
@@ -2,4 +2,102 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+use trouble_host::attribute::CharacteristicProp;
+use trouble_host::prelude::Uuid;
+
+pub mod battery;
+pub mod control;
+pub mod current_time;
 pub mod device_information;
+pub mod environmental_sensing;
+pub mod gap;
+pub mod immediate_alert;
+pub mod link;
+pub mod link_loss;
+pub mod motion;
+pub mod nus;
+pub mod observable;
+pub mod tx_power;
+
+/// Base of the 128-bit UUIDs assigned to Lookpoint's vendor specific services
+/// and characteristics, `4c50xxxx-7a3d-4c6e-9f1b-2c9e5d4a8b10`, in little
+/// endian byte order.
+const VENDOR_UUID_BASE: [u8; 16] = [
+    0x10, 0x8b, 0x4a, 0x5d, 0x9e, 0x2c, 0x1b, 0x9f, 0x6e, 0x4c, 0x3d, 0x7a, 0x00, 0x00, 0x50, 0x4c,
+];
+
+/// Build a vendor specific 128-bit UUID by substituting `short` for the `xxxx`
+/// in [`VENDOR_UUID_BASE`].
+pub const fn vendor_uuid(short: u16) -> Uuid {
+    let mut uuid = VENDOR_UUID_BASE;
+    let short = short.to_le_bytes();
+
+    uuid[12] = short[0];
+    uuid[13] = short[1];
+
+    Uuid::new_long(uuid)
+}
+
+/// Properties of a characteristic clients can only read.
+pub const READ: &[CharacteristicProp] = &[CharacteristicProp::Read];
+
+/// Properties of a characteristic clients can read and subscribe to.
+pub const READ_NOTIFY: &[CharacteristicProp] =
+    &[CharacteristicProp::Read, CharacteristicProp::Notify];
+
+/// Properties of a characteristic clients can read and write.
+pub const READ_WRITE: &[CharacteristicProp] =
+    &[CharacteristicProp::Read, CharacteristicProp::Write];
+
+/// Properties of a characteristic clients can only write.
+pub const WRITE: &[CharacteristicProp] = &[CharacteristicProp::Write];
+
+/// Properties of a characteristic clients can only write, without a response.
+pub const WRITE_WITHOUT_RESPONSE: &[CharacteristicProp] =
+    &[CharacteristicProp::WriteWithoutResponse];
+
+/// Returns `true` if a characteristic with `properties` requires a Client
+/// Characteristic Configuration Descriptor (CCCD).
+const fn has_cccd(properties: &[CharacteristicProp]) -> bool {
+    let mut index = 0;
+    while index < properties.len() {
+        if matches!(
+            properties[index],
+            CharacteristicProp::Notify | CharacteristicProp::Indicate
+        ) {
+            return true;
+        }
+        index += 1;
+    }
+
+    false
+}
+
+/// Returns the number of attributes a service adds to the attribute table,
+/// given the properties of each of its characteristics.
+///
+/// The service declaration adds one attribute. Each characteristic adds two,
+/// its declaration and value, plus its CCCD if it notifies or indicates.
+pub const fn attribute_count(characteristics: &[&[CharacteristicProp]]) -> usize {
+    let mut count = 1;
+    let mut index = 0;
+    while index < characteristics.len() {
+        count += 2 + has_cccd(characteristics[index]) as usize;
+        index += 1;
+    }
+
+    count
+}
+
+/// Returns the number of CCCDs a service adds to the attribute table, given
+/// the properties of each of its characteristics.
+pub const fn cccd_count(characteristics: &[&[CharacteristicProp]]) -> usize {
+    let mut count = 0;
+    let mut index = 0;
+    while index < characteristics.len() {
+        count += has_cccd(characteristics[index]) as usize;
+        index += 1;
+    }
+
+    count
+}
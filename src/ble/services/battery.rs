@@ -0,0 +1,68 @@
+// SPDX-FileCopyrightText: 2025 Derek Sauer
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use bt_hci::uuid::{BluetoothUuid16, characteristic, service};
+use static_cell::StaticCell;
+use trouble_host::attribute::{AttributeTable, Characteristic, CharacteristicProp, Service};
+
+use super::observable::ObservableValue;
+use super::{READ_NOTIFY, attribute_count, cccd_count};
+
+/// Battery level reported until the battery is first measured, and while the
+/// device runs from a regulated supply whose charge cannot be estimated.
+pub const DEFAULT_BATTERY_LEVEL: u8 = 100;
+
+/// Latest battery level, in percent. Set each time the battery is measured,
+/// subscribed clients are notified only when it changed.
+pub static BATTERY_LEVEL: ObservableValue<u8> = ObservableValue::new();
+
+/// The Battery Service exposes the charge level of the battery powering the
+/// device.
+#[allow(dead_code)]
+pub struct BatteryService {
+    /// The Battery Level characteristic is the current charge level of the
+    /// battery, in percent from 0 to 100.
+    pub level: Characteristic<u8>,
+
+    handle: u16,
+}
+
+impl BatteryService {
+    /// Attributes added to the attribute table, derived from the
+    /// characteristics of the service.
+    pub const ATTRIBUTE_COUNT: usize = attribute_count(&Self::CHARACTERISTICS);
+    /// BLE 16-bit UUID assigned to the Battery service.
+    pub const BLE_UUID16: BluetoothUuid16 = service::BATTERY;
+    /// The battery level characteristic notifies and requires a Client
+    /// Characteristic Configuration Descriptor (CCCD).
+    pub const CCCD_COUNT: usize = cccd_count(&Self::CHARACTERISTICS);
+    /// Properties of each characteristic of the service.
+    const CHARACTERISTICS: [&[CharacteristicProp]; 1] = [READ_NOTIFY];
+
+    pub fn new<MUTEX, const MAX_ATTRIBUTES: usize>(
+        attributes_table: &mut AttributeTable<'_, MUTEX, MAX_ATTRIBUTES>,
+    ) -> Self
+    where
+        MUTEX: embassy_sync::blocking_mutex::raw::RawMutex,
+    {
+        let mut service = attributes_table.add_service(Service::new(service::BATTERY));
+
+        let level = {
+            static STORE: StaticCell<[u8; 1]> = StaticCell::new();
+            service
+                .add_characteristic(
+                    characteristic::BATTERY_LEVEL,
+                    READ_NOTIFY,
+                    DEFAULT_BATTERY_LEVEL,
+                    STORE.init([0; 1]),
+                )
+                .build()
+        };
+
+        Self {
+            handle: service.build(),
+            level,
+        }
+    }
+}
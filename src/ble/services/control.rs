@@ -0,0 +1,482 @@
+// SPDX-FileCopyrightText: 2025 Derek Sauer
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use embassy_time::Duration;
+use static_cell::StaticCell;
+use trouble_host::attribute::{AttributeTable, Characteristic, CharacteristicProp, Service};
+use trouble_host::prelude::{AttErrorCode, SecurityLevel, Uuid};
+
+use super::{READ, READ_WRITE, WRITE, attribute_count, cccd_count, vendor_uuid};
+use crate::battery::BatteryChemistry;
+use crate::ble::allow_list;
+use crate::ble::beacon::{BeaconIdentity, EddystoneUidIdentity, IBeaconIdentity};
+use crate::ble::connection_params::CONNECTION_CONFIG;
+use crate::ble::device_name::MAX_LOCAL_NAME_LENGTH;
+use crate::ble::permissions::Security;
+use crate::config::DeviceConfig;
+use crate::{capabilities, config, indicator, sensors, settings, system, thermal};
+
+/// Largest command accepted by the control point: an opcode followed by its
+/// parameters. Commands longer than 20 bytes require the central to negotiate
+/// a larger ATT MTU.
+pub const CONTROL_POINT_LENGTH: usize = 24;
+
+/// Value written to the control point characteristic.
+pub type ControlPointValue = heapless::Vec<u8, CONTROL_POINT_LENGTH>;
+
+/// Version of the configuration characteristic's layout, incremented whenever
+/// it changes.
+const CONFIGURATION_FORMAT_VERSION: u8 = 2;
+
+/// Offset of the device name in the configuration characteristic.
+const CONFIGURATION_NAME_OFFSET: usize = 12 + BeaconIdentity::ENCODED_LENGTH;
+
+/// Largest value of the configuration characteristic.
+pub const CONFIGURATION_LENGTH: usize = CONFIGURATION_NAME_OFFSET + MAX_LOCAL_NAME_LENGTH;
+
+/// Value of the configuration characteristic.
+pub type ConfigurationValue = heapless::Vec<u8, CONFIGURATION_LENGTH>;
+
+/// Longest name accepted by the device name characteristic, truncated to
+/// [`MAX_LOCAL_NAME_LENGTH`] once written.
+pub const DEVICE_NAME_LENGTH: usize = settings::MAX_VALUE_LENGTH;
+
+/// Value of the device name characteristic.
+pub type DeviceNameValue = heapless::Vec<u8, DEVICE_NAME_LENGTH>;
+
+/// Parameters confirming a factory reset, so a stray write cannot wipe the
+/// device.
+const FACTORY_RESET_KEY: [u8; 4] = *b"WIPE";
+
+/// How long the device identifies itself when the identify command does not
+/// specify a duration.
+const DEFAULT_IDENTIFY_DURATION: Duration = Duration::from_secs(5);
+
+/// Commands accepted by the control point. Each is the first byte of a write to
+/// the control point characteristic.
+#[derive(Clone, Copy, defmt::Format)]
+#[repr(u8)]
+pub enum Opcode {
+    /// Conspicuously blink the status LED so a specific unit can be located
+    /// among many. Optionally followed by a one byte duration in seconds.
+    Identify              = 0x01,
+
+    /// Provision the device's iBeacon identity. Followed by the 16 byte
+    /// proximity UUID, the little endian major and minor, and the measured
+    /// power at 1 m in dBm.
+    ProvisionIBeacon      = 0x02,
+
+    /// Provision the device's Eddystone-UID identity. Followed by the 10 byte
+    /// namespace, the 6 byte instance, and the calibrated transmit power at 0 m
+    /// in dBm.
+    ProvisionEddystoneUid = 0x03,
+
+    /// Enable or disable the sampling of individual sensors. Followed by a one
+    /// byte mask of the [`Sensor`](sensors::Sensor)s to enable.
+    SetEnabledSensors     = 0x04,
+
+    /// Select the chemistry of the cell powering the device. Followed by a one
+    /// byte [`BatteryChemistry`].
+    SetBatteryChemistry   = 0x05,
+
+    /// Erase the configuration, settings, allow list, and bonds, then reset
+    /// the device. Followed by [`FACTORY_RESET_KEY`]. Requires an encrypted
+    /// link.
+    FactoryReset          = 0x06,
+
+    /// Set the calibration offset added to the die temperature. Followed by
+    /// the little endian `i16` offset in hundredths of a degree Celsius.
+    SetTemperatureOffset  = 0x07,
+
+    /// Set the temperature alert thresholds. Followed by the little endian
+    /// `i16` high then low thresholds in hundredths of a degree Celsius,
+    /// `0x7fff` and `0x8000` disabling either alert.
+    SetTemperatureAlerts  = 0x08,
+
+    /// Reset into the bootloader's DFU mode to update the firmware over the
+    /// air. Requires an encrypted link, and a build with the `dfu` feature.
+    EnterBootloader       = 0x09,
+
+    /// Enforce the allow list, or let any central connect, until the next
+    /// reset. Followed by one byte, non-zero to enforce. Requires an encrypted
+    /// link.
+    SetAllowListEnforced  = 0x0a,
+
+    /// Forget every peer on the allow list, letting any central connect and
+    /// bond until one is allowed again. Requires an encrypted link.
+    ClearAllowList        = 0x0b,
+}
+
+impl TryFrom<u8> for Opcode {
+    type Error = u8;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0x01 => Ok(Self::Identify),
+            0x02 => Ok(Self::ProvisionIBeacon),
+            0x03 => Ok(Self::ProvisionEddystoneUid),
+            0x04 => Ok(Self::SetEnabledSensors),
+            0x05 => Ok(Self::SetBatteryChemistry),
+            0x06 => Ok(Self::FactoryReset),
+            0x07 => Ok(Self::SetTemperatureOffset),
+            0x08 => Ok(Self::SetTemperatureAlerts),
+            0x09 => Ok(Self::EnterBootloader),
+            0x0a => Ok(Self::SetAllowListEnforced),
+            0x0b => Ok(Self::ClearAllowList),
+            _ => Err(value),
+        }
+    }
+}
+
+/// Lookpoint's vendor specific control service. Centrals write commands to its
+/// control point characteristic to act on the device.
+#[allow(dead_code)]
+pub struct ControlService {
+    /// Write only characteristic receiving an [`Opcode`] followed by its
+    /// parameters.
+    pub control_point: Characteristic<ControlPointValue>,
+
+    /// Read only characteristic reporting the mask of enabled
+    /// [`Sensor`](sensors::Sensor)s.
+    pub enabled_sensors: Characteristic<u8>,
+
+    /// Read only characteristic reporting the effective device configuration,
+    /// see [`ControlService::encode_configuration`]. Longer than the default
+    /// ATT MTU, centrals read it with long reads.
+    pub configuration: Characteristic<ConfigurationValue>,
+
+    /// Read only characteristic reporting the unit's
+    /// [`Capability`](capabilities::Capability) mask, as a little endian
+    /// `u16`. Computed at boot.
+    pub capabilities: Characteristic<u16>,
+
+    /// Characteristic reading the device name, UTF-8 encoded, and renaming
+    /// the device when written. The new name is saved and advertised once
+    /// advertising restarts. Writes require an encrypted link.
+    pub device_name: Characteristic<DeviceNameValue>,
+
+    handle: u16,
+}
+
+impl ControlService {
+    /// Attributes added to the attribute table, derived from the
+    /// characteristics of the service.
+    pub const ATTRIBUTE_COUNT: usize = attribute_count(&Self::CHARACTERISTICS);
+    /// Vendor specific 128-bit UUID of the capabilities characteristic.
+    pub const CAPABILITIES_UUID: Uuid = vendor_uuid(0x0005);
+    /// Write and read only attributes do not require Client Characteristic
+    /// Configuration Descriptors (CCCD).
+    pub const CCCD_COUNT: usize = cccd_count(&Self::CHARACTERISTICS);
+    /// Properties of each characteristic of the service.
+    const CHARACTERISTICS: [&[CharacteristicProp]; 5] = [WRITE, READ, READ, READ, READ_WRITE];
+    /// Vendor specific 128-bit UUID of the configuration characteristic.
+    pub const CONFIGURATION_UUID: Uuid = vendor_uuid(0x0004);
+    /// Vendor specific 128-bit UUID of the control point characteristic.
+    pub const CONTROL_POINT_UUID: Uuid = vendor_uuid(0x0002);
+    /// Vendor specific 128-bit UUID of the device name characteristic.
+    pub const DEVICE_NAME_UUID: Uuid = vendor_uuid(0x0006);
+    /// Vendor specific 128-bit UUID of the enabled sensors characteristic.
+    pub const ENABLED_SENSORS_UUID: Uuid = vendor_uuid(0x0003);
+    /// Vendor specific 128-bit UUID of the control service.
+    pub const SERVICE_UUID: Uuid = vendor_uuid(0x0001);
+
+    pub fn new<MUTEX, const MAX_ATTRIBUTES: usize>(
+        attributes_table: &mut AttributeTable<'_, MUTEX, MAX_ATTRIBUTES>,
+    ) -> Self
+    where
+        MUTEX: embassy_sync::blocking_mutex::raw::RawMutex,
+    {
+        let mut service = attributes_table.add_service(Service::new(Self::SERVICE_UUID));
+
+        let control_point = {
+            static STORE: StaticCell<[u8; CONTROL_POINT_LENGTH]> = StaticCell::new();
+            service
+                .add_characteristic(
+                    Self::CONTROL_POINT_UUID,
+                    WRITE,
+                    ControlPointValue::new(),
+                    STORE.init([0; CONTROL_POINT_LENGTH]),
+                )
+                .build()
+        };
+
+        let enabled_sensors = {
+            static STORE: StaticCell<[u8; 1]> = StaticCell::new();
+            service
+                .add_characteristic(
+                    Self::ENABLED_SENSORS_UUID,
+                    READ,
+                    sensors::enabled_mask(),
+                    STORE.init([0; 1]),
+                )
+                .build()
+        };
+
+        let configuration = {
+            static STORE: StaticCell<[u8; CONFIGURATION_LENGTH]> = StaticCell::new();
+            service
+                .add_characteristic(
+                    Self::CONFIGURATION_UUID,
+                    READ,
+                    ConfigurationValue::new(),
+                    STORE.init([0; CONFIGURATION_LENGTH]),
+                )
+                .build()
+        };
+
+        let capabilities = {
+            static STORE: StaticCell<[u8; 2]> = StaticCell::new();
+            service
+                .add_characteristic(
+                    Self::CAPABILITIES_UUID,
+                    READ,
+                    capabilities::capabilities(),
+                    STORE.init([0; 2]),
+                )
+                .build()
+        };
+
+        let device_name = {
+            static STORE: StaticCell<[u8; DEVICE_NAME_LENGTH]> = StaticCell::new();
+            service
+                .add_characteristic(
+                    Self::DEVICE_NAME_UUID,
+                    READ_WRITE,
+                    DeviceNameValue::new(),
+                    STORE.init([0; DEVICE_NAME_LENGTH]),
+                )
+                .build()
+        };
+
+        Self {
+            handle: service.build(),
+            control_point,
+            enabled_sensors,
+            configuration,
+            capabilities,
+            device_name,
+        }
+    }
+
+    /// Execute a command written to the control point over a connection at
+    /// `security_level`.
+    ///
+    /// Returns the ATT error to reject the write with if the command is
+    /// empty, has an unknown opcode, has invalid parameters, or requires more
+    /// security than the connection has.
+    pub fn process_command(
+        &self,
+        command: &[u8],
+        security_level: SecurityLevel,
+    ) -> Result<(), AttErrorCode> {
+        let Some((&opcode, parameters)) = command.split_first() else {
+            defmt::warn!("[control] empty command written to the control point");
+            return Err(AttErrorCode::VALUE_NOT_ALLOWED);
+        };
+
+        match Opcode::try_from(opcode) {
+            Ok(Opcode::Identify) => {
+                let duration = match parameters.first() {
+                    Some(&seconds) => Duration::from_secs(u64::from(seconds)),
+                    None => DEFAULT_IDENTIFY_DURATION,
+                };
+
+                defmt::info!("[control] identifying for {} s", duration.as_secs());
+                indicator::identify(duration);
+            }
+            Ok(Opcode::ProvisionIBeacon) => match IBeaconIdentity::from_bytes(parameters) {
+                Ok(identity) => Self::provision_beacon(BeaconIdentity::IBeacon(identity)),
+                Err(error) => {
+                    defmt::warn!("[control] invalid iBeacon identity: {}", error);
+                    return Err(AttErrorCode::VALUE_NOT_ALLOWED);
+                }
+            },
+            Ok(Opcode::ProvisionEddystoneUid) => {
+                match EddystoneUidIdentity::from_bytes(parameters) {
+                    Ok(identity) => Self::provision_beacon(BeaconIdentity::EddystoneUid(identity)),
+                    Err(error) => {
+                        defmt::warn!("[control] invalid Eddystone identity: {}", error);
+                        return Err(AttErrorCode::VALUE_NOT_ALLOWED);
+                    }
+                }
+            }
+            Ok(Opcode::SetEnabledSensors) => match parameters.first() {
+                Some(&mask) => {
+                    defmt::info!("[control] enabled sensors: {:#04x}", mask);
+                    sensors::set_enabled_mask(mask);
+
+                    let mask = sensors::enabled_mask();
+                    if let Err(error) = config::update(|config| config.enabled_sensors = mask) {
+                        defmt::error!("[control] failed to persist the enabled sensors: {}", error);
+                    }
+                }
+                None => {
+                    defmt::warn!("[control] enabled sensors command without a mask");
+                    return Err(AttErrorCode::VALUE_NOT_ALLOWED);
+                }
+            },
+            Ok(Opcode::SetBatteryChemistry) => {
+                match parameters
+                    .first()
+                    .map(|&chemistry| BatteryChemistry::try_from(chemistry))
+                {
+                    Some(Ok(chemistry)) => {
+                        defmt::info!("[control] battery chemistry: {}", chemistry);
+                        if let Err(error) =
+                            config::update(|config| config.battery_chemistry = chemistry)
+                        {
+                            defmt::error!(
+                                "[control] failed to persist the battery chemistry: {}",
+                                error
+                            );
+                        }
+                    }
+                    Some(Err(chemistry)) => {
+                        defmt::warn!("[control] unknown battery chemistry: {}", chemistry);
+                        return Err(AttErrorCode::VALUE_NOT_ALLOWED);
+                    }
+                    None => {
+                        defmt::warn!("[control] battery chemistry command without a chemistry");
+                        return Err(AttErrorCode::VALUE_NOT_ALLOWED);
+                    }
+                }
+            }
+            Ok(Opcode::FactoryReset) => {
+                if !Security::Encrypted.is_met_by(security_level) {
+                    defmt::warn!("[control] factory reset denied, the link is not encrypted");
+                    return Err(Security::Encrypted.att_error());
+                }
+
+                if parameters != FACTORY_RESET_KEY {
+                    defmt::warn!("[control] factory reset without the confirmation key");
+                    return Err(AttErrorCode::VALUE_NOT_ALLOWED);
+                }
+
+                system::request_factory_reset();
+            }
+            Ok(Opcode::SetTemperatureOffset) => match parameters.try_into() {
+                Ok(offset) => {
+                    if let Err(error) = thermal::set_calibration_offset(i16::from_le_bytes(offset))
+                    {
+                        defmt::error!(
+                            "[control] failed to persist the temperature offset: {}",
+                            error
+                        );
+                    }
+                }
+                Err(_) => {
+                    defmt::warn!("[control] temperature offset command without an offset");
+                    return Err(AttErrorCode::VALUE_NOT_ALLOWED);
+                }
+            },
+            Ok(Opcode::SetTemperatureAlerts) => match parameters {
+                &[high_low, high_high, low_low, low_high] => {
+                    let high = i16::from_le_bytes([high_low, high_high]);
+                    let low = i16::from_le_bytes([low_low, low_high]);
+                    if let Err(error) = thermal::set_alert_thresholds(high, low) {
+                        defmt::error!(
+                            "[control] failed to persist the temperature alerts: {}",
+                            error
+                        );
+                    }
+                }
+                _ => {
+                    defmt::warn!("[control] temperature alerts command without thresholds");
+                    return Err(AttErrorCode::VALUE_NOT_ALLOWED);
+                }
+            },
+            #[cfg(feature = "dfu")]
+            Ok(Opcode::EnterBootloader) => {
+                if !Security::Encrypted.is_met_by(security_level) {
+                    defmt::warn!("[control] bootloader denied, the link is not encrypted");
+                    return Err(Security::Encrypted.att_error());
+                }
+
+                system::request_bootloader();
+            }
+            #[cfg(not(feature = "dfu"))]
+            Ok(Opcode::EnterBootloader) => {
+                defmt::warn!("[control] bootloader requested, but DFU is not supported");
+                return Err(AttErrorCode::REQUEST_NOT_SUPPORTED);
+            }
+            Ok(Opcode::SetAllowListEnforced) => {
+                if !Security::Encrypted.is_met_by(security_level) {
+                    defmt::warn!("[control] allow list change denied, the link is not encrypted");
+                    return Err(Security::Encrypted.att_error());
+                }
+
+                match parameters.first() {
+                    Some(&enforced) => allow_list::set_enabled(enforced != 0),
+                    None => {
+                        defmt::warn!("[control] allow list command without a state");
+                        return Err(AttErrorCode::VALUE_NOT_ALLOWED);
+                    }
+                }
+            }
+            Ok(Opcode::ClearAllowList) => {
+                if !Security::Encrypted.is_met_by(security_level) {
+                    defmt::warn!("[control] allow list change denied, the link is not encrypted");
+                    return Err(Security::Encrypted.att_error());
+                }
+
+                allow_list::clear();
+            }
+            Err(opcode) => {
+                defmt::warn!("[control] unknown opcode: {:#04x}", opcode);
+                return Err(AttErrorCode::VALUE_NOT_ALLOWED);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Store a validated beacon identity in the device configuration.
+    fn provision_beacon(identity: BeaconIdentity) {
+        defmt::info!("[control] provisioning beacon identity: {}", identity);
+
+        if let Err(error) = config::update(|config| config.beacon = identity) {
+            defmt::error!("[control] failed to persist the beacon identity: {}", error);
+        }
+    }
+
+    /// Serialize the effective device configuration for the configuration
+    /// characteristic. Secrets, such as bond keys, are never included.
+    ///
+    /// | Offset | Length | Field                                                        |
+    /// |--------|--------|--------------------------------------------------------------|
+    /// | 0      | 1      | Format version, currently 2                                  |
+    /// | 1      | 8      | Preferred connection parameters, encoded as the GAP PPCP     |
+    /// | 9      | 1      | Preferred PHY: 0 for 1M, 1 for 2M, 2 for Coded               |
+    /// | 10     | 1      | Enabled sensor mask                                          |
+    /// | 11     | 1      | Battery chemistry: 0 for CR2032, 1 for LiPo, 2 regulated     |
+    /// | 12     | 22     | Beacon identity: a tag (0 none, 1 iBeacon, 2 Eddystone-UID)  |
+    /// |        |        | followed by the identity as written to the control point     |
+    /// | 34     | 0-17   | Device name, UTF-8, filling the rest of the value            |
+    pub fn encode_configuration(device_name: &str, config: &DeviceConfig) -> ConfigurationValue {
+        let mut value = ConfigurationValue::new();
+
+        // UNWRAP: Infallible. The value is sized for the fields and the longest
+        // device name.
+        value.push(CONFIGURATION_FORMAT_VERSION).unwrap();
+        value
+            .extend_from_slice(&CONNECTION_CONFIG.parameters.ppcp_value())
+            .unwrap();
+        value.push(CONNECTION_CONFIG.phy as u8).unwrap();
+        value.push(config.enabled_sensors).unwrap();
+        value.push(config.battery_chemistry as u8).unwrap();
+        value.extend_from_slice(&config.beacon.encode()).unwrap();
+        value.extend_from_slice(device_name.as_bytes()).unwrap();
+
+        value
+    }
+
+    /// Returns the device name serialized in a configuration characteristic
+    /// `value`.
+    pub fn configuration_device_name(value: &[u8]) -> &str {
+        value
+            .get(CONFIGURATION_NAME_OFFSET..)
+            .and_then(|name| core::str::from_utf8(name).ok())
+            .unwrap_or_default()
+    }
+}
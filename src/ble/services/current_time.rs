@@ -0,0 +1,139 @@
+// SPDX-FileCopyrightText: 2025 Derek Sauer
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use bt_hci::uuid::{BluetoothUuid16, characteristic, service};
+use static_cell::StaticCell;
+use trouble_host::attribute::{AttributeTable, Characteristic, CharacteristicProp, Service};
+
+use super::{READ_WRITE, attribute_count, cccd_count};
+use crate::wall_clock::{self, DateTime};
+
+/// Length of the Current Time characteristic: the Exact Time 256 followed by
+/// the adjust reason.
+pub const CURRENT_TIME_LENGTH: usize = 10;
+
+/// Value of the Current Time characteristic.
+///
+/// | Offset | Length | Field                                                 |
+/// |--------|--------|-------------------------------------------------------|
+/// | 0      | 2      | Year, little endian, 1582 to 9999                     |
+/// | 2      | 1      | Month, 1 to 12                                        |
+/// | 3      | 1      | Day, 1 to 31                                          |
+/// | 4      | 1      | Hours, 0 to 23                                        |
+/// | 5      | 1      | Minutes, 0 to 59                                      |
+/// | 6      | 1      | Seconds, 0 to 59                                      |
+/// | 7      | 1      | Day of week, 1 for Monday to 7 for Sunday, 0 unknown  |
+/// | 8      | 1      | Fractions of a second, in 1/256ths                    |
+/// | 9      | 1      | Adjust reason flags                                   |
+pub type CurrentTimeValue = [u8; CURRENT_TIME_LENGTH];
+
+/// Adjust reason flag of a time set manually, by the client.
+const MANUAL_TIME_UPDATE: u8 = 0x01;
+
+/// The Current Time Service lets a client, such as a phone, set the device's
+/// wall clock so it can timestamp events.
+#[allow(dead_code)]
+pub struct CurrentTimeService {
+    /// The Current Time characteristic, see [`CurrentTimeValue`]. All zeros
+    /// until a client sets the time, which requires an encrypted link.
+    pub current_time: Characteristic<CurrentTimeValue>,
+
+    handle: u16,
+}
+
+impl CurrentTimeService {
+    /// Attributes added to the attribute table, derived from the
+    /// characteristics of the service.
+    pub const ATTRIBUTE_COUNT: usize = attribute_count(&Self::CHARACTERISTICS);
+    /// BLE 16-bit UUID assigned to the Current Time service.
+    pub const BLE_UUID16: BluetoothUuid16 = service::CURRENT_TIME;
+    /// The current time characteristic does not notify.
+    pub const CCCD_COUNT: usize = cccd_count(&Self::CHARACTERISTICS);
+    /// Properties of each characteristic of the service.
+    const CHARACTERISTICS: [&[CharacteristicProp]; 1] = [READ_WRITE];
+
+    pub fn new<MUTEX, const MAX_ATTRIBUTES: usize>(
+        attributes_table: &mut AttributeTable<'_, MUTEX, MAX_ATTRIBUTES>,
+    ) -> Self
+    where
+        MUTEX: embassy_sync::blocking_mutex::raw::RawMutex,
+    {
+        let mut service = attributes_table.add_service(Service::new(Self::BLE_UUID16));
+
+        let current_time = {
+            static STORE: StaticCell<CurrentTimeValue> = StaticCell::new();
+            service
+                .add_characteristic(
+                    characteristic::CURRENT_TIME,
+                    READ_WRITE,
+                    [0; CURRENT_TIME_LENGTH],
+                    STORE.init([0; CURRENT_TIME_LENGTH]),
+                )
+                .build()
+        };
+
+        Self {
+            handle: service.build(),
+            current_time,
+        }
+    }
+
+    /// Returns the value of the current time characteristic: the current time,
+    /// or all zeros if the clock was not set since boot.
+    pub fn current_time_value() -> CurrentTimeValue {
+        let Some(now) = wall_clock::now() else {
+            return [0; CURRENT_TIME_LENGTH];
+        };
+
+        let year = now.year.to_le_bytes();
+        [
+            year[0],
+            year[1],
+            now.month,
+            now.day,
+            now.hours,
+            now.minutes,
+            now.seconds,
+            now.day_of_week(),
+            now.fractions256,
+            MANUAL_TIME_UPDATE,
+        ]
+    }
+
+    /// Returns the date and time of a value written to the current time
+    /// characteristic, or `None` if it is not a valid time.
+    ///
+    /// The day of week must be unknown or match the date, and is otherwise
+    /// derived from the date. The adjust reason is ignored.
+    pub fn decode(value: &[u8]) -> Option<DateTime> {
+        let &[
+            year_low,
+            year_high,
+            month,
+            day,
+            hours,
+            minutes,
+            seconds,
+            day_of_week,
+            fractions256,
+            _adjust_reason,
+        ] = value
+        else {
+            return None;
+        };
+
+        let date_time = DateTime {
+            year: u16::from_le_bytes([year_low, year_high]),
+            month,
+            day,
+            hours,
+            minutes,
+            seconds,
+            fractions256,
+        }
+        .validated()?;
+
+        (day_of_week == 0 || day_of_week == date_time.day_of_week()).then_some(date_time)
+    }
+}
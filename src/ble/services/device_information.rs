@@ -3,27 +3,29 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
 use bt_hci::uuid::{BluetoothUuid16, characteristic, service};
-use trouble_host::attribute::{AttributeTable, Characteristic, Service};
+use trouble_host::attribute::{AttributeTable, Characteristic, CharacteristicProp, Service};
 
-/// Name of the manufacturer of the device.
-static MANUFACTURER_NAME: &str = "Sauerstoff.ca";
+use super::{READ, attribute_count, cccd_count};
 
-/// Model number or name of the device.
-static MODEL_NUMBER: &str = "Lookpoint-01";
+/// Name of the manufacturer of the device. Overridable at build time with the
+/// `LOOKPOINT_MANUFACTURER_NAME` environment variable.
+static MANUFACTURER_NAME: &str = env!("LOOKPOINT_MANUFACTURER_NAME");
+
+/// Model number or name of the device. Overridable at build time with the
+/// `LOOKPOINT_MODEL_NUMBER` environment variable.
+static MODEL_NUMBER: &str = env!("LOOKPOINT_MODEL_NUMBER");
 
 /// The device's serial number.
 /// TODO: Setup serial number automation.
-static SERIAL_NUMBER: &str = "AG-202509-0001";
+pub static SERIAL_NUMBER: &str = "AG-202509-0001";
 
 /// This firmware's version.
-static FIRMWARE_REVISION: &str = env!("CARGO_PKG_VERSION");
+pub static FIRMWARE_REVISION: &str = env!("CARGO_PKG_VERSION");
 
-/// Hardware revision name or number of this device.
-static HARDWARE_REVISION: &str = if cfg!(feature = "nano_33_ble") {
-    "ABX00071"
-} else {
-    "unknown"
-};
+/// Hardware revision name or number of this device. Overridable at build time
+/// with the `LOOKPOINT_HARDWARE_REVISION` environment variable, defaults to the
+/// revision of the selected board.
+pub static HARDWARE_REVISION: &str = env!("LOOKPOINT_HARDWARE_REVISION");
 
 /// The Device Information Service exposes manufacturer and/or vendor
 /// information about a device.
@@ -58,14 +60,16 @@ pub struct DeviceInformation {
 }
 
 impl DeviceInformation {
-    /// Each read only characteristic adds two attributes to the attribute
-    /// table. The service itself also adds one attribute.
-    pub const ATTRIBUTE_COUNT: usize = 5 * 2 + 1;
+    /// Attributes added to the attribute table, derived from the
+    /// characteristics of the service.
+    pub const ATTRIBUTE_COUNT: usize = attribute_count(&Self::CHARACTERISTICS);
     /// BLE 16-bit UUID assigned to the Device Information service.
-    pub const BLE_UUID16: BluetoothUuid16 = bt_hci::uuid::service::DEVICE_INFORMATION;
+    pub const BLE_UUID16: BluetoothUuid16 = service::DEVICE_INFORMATION;
     /// Read only attributes do not require Client Characteristic Configuration
     /// Descriptors (CCCD).
-    pub const CCCD_COUNT: usize = 0;
+    pub const CCCD_COUNT: usize = cccd_count(&Self::CHARACTERISTICS);
+    /// Properties of each characteristic of the service, all read only.
+    const CHARACTERISTICS: [&[CharacteristicProp]; 5] = [READ; 5];
 
     pub fn new<MUTEX, const MAX_ATTRIBUTES: usize>(
         attributes_table: &mut AttributeTable<'_, MUTEX, MAX_ATTRIBUTES>,
@@ -73,7 +77,7 @@ impl DeviceInformation {
     where
         MUTEX: embassy_sync::blocking_mutex::raw::RawMutex,
     {
-        let mut service = attributes_table.add_service(Service::new(service::DEVICE_INFORMATION));
+        let mut service = attributes_table.add_service(Service::new(Self::BLE_UUID16));
 
         let manufacturer_name = service
             .add_characteristic_ro(characteristic::MANUFACTURER_NAME_STRING, &MANUFACTURER_NAME)
@@ -0,0 +1,80 @@
+// SPDX-FileCopyrightText: 2025 Derek Sauer
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use bt_hci::uuid::{BluetoothUuid16, characteristic, service};
+use static_cell::StaticCell;
+use trouble_host::attribute::{AttributeTable, Characteristic, CharacteristicProp, Service};
+
+use super::{READ_NOTIFY, attribute_count, cccd_count};
+use crate::thermal;
+
+/// Value of the temperature characteristic until the temperature is first
+/// measured, as defined by the characteristic.
+pub const TEMPERATURE_UNKNOWN: i16 = i16::MIN;
+
+/// The Environmental Sensing Service exposes measurements of the device's
+/// environment.
+///
+/// # Remarks
+///
+/// The device has no ambient temperature sensor, the temperature is that of
+/// the chip's die. It runs a few degrees above ambient while the radio is busy.
+#[allow(dead_code)]
+pub struct EnvironmentalSensing {
+    /// The Temperature characteristic is the die temperature, as a little
+    /// endian `i16` in hundredths of a degree Celsius.
+    /// [`TEMPERATURE_UNKNOWN`] until the temperature is first measured.
+    pub temperature: Characteristic<i16>,
+
+    handle: u16,
+}
+
+impl EnvironmentalSensing {
+    /// Attributes added to the attribute table, derived from the
+    /// characteristics of the service.
+    pub const ATTRIBUTE_COUNT: usize = attribute_count(&Self::CHARACTERISTICS);
+    /// BLE 16-bit UUID assigned to the Environmental Sensing service.
+    pub const BLE_UUID16: BluetoothUuid16 = service::ENVIRONMENTAL_SENSING;
+    /// The temperature characteristic notifies and requires a Client
+    /// Characteristic Configuration Descriptor (CCCD).
+    pub const CCCD_COUNT: usize = cccd_count(&Self::CHARACTERISTICS);
+    /// Properties of each characteristic of the service.
+    const CHARACTERISTICS: [&[CharacteristicProp]; 1] = [READ_NOTIFY];
+
+    pub fn new<MUTEX, const MAX_ATTRIBUTES: usize>(
+        attributes_table: &mut AttributeTable<'_, MUTEX, MAX_ATTRIBUTES>,
+    ) -> Self
+    where
+        MUTEX: embassy_sync::blocking_mutex::raw::RawMutex,
+    {
+        let mut service = attributes_table.add_service(Service::new(Self::BLE_UUID16));
+
+        let temperature = {
+            static STORE: StaticCell<[u8; 2]> = StaticCell::new();
+            service
+                .add_characteristic(
+                    characteristic::TEMPERATURE,
+                    READ_NOTIFY,
+                    TEMPERATURE_UNKNOWN,
+                    STORE.init([0; 2]),
+                )
+                .build()
+        };
+
+        Self {
+            handle: service.build(),
+            temperature,
+        }
+    }
+
+    /// Returns the current value of the temperature characteristic.
+    pub fn temperature_value() -> i16 {
+        let Some(centi_celsius) = thermal::temperature() else {
+            return TEMPERATURE_UNKNOWN;
+        };
+
+        // Clamped out of the value reserved for unknown temperatures.
+        centi_celsius.clamp(i32::from(i16::MIN) + 1, i32::from(i16::MAX)) as i16
+    }
+}
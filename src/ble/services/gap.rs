@@ -0,0 +1,86 @@
+// SPDX-FileCopyrightText: 2025 Derek Sauer
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use bt_hci::uuid::{BluetoothUuid16, characteristic, service};
+use static_cell::StaticCell;
+use trouble_host::attribute::{AttributeTable, Characteristic, CharacteristicProp, Service};
+use trouble_host::prelude::HeaplessString;
+
+use super::{READ, attribute_count, cccd_count};
+use crate::ble::APPEARANCE;
+use crate::ble::connection_params::CONNECTION_CONFIG;
+use crate::ble::device_name::MAX_GAP_DEVICE_NAME_LENGTH;
+
+/// Value of the Peripheral Preferred Connection Parameters characteristic,
+/// encoded from the parameters requested from the central once connected.
+static PREFERRED_CONNECTION_PARAMETERS: [u8; 8] = CONNECTION_CONFIG.parameters.ppcp_value();
+
+/// The Generic Access Profile (GAP) Service exposes the device's name and
+/// appearance, and the connection parameters it prefers so a central can
+/// apply them up front instead of waiting for the peripheral to request them.
+///
+/// Built here rather than by the BLE host, whose GAP service has no room for
+/// the preferred connection parameters. It is followed by the empty Generic
+/// Attribute (GATT) service, as the host's is.
+#[allow(dead_code)]
+pub struct GapService {
+    /// The Peripheral Preferred Connection Parameters (PPCP) characteristic,
+    /// see [`PreferredConnectionParameters::ppcp_value`].
+    ///
+    /// [`PreferredConnectionParameters::ppcp_value`]: crate::ble::connection_params::PreferredConnectionParameters::ppcp_value
+    pub preferred_connection_parameters: Characteristic<[u8; 8]>,
+
+    handle: u16,
+}
+
+impl GapService {
+    /// Attributes added to the attribute table, derived from the
+    /// characteristics of the service, plus the GATT service declaration.
+    pub const ATTRIBUTE_COUNT: usize = attribute_count(&Self::CHARACTERISTICS) + 1;
+    /// BLE 16-bit UUID assigned to the GAP service.
+    pub const BLE_UUID16: BluetoothUuid16 = service::GAP;
+    /// None of the characteristics notify.
+    pub const CCCD_COUNT: usize = cccd_count(&Self::CHARACTERISTICS);
+    /// Properties of each characteristic of the service: the device name,
+    /// appearance, and preferred connection parameters.
+    const CHARACTERISTICS: [&[CharacteristicProp]; 3] = [READ, READ, READ];
+
+    /// Add the GAP and GATT services to the attribute table, serving
+    /// `device_name`. Must be the first services added.
+    ///
+    /// Returns an error if `device_name` is longer than
+    /// [`MAX_GAP_DEVICE_NAME_LENGTH`].
+    pub fn new<MUTEX, const MAX_ATTRIBUTES: usize>(
+        attributes_table: &mut AttributeTable<'_, MUTEX, MAX_ATTRIBUTES>,
+        device_name: &str,
+    ) -> Result<Self, &'static str>
+    where
+        MUTEX: embassy_sync::blocking_mutex::raw::RawMutex,
+    {
+        static DEVICE_NAME: StaticCell<HeaplessString<MAX_GAP_DEVICE_NAME_LENGTH>> =
+            StaticCell::new();
+        let name = DEVICE_NAME.init(HeaplessString::new());
+        name.push_str(device_name)
+            .map_err(|_| "the device name is too long for the GAP service")?;
+
+        let mut service = attributes_table.add_service(Service::new(service::GAP));
+
+        service.add_characteristic_ro(characteristic::DEVICE_NAME, name);
+        service.add_characteristic_ro(characteristic::APPEARANCE, &APPEARANCE);
+        let preferred_connection_parameters = service
+            .add_characteristic_ro(
+                characteristic::PERIPHERAL_PREFERRED_CONNECTION_PARAMETERS,
+                &PREFERRED_CONNECTION_PARAMETERS,
+            )
+            .build();
+
+        let handle = service.build();
+        attributes_table.add_service(Service::new(service::GATT));
+
+        Ok(Self {
+            handle,
+            preferred_connection_parameters,
+        })
+    }
+}
@@ -0,0 +1,59 @@
+// SPDX-FileCopyrightText: 2025 Derek Sauer
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use bt_hci::uuid::{BluetoothUuid16, characteristic, service};
+use static_cell::StaticCell;
+use trouble_host::attribute::{AttributeTable, Characteristic, CharacteristicProp, Service};
+
+use super::{WRITE_WITHOUT_RESPONSE, attribute_count, cccd_count};
+use crate::indicator::AlertLevel;
+
+/// The Immediate Alert Service lets a client, such as a phone looking for a
+/// lost tracker, make the device beep.
+#[allow(dead_code)]
+pub struct ImmediateAlertService {
+    /// The Alert Level characteristic is written with the [`AlertLevel`] the
+    /// device should alert at.
+    pub alert_level: Characteristic<u8>,
+
+    handle: u16,
+}
+
+impl ImmediateAlertService {
+    /// Attributes added to the attribute table, derived from the
+    /// characteristics of the service.
+    pub const ATTRIBUTE_COUNT: usize = attribute_count(&Self::CHARACTERISTICS);
+    /// BLE 16-bit UUID assigned to the Immediate Alert service.
+    pub const BLE_UUID16: BluetoothUuid16 = service::IMMEDIATE_ALERT;
+    /// The alert level characteristic does not notify.
+    pub const CCCD_COUNT: usize = cccd_count(&Self::CHARACTERISTICS);
+    /// Properties of each characteristic of the service.
+    const CHARACTERISTICS: [&[CharacteristicProp]; 1] = [WRITE_WITHOUT_RESPONSE];
+
+    pub fn new<MUTEX, const MAX_ATTRIBUTES: usize>(
+        attributes_table: &mut AttributeTable<'_, MUTEX, MAX_ATTRIBUTES>,
+    ) -> Self
+    where
+        MUTEX: embassy_sync::blocking_mutex::raw::RawMutex,
+    {
+        let mut service = attributes_table.add_service(Service::new(service::IMMEDIATE_ALERT));
+
+        let alert_level = {
+            static STORE: StaticCell<[u8; 1]> = StaticCell::new();
+            service
+                .add_characteristic(
+                    characteristic::ALERT_LEVEL,
+                    WRITE_WITHOUT_RESPONSE,
+                    AlertLevel::NoAlert as u8,
+                    STORE.init([0; 1]),
+                )
+                .build()
+        };
+
+        Self {
+            handle: service.build(),
+            alert_level,
+        }
+    }
+}
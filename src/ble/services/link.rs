@@ -0,0 +1,138 @@
+// SPDX-FileCopyrightText: 2025 Derek Sauer
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use embassy_time::Duration;
+use static_cell::StaticCell;
+use trouble_host::attribute::{AttributeTable, Characteristic, CharacteristicProp, Service};
+use trouble_host::prelude::{PhyKind, Uuid};
+
+use super::{READ_NOTIFY, attribute_count, cccd_count, vendor_uuid};
+
+/// Encoded value of the link characteristic.
+pub type LinkValue = [u8; 4];
+
+/// Link layer payload length, in bytes, used until the data length is
+/// extended.
+pub const DEFAULT_DATA_LENGTH: u16 = 27;
+
+/// Value of the RSSI characteristic while the controller has no RSSI to
+/// report, the same as HCI's.
+pub const RSSI_UNAVAILABLE: i8 = 127;
+
+/// State of the connection serving a client.
+#[derive(Clone, Copy, PartialEq)]
+pub struct Link {
+    /// Negotiated ATT MTU.
+    pub att_mtu: u16,
+
+    /// PHY the device transmits on.
+    pub tx_phy: PhyKind,
+
+    /// PHY the device receives on.
+    pub rx_phy: PhyKind,
+
+    /// Connection interval, once the central reports it by updating the
+    /// connection parameters. Not part of the link characteristic.
+    pub conn_interval: Option<Duration>,
+
+    /// Largest link layer payload the device transmits, in bytes. Not part of
+    /// the link characteristic.
+    pub max_tx_octets: u16,
+
+    /// Largest link layer payload the device receives, in bytes. Not part of
+    /// the link characteristic.
+    pub max_rx_octets: u16,
+}
+
+impl Link {
+    /// Encode the link as the value of the link characteristic: the little
+    /// endian ATT MTU, then the TX and RX PHYs coded as in HCI, 1 for 1M, 2 for
+    /// 2M and 3 for Coded.
+    pub fn value(&self) -> LinkValue {
+        let att_mtu = self.att_mtu.to_le_bytes();
+        [
+            att_mtu[0],
+            att_mtu[1],
+            phy_code(self.tx_phy),
+            phy_code(self.rx_phy),
+        ]
+    }
+}
+
+/// Returns the HCI code of `phy`.
+fn phy_code(phy: PhyKind) -> u8 {
+    match phy {
+        PhyKind::Le1M => 1,
+        PhyKind::Le2M => 2,
+        PhyKind::LeCoded | PhyKind::LeCodedS2 => 3,
+    }
+}
+
+/// Lookpoint's vendor specific link service reports the negotiated ATT MTU and
+/// PHYs of the connection, letting a client pick chunk sizes and anticipate
+/// throughput without guessing, and the strength of the link, letting a phone
+/// guide its user to the device.
+#[allow(dead_code)]
+pub struct LinkService {
+    /// The connection's [`Link`] state, see [`Link::value`]. Notified whenever
+    /// it changes during the connection.
+    pub link: Characteristic<LinkValue>,
+
+    /// Signal strength of the connection as received by the device, in dBm,
+    /// or [`RSSI_UNAVAILABLE`]. Notified as it changes.
+    pub rssi: Characteristic<i8>,
+
+    handle: u16,
+}
+
+impl LinkService {
+    /// Attributes added to the attribute table, derived from the
+    /// characteristics of the service.
+    pub const ATTRIBUTE_COUNT: usize = attribute_count(&Self::CHARACTERISTICS);
+    /// Both characteristics notify and require a Client Characteristic
+    /// Configuration Descriptor (CCCD).
+    pub const CCCD_COUNT: usize = cccd_count(&Self::CHARACTERISTICS);
+    /// Properties of each characteristic of the service.
+    const CHARACTERISTICS: [&[CharacteristicProp]; 2] = [READ_NOTIFY, READ_NOTIFY];
+    /// Vendor specific 128-bit UUID of the link characteristic.
+    pub const LINK_UUID: Uuid = vendor_uuid(0x0021);
+    /// Vendor specific 128-bit UUID of the RSSI characteristic.
+    pub const RSSI_UUID: Uuid = vendor_uuid(0x0022);
+    /// Vendor specific 128-bit UUID of the link service.
+    pub const SERVICE_UUID: Uuid = vendor_uuid(0x0020);
+
+    pub fn new<MUTEX, const MAX_ATTRIBUTES: usize>(
+        attributes_table: &mut AttributeTable<'_, MUTEX, MAX_ATTRIBUTES>,
+    ) -> Self
+    where
+        MUTEX: embassy_sync::blocking_mutex::raw::RawMutex,
+    {
+        let mut service = attributes_table.add_service(Service::new(Self::SERVICE_UUID));
+
+        let link = {
+            static STORE: StaticCell<LinkValue> = StaticCell::new();
+            service
+                .add_characteristic(Self::LINK_UUID, READ_NOTIFY, [0; 4], STORE.init([0; 4]))
+                .build()
+        };
+
+        let rssi = {
+            static STORE: StaticCell<[u8; 1]> = StaticCell::new();
+            service
+                .add_characteristic(
+                    Self::RSSI_UUID,
+                    READ_NOTIFY,
+                    RSSI_UNAVAILABLE,
+                    STORE.init([0; 1]),
+                )
+                .build()
+        };
+
+        Self {
+            handle: service.build(),
+            link,
+            rssi,
+        }
+    }
+}
@@ -0,0 +1,61 @@
+// SPDX-FileCopyrightText: 2025 Derek Sauer
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use bt_hci::uuid::{BluetoothUuid16, characteristic, service};
+use static_cell::StaticCell;
+use trouble_host::attribute::{AttributeTable, Characteristic, CharacteristicProp, Service};
+
+use super::{READ_WRITE, attribute_count, cccd_count};
+use crate::indicator::AlertLevel;
+
+/// The Link Loss Service lets a client, such as a phone, choose how the device
+/// alerts when the connection to it is lost, for example because the phone
+/// moved out of range.
+#[allow(dead_code)]
+pub struct LinkLossService {
+    /// The Alert Level characteristic is the [`AlertLevel`] the device alerts
+    /// at when the connection is lost. Defaults to
+    /// [`AlertLevel::NoAlert`].
+    pub alert_level: Characteristic<u8>,
+
+    handle: u16,
+}
+
+impl LinkLossService {
+    /// Attributes added to the attribute table, derived from the
+    /// characteristics of the service.
+    pub const ATTRIBUTE_COUNT: usize = attribute_count(&Self::CHARACTERISTICS);
+    /// BLE 16-bit UUID assigned to the Link Loss service.
+    pub const BLE_UUID16: BluetoothUuid16 = service::LINK_LOSS;
+    /// The alert level characteristic does not notify.
+    pub const CCCD_COUNT: usize = cccd_count(&Self::CHARACTERISTICS);
+    /// Properties of each characteristic of the service.
+    const CHARACTERISTICS: [&[CharacteristicProp]; 1] = [READ_WRITE];
+
+    pub fn new<MUTEX, const MAX_ATTRIBUTES: usize>(
+        attributes_table: &mut AttributeTable<'_, MUTEX, MAX_ATTRIBUTES>,
+    ) -> Self
+    where
+        MUTEX: embassy_sync::blocking_mutex::raw::RawMutex,
+    {
+        let mut service = attributes_table.add_service(Service::new(service::LINK_LOSS));
+
+        let alert_level = {
+            static STORE: StaticCell<[u8; 1]> = StaticCell::new();
+            service
+                .add_characteristic(
+                    characteristic::ALERT_LEVEL,
+                    READ_WRITE,
+                    AlertLevel::NoAlert as u8,
+                    STORE.init([0; 1]),
+                )
+                .build()
+        };
+
+        Self {
+            handle: service.build(),
+            alert_level,
+        }
+    }
+}
@@ -0,0 +1,133 @@
+// SPDX-FileCopyrightText: 2025 Derek Sauer
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use static_cell::StaticCell;
+use trouble_host::attribute::{AttributeTable, Characteristic, CharacteristicProp, Service};
+use trouble_host::prelude::Uuid;
+
+use super::{READ_NOTIFY, attribute_count, cccd_count, vendor_uuid};
+use crate::{motion, wall_clock};
+
+/// Value of the stationary time characteristic when no motion has been
+/// detected since boot, distinguishing "unknown" from "moved recently".
+pub const STATIONARY_TIME_UNKNOWN: u32 = u32::MAX;
+
+/// Length of the last motion characteristic.
+pub const LAST_MOTION_LENGTH: usize = 5;
+
+/// Value of the last motion characteristic.
+///
+/// | Offset | Length | Field                                                |
+/// |--------|--------|------------------------------------------------------|
+/// | 0      | 4      | Time of the last motion, little endian, in seconds   |
+/// | 4      | 1      | Status flags                                         |
+///
+/// The time is Unix time if [`LAST_MOTION_WALL_CLOCK`] is set, otherwise the
+/// uptime at which the motion was detected. Both are zero until
+/// [`LAST_MOTION_DETECTED`] is set.
+pub type LastMotionValue = [u8; LAST_MOTION_LENGTH];
+
+/// Status flag set once motion has been detected since boot.
+pub const LAST_MOTION_DETECTED: u8 = 0x01;
+
+/// Status flag set if the time is Unix time, the wall clock having been set
+/// through the Current Time Service.
+pub const LAST_MOTION_WALL_CLOCK: u8 = 0x02;
+
+/// Lookpoint's vendor specific motion service reports how the device has been
+/// moving, letting a gateway flag assets that have not moved in a long time.
+#[allow(dead_code)]
+pub struct MotionService {
+    /// Seconds since motion was last detected, as a little endian `u32`.
+    /// [`STATIONARY_TIME_UNKNOWN`] if no motion has been detected since boot.
+    pub stationary_time: Characteristic<u32>,
+
+    /// When motion was last detected, see [`LastMotionValue`]. Lets an owner
+    /// see when an asset was last disturbed.
+    pub last_motion: Characteristic<LastMotionValue>,
+
+    handle: u16,
+}
+
+impl MotionService {
+    /// Attributes added to the attribute table, derived from the
+    /// characteristics of the service.
+    pub const ATTRIBUTE_COUNT: usize = attribute_count(&Self::CHARACTERISTICS);
+    /// Both characteristics notify and require a Client Characteristic
+    /// Configuration Descriptor (CCCD).
+    pub const CCCD_COUNT: usize = cccd_count(&Self::CHARACTERISTICS);
+    /// Properties of each characteristic of the service.
+    const CHARACTERISTICS: [&[CharacteristicProp]; 2] = [READ_NOTIFY, READ_NOTIFY];
+    /// Vendor specific 128-bit UUID of the last motion characteristic.
+    pub const LAST_MOTION_UUID: Uuid = vendor_uuid(0x0012);
+    /// Vendor specific 128-bit UUID of the motion service.
+    pub const SERVICE_UUID: Uuid = vendor_uuid(0x0010);
+    /// Vendor specific 128-bit UUID of the stationary time characteristic.
+    pub const STATIONARY_TIME_UUID: Uuid = vendor_uuid(0x0011);
+
+    pub fn new<MUTEX, const MAX_ATTRIBUTES: usize>(
+        attributes_table: &mut AttributeTable<'_, MUTEX, MAX_ATTRIBUTES>,
+    ) -> Self
+    where
+        MUTEX: embassy_sync::blocking_mutex::raw::RawMutex,
+    {
+        let mut service = attributes_table.add_service(Service::new(Self::SERVICE_UUID));
+
+        let stationary_time = {
+            static STORE: StaticCell<[u8; 4]> = StaticCell::new();
+            service
+                .add_characteristic(
+                    Self::STATIONARY_TIME_UUID,
+                    READ_NOTIFY,
+                    STATIONARY_TIME_UNKNOWN,
+                    STORE.init([0; 4]),
+                )
+                .build()
+        };
+
+        let last_motion = {
+            static STORE: StaticCell<LastMotionValue> = StaticCell::new();
+            service
+                .add_characteristic(
+                    Self::LAST_MOTION_UUID,
+                    READ_NOTIFY,
+                    [0; LAST_MOTION_LENGTH],
+                    STORE.init([0; LAST_MOTION_LENGTH]),
+                )
+                .build()
+        };
+
+        Self {
+            handle: service.build(),
+            stationary_time,
+            last_motion,
+        }
+    }
+
+    /// Returns the current value of the stationary time characteristic.
+    pub fn stationary_time_value() -> u32 {
+        motion::seconds_since_motion().unwrap_or(STATIONARY_TIME_UNKNOWN)
+    }
+
+    /// Returns the current value of the last motion characteristic.
+    pub fn last_motion_value() -> LastMotionValue {
+        let Some(last_motion) = motion::last_motion() else {
+            return [0; LAST_MOTION_LENGTH];
+        };
+
+        // Unix time fits in 32 bits until 2106, otherwise report the uptime.
+        let (seconds, status) = match wall_clock::unix_seconds_at(last_motion)
+            .and_then(|seconds| u32::try_from(seconds).ok())
+        {
+            Some(seconds) => (seconds, LAST_MOTION_DETECTED | LAST_MOTION_WALL_CLOCK),
+            // Uptime in seconds only overflows after 136 years.
+            None => (last_motion.as_secs() as u32, LAST_MOTION_DETECTED),
+        };
+
+        let mut value = [0; LAST_MOTION_LENGTH];
+        value[..4].copy_from_slice(&seconds.to_le_bytes());
+        value[4] = status;
+        value
+    }
+}
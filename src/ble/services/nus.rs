@@ -0,0 +1,261 @@
+// SPDX-FileCopyrightText: 2025 Derek Sauer
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Nordic UART Service (NUS), a serial link over BLE that terminal apps such
+//! as nRF Toolbox speak. It carries a small debug console: the client writes
+//! command lines to the RX characteristic, and the device answers on the TX
+//! characteristic.
+
+use core::fmt::Write;
+
+use embassy_time::Instant;
+use static_cell::StaticCell;
+use trouble_host::attribute::{AttributeTable, Characteristic, CharacteristicProp, Service};
+use trouble_host::prelude::Uuid;
+
+use super::{attribute_count, cccd_count};
+use crate::ble::ATT_MTU;
+use crate::{battery, motion, thermal, wall_clock};
+
+/// Largest value of the RX and TX characteristics: the payload of a write or
+/// notification at the largest ATT MTU.
+pub const NUS_LENGTH: usize = ATT_MTU - 3;
+
+/// Value of the RX and TX characteristics.
+pub type NusValue = heapless::Vec<u8, NUS_LENGTH>;
+
+/// Longest command line accepted by the console. Longer lines are discarded.
+const MAX_LINE_LENGTH: usize = 32;
+
+/// Longest response of the console. Sent in as many notifications as the ATT
+/// MTU requires.
+pub const MAX_RESPONSE_LENGTH: usize = 160;
+
+/// Response of the console to a command line.
+pub type ConsoleResponse = heapless::String<MAX_RESPONSE_LENGTH>;
+
+/// Properties of the RX characteristic: terminals write lines without waiting
+/// for a response.
+const RX: &[CharacteristicProp] = &[
+    CharacteristicProp::Write,
+    CharacteristicProp::WriteWithoutResponse,
+];
+
+/// Properties of the TX characteristic.
+const TX: &[CharacteristicProp] = &[CharacteristicProp::Notify];
+
+/// The Nordic UART Service.
+#[allow(dead_code)]
+pub struct NusService {
+    /// Written by the client with console input. Lines may span several
+    /// writes, and a write may hold several lines. Writes require an
+    /// encrypted link.
+    pub rx: Characteristic<NusValue>,
+
+    /// Notifies the client of console output, split to fit the ATT MTU.
+    pub tx: Characteristic<NusValue>,
+
+    handle: u16,
+}
+
+impl NusService {
+    /// Attributes added to the attribute table, derived from the
+    /// characteristics of the service.
+    pub const ATTRIBUTE_COUNT: usize = attribute_count(&Self::CHARACTERISTICS);
+    /// The TX characteristic notifies and requires a Client Characteristic
+    /// Configuration Descriptor (CCCD).
+    pub const CCCD_COUNT: usize = cccd_count(&Self::CHARACTERISTICS);
+    /// Properties of each characteristic of the service.
+    const CHARACTERISTICS: [&[CharacteristicProp]; 2] = [RX, TX];
+    /// Nordic's 128-bit UUID of the RX characteristic,
+    /// `6e400002-b5a3-f393-e0a9-e50e24dcca9e`.
+    pub const RX_UUID: Uuid = Uuid::new_long([
+        0x9e, 0xca, 0xdc, 0x24, 0x0e, 0xe5, 0xa9, 0xe0, 0x93, 0xf3, 0xa3, 0xb5, 0x02, 0x00, 0x40,
+        0x6e,
+    ]);
+    /// Nordic's 128-bit UUID of the service,
+    /// `6e400001-b5a3-f393-e0a9-e50e24dcca9e`.
+    pub const SERVICE_UUID: Uuid = Uuid::new_long([
+        0x9e, 0xca, 0xdc, 0x24, 0x0e, 0xe5, 0xa9, 0xe0, 0x93, 0xf3, 0xa3, 0xb5, 0x01, 0x00, 0x40,
+        0x6e,
+    ]);
+    /// Nordic's 128-bit UUID of the TX characteristic,
+    /// `6e400003-b5a3-f393-e0a9-e50e24dcca9e`.
+    pub const TX_UUID: Uuid = Uuid::new_long([
+        0x9e, 0xca, 0xdc, 0x24, 0x0e, 0xe5, 0xa9, 0xe0, 0x93, 0xf3, 0xa3, 0xb5, 0x03, 0x00, 0x40,
+        0x6e,
+    ]);
+
+    pub fn new<MUTEX, const MAX_ATTRIBUTES: usize>(
+        attributes_table: &mut AttributeTable<'_, MUTEX, MAX_ATTRIBUTES>,
+    ) -> Self
+    where
+        MUTEX: embassy_sync::blocking_mutex::raw::RawMutex,
+    {
+        let mut service = attributes_table.add_service(Service::new(Self::SERVICE_UUID));
+
+        let rx = {
+            static STORE: StaticCell<[u8; NUS_LENGTH]> = StaticCell::new();
+            service
+                .add_characteristic(
+                    Self::RX_UUID,
+                    RX,
+                    NusValue::new(),
+                    STORE.init([0; NUS_LENGTH]),
+                )
+                .build()
+        };
+
+        let tx = {
+            static STORE: StaticCell<[u8; NUS_LENGTH]> = StaticCell::new();
+            service
+                .add_characteristic(
+                    Self::TX_UUID,
+                    TX,
+                    NusValue::new(),
+                    STORE.init([0; NUS_LENGTH]),
+                )
+                .build()
+        };
+
+        Self {
+            handle: service.build(),
+            rx,
+            tx,
+        }
+    }
+}
+
+/// Commands of the console, each a line of text.
+#[derive(Clone, Copy, PartialEq, defmt::Format)]
+pub enum ConsoleCommand {
+    /// `help`: list the commands.
+    Help,
+
+    /// `temp`: the die temperature.
+    Temperature,
+
+    /// `batt`: the battery voltage and level.
+    Battery,
+
+    /// `uptime`: time since boot.
+    Uptime,
+
+    /// `time`: the wall clock time, and when motion was last detected.
+    Time,
+
+    /// `reset`: reset the device once queued flash writes complete.
+    Reset,
+}
+
+impl ConsoleCommand {
+    /// Returns the command of `line`, ignoring surrounding whitespace, or
+    /// `None` if it is not a command.
+    pub fn parse(line: &str) -> Option<Self> {
+        match line.trim() {
+            "help" => Some(Self::Help),
+            "temp" => Some(Self::Temperature),
+            "batt" => Some(Self::Battery),
+            "uptime" => Some(Self::Uptime),
+            "time" => Some(Self::Time),
+            "reset" => Some(Self::Reset),
+            _ => None,
+        }
+    }
+
+    /// Returns the response to the command, one or more lines of text.
+    pub fn respond(self) -> ConsoleResponse {
+        let mut response = ConsoleResponse::new();
+
+        // Infallible. Every response fits in MAX_RESPONSE_LENGTH.
+        let _ = match self {
+            Self::Help => response.write_str("commands: help, temp, batt, uptime, time, reset\n"),
+            Self::Temperature => match thermal::temperature() {
+                Some(centi_celsius) => writeln!(
+                    response,
+                    "{}{}.{:02} C",
+                    if centi_celsius < 0 { "-" } else { "" },
+                    centi_celsius.unsigned_abs() / 100,
+                    centi_celsius.unsigned_abs() % 100
+                ),
+                None => response.write_str("temperature not measured yet\n"),
+            },
+            Self::Battery => match (battery::millivolts(), battery::level()) {
+                (Some(millivolts), Some(percent)) => {
+                    writeln!(response, "{} mV, {}%", millivolts, percent)
+                }
+                _ => response.write_str("battery not measured yet\n"),
+            },
+            Self::Uptime => writeln!(response, "{} s", Instant::now().as_secs()),
+            Self::Time => {
+                match wall_clock::now() {
+                    Some(now) => writeln!(response, "{}", now).and_then(|()| {
+                        match motion::last_motion().and_then(wall_clock::at) {
+                            Some(last_motion) => writeln!(response, "last motion: {}", last_motion),
+                            None => Ok(()),
+                        }
+                    }),
+                    None => response.write_str("time not set\n"),
+                }
+            }
+            Self::Reset => response.write_str("resetting\n"),
+        };
+
+        response
+    }
+}
+
+/// Assembles console input into command lines.
+pub struct Console {
+    /// Bytes of the line received so far.
+    line: heapless::Vec<u8, MAX_LINE_LENGTH>,
+
+    /// Whether the line overflowed [`MAX_LINE_LENGTH`], and is discarded.
+    overflowed: bool,
+}
+
+impl Console {
+    /// Create a console waiting for a line.
+    pub const fn new() -> Self {
+        Self {
+            line:       heapless::Vec::new(),
+            overflowed: false,
+        }
+    }
+
+    /// Add `byte` to the line received so far. Returns the response to the
+    /// line, and its command if any, once `byte` ends it. Empty lines are
+    /// ignored.
+    pub fn push(&mut self, byte: u8) -> Option<(Option<ConsoleCommand>, ConsoleResponse)> {
+        if !matches!(byte, b'\n' | b'\r') {
+            if self.line.push(byte).is_err() {
+                self.overflowed = true;
+            }
+            return None;
+        }
+
+        let overflowed = core::mem::replace(&mut self.overflowed, false);
+        let line = core::mem::take(&mut self.line);
+        if line.is_empty() && !overflowed {
+            return None;
+        }
+
+        let command = match core::str::from_utf8(&line) {
+            Ok(line) if !overflowed => ConsoleCommand::parse(line),
+            _ => None,
+        };
+
+        let response = match command {
+            Some(command) => command.respond(),
+            None => {
+                let mut response = ConsoleResponse::new();
+                // Infallible. The message fits in MAX_RESPONSE_LENGTH.
+                let _ = response.write_str("unknown command, try help\n");
+                response
+            }
+        };
+
+        Some((command, response))
+    }
+}
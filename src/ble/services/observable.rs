@@ -0,0 +1,97 @@
+// SPDX-FileCopyrightText: 2025 Derek Sauer
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::watch::{Receiver, Watch};
+use trouble_host::prelude::*;
+
+use crate::ble::MAX_CONNECTIONS;
+
+/// Holds the value of a characteristic and wakes the notification task of
+/// every connection when it changes.
+///
+/// Producers, such as a sensor sampling task, [`set`](Self::set) the value
+/// from anywhere. Each connection observes it with its own [`Observer`], so
+/// one connection being notified of a change never hides it from another, and
+/// a value set again unchanged costs no radio time.
+pub struct ObservableValue<T: Clone + PartialEq> {
+    /// Current value, `None` until first set, with one receiver reserved for
+    /// every connection.
+    watch: Watch<CriticalSectionRawMutex, T, MAX_CONNECTIONS>,
+}
+
+impl<T: Clone + PartialEq> ObservableValue<T> {
+    /// Create a new [`ObservableValue`] holding no value until first set.
+    pub const fn new() -> Self {
+        Self {
+            watch: Watch::new(),
+        }
+    }
+
+    /// Store a new value. Returns `true` and wakes every [`Observer`] if it
+    /// differs from the current one.
+    pub fn set(&self, value: T) -> bool {
+        let mut changed = false;
+        self.watch.sender().send_if_modified(|current| {
+            changed = current.as_ref() != Some(&value);
+            if changed {
+                *current = Some(value.clone());
+            }
+            changed
+        });
+
+        changed
+    }
+
+    /// Start observing the value for a connection, or `None` if
+    /// [`MAX_CONNECTIONS`] observers already are. Only changes made from now
+    /// on are observed.
+    pub fn observe(&self) -> Option<Observer<'_, T>> {
+        let mut receiver = self.watch.receiver()?;
+        receiver.try_changed();
+
+        Some(Observer { receiver })
+    }
+}
+
+/// Observes an [`ObservableValue`] for a single connection, and is dropped
+/// with it to make room for the next one.
+pub struct Observer<'value, T: Clone + PartialEq> {
+    receiver: Receiver<'value, CriticalSectionRawMutex, T, MAX_CONNECTIONS>,
+}
+
+impl<T: Clone + PartialEq> Observer<'_, T> {
+    /// Wait until the value changes, returning the new value.
+    pub async fn changed(&mut self) -> T {
+        self.receiver.changed().await
+    }
+
+    /// Notify the client of `connection` of each change of the value of
+    /// `characteristic` while `subscribed` returns `true`, until the
+    /// connection ends.
+    ///
+    /// Changes made while the client is not subscribed are skipped: a client
+    /// is notified of the current value when it subscribes, see
+    /// [`GattServer`](crate::ble::gatt_server::GattServer).
+    pub async fn notify_changes<P: PacketPool>(
+        &mut self,
+        characteristic: &Characteristic<T>,
+        connection: &GattConnection<'_, '_, P>,
+        subscribed: impl Fn() -> bool,
+    ) -> !
+    where
+        T: FromGatt,
+    {
+        loop {
+            let value = self.changed().await;
+            if !subscribed() {
+                continue;
+            }
+
+            if let Err(error) = characteristic.notify(connection, &value).await {
+                defmt::warn!("[gatt] failed to notify a changed value: {}", error);
+            }
+        }
+    }
+}
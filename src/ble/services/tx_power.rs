@@ -0,0 +1,58 @@
+// SPDX-FileCopyrightText: 2025 Derek Sauer
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use bt_hci::uuid::{BluetoothUuid16, characteristic, service};
+use static_cell::StaticCell;
+use trouble_host::attribute::{AttributeTable, Characteristic, CharacteristicProp, Service};
+
+use super::{READ, attribute_count, cccd_count};
+use crate::ble::advertise;
+
+/// The Tx Power Service exposes the device's transmit power, letting a client
+/// estimate its distance to the device from the received signal strength.
+#[allow(dead_code)]
+pub struct TxPowerService {
+    /// The Tx Power Level characteristic is the transmit power, in dBm.
+    pub level: Characteristic<i8>,
+
+    handle: u16,
+}
+
+impl TxPowerService {
+    /// Attributes added to the attribute table, derived from the
+    /// characteristics of the service.
+    pub const ATTRIBUTE_COUNT: usize = attribute_count(&Self::CHARACTERISTICS);
+    /// BLE 16-bit UUID assigned to the Tx Power service.
+    pub const BLE_UUID16: BluetoothUuid16 = service::TX_POWER;
+    /// The transmit power level characteristic does not notify.
+    pub const CCCD_COUNT: usize = cccd_count(&Self::CHARACTERISTICS);
+    /// Properties of each characteristic of the service.
+    const CHARACTERISTICS: [&[CharacteristicProp]; 1] = [READ];
+
+    pub fn new<MUTEX, const MAX_ATTRIBUTES: usize>(
+        attributes_table: &mut AttributeTable<'_, MUTEX, MAX_ATTRIBUTES>,
+    ) -> Self
+    where
+        MUTEX: embassy_sync::blocking_mutex::raw::RawMutex,
+    {
+        let mut service = attributes_table.add_service(Service::new(service::TX_POWER));
+
+        let level = {
+            static STORE: StaticCell<[u8; 1]> = StaticCell::new();
+            service
+                .add_characteristic(
+                    characteristic::TX_POWER_LEVEL,
+                    READ,
+                    advertise::tx_power_level(),
+                    STORE.init([0; 1]),
+                )
+                .build()
+        };
+
+        Self {
+            handle: service.build(),
+            level,
+        }
+    }
+}
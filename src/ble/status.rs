@@ -0,0 +1,60 @@
+// SPDX-FileCopyrightText: 2025 Derek Sauer
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Device status flags broadcast in the manufacturer specific data of the
+//! advertisement, letting a gateway monitor units without connecting.
+
+use core::sync::atomic::{AtomicU8, Ordering};
+
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::signal::Signal;
+use embassy_time::Duration;
+
+/// Company identifier of the manufacturer specific data. 0xFFFF is reserved by
+/// the Bluetooth SIG for internal use and testing.
+pub const COMPANY_IDENTIFIER: u16 = 0xffff;
+
+/// Bits of the status byte broadcast in the manufacturer specific data.
+///
+/// Bit assignments are part of the advertising format and must remain stable.
+#[derive(Clone, Copy, defmt::Format)]
+#[repr(u8)]
+pub enum StatusFlag {
+    /// The battery is low and should be replaced or recharged.
+    BatteryLow       = 1 << 0,
+
+    /// The temperature is beyond one of the configured alert thresholds.
+    TemperatureAlert = 1 << 1,
+
+    /// The device moved recently, see [`MOVED_FLAG_DURATION`].
+    Moved            = 1 << 2,
+}
+
+/// Time the device must be still before [`StatusFlag::Moved`] is cleared.
+pub const MOVED_FLAG_DURATION: Duration = Duration::from_secs(10 * 60);
+
+/// Current value of the status byte.
+static STATUS_FLAGS: AtomicU8 = AtomicU8::new(0);
+
+/// Signaled whenever the status byte changes, so the advertiser can update
+/// the advertised status.
+pub static STATUS_FLAGS_CHANGED: Signal<CriticalSectionRawMutex, ()> = Signal::new();
+
+/// Set or clear `flag`.
+pub fn set_flag(flag: StatusFlag, value: bool) {
+    let previous = if value {
+        STATUS_FLAGS.fetch_or(flag as u8, Ordering::Relaxed)
+    } else {
+        STATUS_FLAGS.fetch_and(!(flag as u8), Ordering::Relaxed)
+    };
+
+    if previous != status_flags() {
+        STATUS_FLAGS_CHANGED.signal(());
+    }
+}
+
+/// Returns the status byte to broadcast.
+pub fn status_flags() -> u8 {
+    STATUS_FLAGS.load(Ordering::Relaxed)
+}
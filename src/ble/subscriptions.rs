@@ -0,0 +1,8 @@
+// SPDX-FileCopyrightText: 2025 Derek Sauer
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Tracks which characteristics a client subscribed to notifications of, so
+//! notifications are only sent to clients that asked for them.
+
+pub use lookpoint_logic::subscriptions::{Notifying, Subscriptions};
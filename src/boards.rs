@@ -5,5 +5,7 @@
 #[cfg(feature = "nano_33_ble")]
 mod nano_33_ble;
 
+#[cfg(all(feature = "nano_33_ble", feature = "dfu"))]
+pub use nano_33_ble::request_bootloader;
 #[cfg(feature = "nano_33_ble")]
-pub use nano_33_ble::Board;
+pub use nano_33_ble::{BUTTON_EVENTS, Board, ButtonEvent, NAME_PLACEMENT};
@@ -7,9 +7,21 @@
 //! Vendor's documentation available at:
 //! https://docs.arduino.cc/hardware/nano-33-ble-rev2/
 
+mod battery_sense;
+mod button;
+mod buzzer;
+mod clock;
+mod i2c;
+mod imu;
+mod led;
 mod mpsl;
+mod power;
+mod reset_reason;
 mod sdc;
+mod sensor_power;
+mod watchdog;
 
+pub use button::{BUTTON_EVENTS, ButtonEvent};
 use embassy_executor::Spawner;
 use embassy_nrf::config::{Config, Debug, HfclkSource, LfclkSource};
 use embassy_nrf::interrupt::Priority;
@@ -17,24 +29,61 @@ use nrf_mpsl::MultiprotocolServiceLayer;
 use nrf_sdc::SoftdeviceController;
 use nrf_sdc::mpsl::Flash;
 use static_cell::StaticCell;
-use trouble_host::prelude::DefaultPacketPool;
 use trouble_host::{Address, Host, Stack};
 
+use crate::ble::BlePacketPool;
+use crate::ble::device_name::NamePlacement;
+use crate::ble::services::device_information::{
+    FIRMWARE_REVISION, HARDWARE_REVISION, SERIAL_NUMBER,
+};
+use crate::capabilities::Capability;
+
+/// The board advertises legacy advertisements, with room for the local name in
+/// the scan response.
+pub const NAME_PLACEMENT: NamePlacement = NamePlacement::ScanResponse;
+
+/// The CPU runs at 64 MHz, whether from the internal oscillator or the high
+/// frequency crystal. Used to busy-wait with `cortex_m::asm::delay` where the
+/// time driver is not running.
+const CPU_CYCLES_PER_MS: u32 = 64_000;
+
+/// GPREGRET value asking the bootloader to enter DFU mode rather than start
+/// the application: `BOOTLOADER_DFU_START` of Nordic's Secure DFU bootloader,
+/// also understood by the Adafruit nRF52 bootloader as an OTA DFU request.
+///
+/// The Nano 33 BLE ships with Arduino's serial bootloader, which ignores
+/// GPREGRET: buttonless DFU needs one of these bootloaders installed first.
+#[cfg(feature = "dfu")]
+const BOOTLOADER_DFU_START: u8 = 0xb1;
+
+/// Ask the bootloader to enter DFU mode on the next soft reset.
+#[cfg(feature = "dfu")]
+pub fn request_bootloader() {
+    power::set_retained_register(BOOTLOADER_DFU_START);
+}
+
 /// Board support for the Arduino Nano 33 BLE (Rev2).
 pub struct Board<'mpsl, 'sdc> {
     /// Reference to the MPSL's location in static memory.
     mpsl: &'mpsl MultiprotocolServiceLayer<'static>,
 
-    /// Flash storage handler.
-    flash: Flash<'static>,
+    /// I2C bus shared by the onboard sensor drivers.
+    sensor_bus: &'static i2c::SensorBus,
 
     /// BLE stack (Controller & host resources).
-    ble_stack: Stack<'sdc, SoftdeviceController<'mpsl>, DefaultPacketPool>,
+    ble_stack: Stack<'sdc, SoftdeviceController<'mpsl>, BlePacketPool>,
 }
 
 impl<'mpsl, 'sdc> Board<'mpsl, 'sdc> {
     /// Initialize the [`Board`], its peripherals, and the BLE stack.
-    pub fn init(task_spawner: &Spawner) -> Self {
+    ///
+    /// `device_name` is only logged along with the rest of the unit's
+    /// identity.
+    pub fn init(task_spawner: &Spawner, device_name: &str) -> Self {
+        // Read before anything can reset the chip again.
+        let reset_reason = reset_reason::take();
+        let boot_count = crate::system::count_boot();
+
         let mut board_config = Config::default();
 
         // This board has external oscillators for the high and low frequency clocks.
@@ -46,11 +95,55 @@ impl<'mpsl, 'sdc> Board<'mpsl, 'sdc> {
         board_config.time_interrupt_priority = Priority::P2;
         board_config.gpiote_interrupt_priority = Priority::P2;
 
-        // I want folks to be able to hack on this device.
-        board_config.debug = Debug::Allowed;
+        // I want folks to be able to hack on this device. Production units
+        // leave the debug port as the chip configures it instead, and with the
+        // `approtect` feature lock it. See the features in `Cargo.toml`.
+        board_config.debug = if cfg!(feature = "approtect") {
+            Debug::Disallowed
+        } else if cfg!(feature = "production") {
+            Debug::NotConfigured
+        } else {
+            Debug::Allowed
+        };
+
+        // Probe the external low frequency crystal with a bounded wait. Fall back
+        // to the internal RC oscillator if it is dead so the board can at least
+        // report the fault rather than hanging during initialization.
+        let lfclk_started = clock::probe_lfclk_crystal();
+        if !lfclk_started {
+            board_config.lfclk_source = LfclkSource::InternalRC;
+        }
 
         let peripherals = embassy_nrf::init(board_config);
 
+        if !lfclk_started {
+            defmt::error!("[board] LF clock failed to start, check the 32.768 kHz crystal");
+            led::blink_error_code(peripherals.P0_24, led::ErrorCode::LfClockFailed);
+        }
+
+        // The watchdog resets the device if a critical task hangs.
+        let watchdog = watchdog::init(peripherals.WDT);
+        task_spawner.must_spawn(watchdog::watchdog_task(watchdog));
+
+        let led = led::Led::new(peripherals.P0_24, peripherals.P0_16, peripherals.P0_06);
+        task_spawner.must_spawn(led::led_task(led));
+
+        let button = button::Button::new(peripherals.P1_11);
+        task_spawner.must_spawn(button::button_task(button));
+
+        let buzzer = buzzer::Buzzer::new(peripherals.PWM0, peripherals.P1_12);
+        task_spawner.must_spawn(buzzer::buzzer_task(buzzer));
+
+        // The sensors are unpowered until a sensor driver needs them.
+        sensor_power::init(peripherals.P0_22, peripherals.P1_00);
+        let sensor_bus = i2c::init(peripherals.TWISPI0, peripherals.P0_14, peripherals.P0_15);
+
+        let imu = imu::Imu::new(i2c::device(sensor_bus), peripherals.P0_11);
+        task_spawner.must_spawn(imu::imu_task(imu));
+
+        let battery_sense = battery_sense::BatterySense::new(peripherals.SAADC);
+        task_spawner.must_spawn(battery_sense::battery_task(battery_sense));
+
         // Initialize the MPSL and start its event loop task which will run forever.
         let mpsl = {
             static MPSL: StaticCell<MultiprotocolServiceLayer> = StaticCell::new();
@@ -66,13 +159,20 @@ impl<'mpsl, 'sdc> Board<'mpsl, 'sdc> {
             })
         };
         task_spawner.must_spawn(mpsl::mpsl_task(mpsl));
+        task_spawner.must_spawn(mpsl::thermal_task(mpsl));
 
         // The MPSL offers a flash storage interface that schedules reads &
-        // writes to not conflict with the radio.
-        let flash = Flash::take(mpsl, peripherals.NVMC);
+        // writes to not conflict with the radio. It is owned by the background
+        // writer performing queued writes.
+        //
+        // Writes are refused once the power failure comparator warns that the
+        // supply is sagging, so a write is not cut short by a power loss.
+        power::enable_power_failure_comparator();
+        let flash = power::PowerGuardedFlash::new(Flash::take(mpsl, peripherals.NVMC));
+        task_spawner.must_spawn(mpsl::flash_writer_task(flash));
 
         let ble_address = Self::get_ble_address();
-        let ble_stack = sdc::init_ble_stack(
+        let ble_stack = match sdc::init_ble_stack(
             peripherals.PPI_CH17,
             peripherals.PPI_CH18,
             peripherals.PPI_CH20,
@@ -88,20 +188,85 @@ impl<'mpsl, 'sdc> Board<'mpsl, 'sdc> {
             peripherals.RNG,
             mpsl,
             ble_address,
-        );
+            Self::get_identity_resolving_key(),
+        ) {
+            Ok(ble_stack) => ble_stack,
+            Err(error) => defmt::panic!("[board] failed to initialize the BLE stack: {}", error),
+        };
+
+        Self::log_identity(&ble_address, device_name, reset_reason, boot_count);
 
         Self {
             mpsl,
-            flash,
+            sensor_bus,
             ble_stack,
         }
     }
 
     /// Returns the BLE [`Host`] of this [`Board`].
-    pub fn get_ble_host(&'sdc self) -> Host<'sdc, SoftdeviceController<'mpsl>, DefaultPacketPool> {
+    pub fn get_ble_host(&'sdc self) -> Host<'sdc, SoftdeviceController<'mpsl>, BlePacketPool> {
         self.ble_stack.build()
     }
 
+    /// Returns the BLE [`Stack`] of this [`Board`], used to issue commands on
+    /// established connections.
+    pub fn ble_stack(&self) -> &Stack<'sdc, SoftdeviceController<'mpsl>, BlePacketPool> {
+        &self.ble_stack
+    }
+
+    /// Enter System OFF, the chip's deepest sleep, once queued flash writes
+    /// complete. The chip resets, as if powered on, when the button or the
+    /// reset button is pressed, and the next boot
+    /// reports [`ResetReason::WakeFromSystemOff`](reset_reason::ResetReason).
+    ///
+    /// Should only be called while no connection is active and advertising
+    /// has stopped, as both end abruptly.
+    ///
+    /// The nRF52840 draws about 0.4 µA in System OFF. The board's power LED
+    /// and its regulator's quiescent current dominate: unless the LED's
+    /// jumper is cut, expect the board to draw on the order of a milliamp.
+    pub async fn enter_system_off(&self) -> ! {
+        crate::system::flush_storage().await;
+        button::wake_on_press();
+
+        defmt::info!("[board] entering System OFF");
+        power::system_off()
+    }
+
+    /// Returns the [`Capability`] mask of the board's hardware.
+    pub fn capabilities(&self) -> u16 {
+        // The Nano 33 BLE (Rev2) carries an IMU, but not the environmental
+        // sensors of the Sense variant.
+        Capability::Imu as u16 | Capability::Indicator as u16
+    }
+
+    /// Returns the chip's die temperature in hundredths of a degree Celsius.
+    pub fn temperature(&self) -> i32 {
+        mpsl::temperature(self.mpsl)
+    }
+
+    /// Log everything identifying this unit in a single line, so field logs can
+    /// be matched to the physical unit.
+    fn log_identity(
+        ble_address: &Address,
+        device_name: &str,
+        reset_reason: reset_reason::ResetReason,
+        boot_count: u32,
+    ) {
+        defmt::info!(
+            "[board] identity: address: {}, address type: {}, name: \"{}\", serial: {}, firmware: \
+             {}, hardware: {}, reset reason: {}, boot count: {}",
+            ble_address.addr,
+            ble_address.kind,
+            device_name,
+            SERIAL_NUMBER,
+            FIRMWARE_REVISION,
+            HARDWARE_REVISION,
+            reset_reason,
+            boot_count
+        );
+    }
+
     /// Retrieve the MAC address of this [`Board`].
     // TODO: Ensure the returned address matches the QR Code on the MCU.
     fn get_ble_address() -> Address {
@@ -121,4 +286,19 @@ impl<'mpsl, 'sdc> Board<'mpsl, 'sdc> {
         // UNWRAP: Infallible. Taking lower 6 bytes from an 8 byte value.
         Address::random(address.to_le_bytes()[0..6].try_into().unwrap())
     }
+
+    /// Retrieve the identity resolving key of this [`Board`], in little endian
+    /// byte order, used to generate resolvable private addresses.
+    fn get_identity_resolving_key() -> [u8; 16] {
+        // The FICR holds a random Identity Root programmed at the factory,
+        // unique to the chip.
+        let ficr = embassy_nrf::pac::FICR;
+
+        let mut irk = [0; 16];
+        for (index, word) in irk.chunks_exact_mut(4).enumerate() {
+            word.copy_from_slice(&ficr.ir(index).read().to_le_bytes());
+        }
+
+        irk
+    }
 }
@@ -0,0 +1,85 @@
+// SPDX-FileCopyrightText: 2025 Derek Sauer
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Measures the supply voltage with the SAADC through the nRF52840's internal
+//! VDDH/5 input, so no pin is needed.
+//!
+//! The Nano 33 BLE ties VDDH to the 3.3 V rail, so a battery powering the board
+//! through its 3.3 V pin is measured directly. A battery behind the board's
+//! regulator reads as the regulated 3.3 V, which is why the default
+//! [`BatteryChemistry`](crate::battery::BatteryChemistry) estimates no level.
+
+use embassy_nrf::interrupt::{self, InterruptExt, Priority};
+use embassy_nrf::saadc::{self, ChannelConfig, Gain, Reference, Resolution, Saadc};
+use embassy_nrf::{Peri, bind_interrupts, peripherals};
+use embassy_time::Ticker;
+
+use crate::battery;
+
+/// Full scale of the SAADC input, in millivolts: the 0.6 V internal reference
+/// divided by a gain of 1/6.
+const FULL_SCALE_MILLIVOLTS: u32 = 600 * 6;
+
+/// Ratio of VDDH to the VDDH/5 input.
+const VDDH_DIVIDER: u32 = 5;
+
+/// Number of distinct samples at a 12-bit resolution.
+const SAMPLE_RANGE: u32 = 1 << 12;
+
+/// Supply voltage measurement.
+pub struct BatterySense {
+    saadc: Saadc<'static, 1>,
+}
+
+impl BatterySense {
+    /// Configure the SAADC to sample VDDH/5.
+    pub fn new(saadc: Peri<'static, peripherals::SAADC>) -> Self {
+        bind_interrupts!(struct SaadcIrq {
+            SAADC => saadc::InterruptHandler;
+        });
+
+        // The SoftDevice BLE controller reserves interrupt priorities 0, 1, and 4.
+        interrupt::SAADC.set_priority(Priority::P2);
+
+        let mut config = saadc::Config::default();
+        config.resolution = Resolution::_12BIT;
+
+        let mut channel_config = ChannelConfig::single_ended(saadc::VddhDiv5Input);
+        channel_config.reference = Reference::INTERNAL;
+        channel_config.gain = Gain::GAIN1_6;
+
+        Self {
+            saadc: Saadc::new(saadc, SaadcIrq, config, [channel_config]),
+        }
+    }
+
+    /// Take a single sample of the supply voltage, in millivolts.
+    pub async fn read_millivolts(&mut self) -> u16 {
+        // Measurements are rare, calibrate each time to track the die
+        // temperature.
+        self.saadc.calibrate().await;
+
+        let mut sample = [0; 1];
+        self.saadc.sample(&mut sample).await;
+
+        // Noise can push a sample near ground slightly negative.
+        let raw = sample[0].max(0) as u32;
+        let millivolts = raw * FULL_SCALE_MILLIVOLTS * VDDH_DIVIDER / SAMPLE_RANGE;
+
+        // Infallible. A 12-bit sample scales to at most 18 V.
+        millivolts as u16
+    }
+}
+
+/// Task measuring the supply voltage every [`battery::CHECK_INTERVAL`] and
+/// recording it with [`battery::record_millivolts`].
+#[embassy_executor::task]
+pub async fn battery_task(mut battery_sense: BatterySense) -> ! {
+    let mut ticker = Ticker::every(battery::CHECK_INTERVAL);
+
+    loop {
+        battery::record_millivolts(battery_sense.read_millivolts().await);
+        ticker.next().await;
+    }
+}
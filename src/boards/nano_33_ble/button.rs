@@ -0,0 +1,108 @@
+// SPDX-FileCopyrightText: 2025 Derek Sauer
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! The Nano 33 BLE has no user button, only a reset button. A push button
+//! wired between D2 (P1.11) and ground serves as one. The pin is pulled up, so
+//! it reads low while the button is pressed, and reads high if no button is
+//! fitted.
+
+use embassy_futures::select::{Either, select};
+use embassy_nrf::gpio::{Input, Pull};
+use embassy_nrf::{Peri, peripherals};
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::signal::Signal;
+use embassy_time::{Duration, Timer};
+
+use super::power;
+
+/// Time the button must read steadily pressed, or released, for a change to
+/// be accepted. Longer than the bounce of a typical tactile switch.
+const DEBOUNCE: Duration = Duration::from_millis(20);
+
+/// Time the button must be held for a press to count as a long press.
+const LONG_PRESS: Duration = Duration::from_secs(5);
+
+/// Presses of the button.
+#[derive(Clone, Copy, PartialEq, defmt::Format)]
+pub enum ButtonEvent {
+    /// Pressed and released before [`LONG_PRESS`]. Wakes the device and
+    /// restarts advertising.
+    ShortPress,
+
+    /// Held for [`LONG_PRESS`]. Reported as soon as the time is reached, not
+    /// on release, so the user knows when to let go. Requests a factory reset
+    /// once confirmed by a short press.
+    LongPress,
+}
+
+/// Signaled with each press of the button.
+pub static BUTTON_EVENTS: Signal<CriticalSectionRawMutex, ButtonEvent> = Signal::new();
+
+/// Driver for the external push button.
+pub struct Button {
+    input: Input<'static>,
+}
+
+impl Button {
+    /// Create a new [`Button`] driver.
+    pub fn new(pin: Peri<'static, peripherals::P1_11>) -> Self {
+        Self {
+            input: Input::new(pin, Pull::Up),
+        }
+    }
+
+    /// Wait for the button to read steadily at `pressed` for [`DEBOUNCE`].
+    async fn wait_for_steady(&mut self, pressed: bool) {
+        loop {
+            if pressed {
+                self.input.wait_for_low().await;
+            } else {
+                self.input.wait_for_high().await;
+            }
+
+            Timer::after(DEBOUNCE).await;
+
+            if self.input.is_low() == pressed {
+                return;
+            }
+        }
+    }
+
+    /// Wait for the next press of the button. Returns once it is released,
+    /// or once it has been held for [`LONG_PRESS`].
+    async fn press(&mut self) -> ButtonEvent {
+        self.wait_for_steady(true).await;
+
+        match select(Timer::after(LONG_PRESS), self.wait_for_steady(false)).await {
+            Either::First(()) => ButtonEvent::LongPress,
+            Either::Second(()) => ButtonEvent::ShortPress,
+        }
+    }
+}
+
+/// Configure the button's pin to wake the chip from System OFF when the
+/// button is pressed.
+pub fn wake_on_press() {
+    // SAFETY: The pin stays the pulled up input [`Button`] configured, now
+    // also sensing low. The button task never runs again once the chip enters
+    // System OFF.
+    let pin = unsafe { peripherals::P1_11::steal() };
+    power::wake_on_low(&*pin);
+}
+
+/// Task reporting presses of the button to [`BUTTON_EVENTS`]. Sensing the
+/// pin through the GPIOTE port event draws no current while waiting.
+#[embassy_executor::task]
+pub async fn button_task(mut button: Button) -> ! {
+    loop {
+        let event = button.press().await;
+        defmt::debug!("[button] {}", event);
+        BUTTON_EVENTS.signal(event);
+
+        // A long press is reported while still held, let go before the next.
+        if event == ButtonEvent::LongPress {
+            button.wait_for_steady(false).await;
+        }
+    }
+}
@@ -0,0 +1,149 @@
+// SPDX-FileCopyrightText: 2025 Derek Sauer
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! The Nano 33 BLE has no buzzer. A passive piezo buzzer wired between D3
+//! (P1.12) and ground, driven by a square wave from the PWM0 peripheral, beeps
+//! to help find a lost tracker.
+
+use embassy_futures::select::{Either, select};
+use embassy_nrf::gpio::OutputDrive;
+use embassy_nrf::pwm::SimplePwm;
+use embassy_nrf::{Peri, peripherals};
+use embassy_time::{Duration, Timer, with_timeout};
+
+use crate::indicator::{self, AlertLevel};
+
+/// Resonant frequency of a typical small piezo buzzer, where it is loudest.
+const BEEP_FREQUENCY_HZ: u32 = 4_000;
+
+/// Longest time a high alert beeps for, unless stopped sooner by the client.
+const HIGH_ALERT_DURATION: Duration = Duration::from_secs(30);
+
+/// A beep of a [`Pattern`], followed by a silence.
+#[derive(Clone, Copy)]
+pub struct Beep {
+    /// Frequency of the tone.
+    pub frequency_hz: u32,
+
+    /// Time the tone sounds for.
+    pub duration: Duration,
+
+    /// Silence following the tone.
+    pub pause: Duration,
+}
+
+/// Sequence of beeps played one after another.
+pub type Pattern = [Beep];
+
+/// Three short beeps, answering a mild alert.
+pub const THREE_BEEPS: &Pattern = &[
+    Beep {
+        frequency_hz: BEEP_FREQUENCY_HZ,
+        duration:     Duration::from_millis(100),
+        pause:        Duration::from_millis(100),
+    },
+    Beep {
+        frequency_hz: BEEP_FREQUENCY_HZ,
+        duration:     Duration::from_millis(100),
+        pause:        Duration::from_millis(100),
+    },
+    Beep {
+        frequency_hz: BEEP_FREQUENCY_HZ,
+        duration:     Duration::from_millis(100),
+        pause:        Duration::from_millis(700),
+    },
+];
+
+/// A long beep, alternating between two tones, repeated during a high alert.
+/// The changing pitch is easier to locate than a steady tone.
+pub const SIREN: &Pattern = &[
+    Beep {
+        frequency_hz: BEEP_FREQUENCY_HZ,
+        duration:     Duration::from_millis(250),
+        pause:        Duration::from_ticks(0),
+    },
+    Beep {
+        frequency_hz: BEEP_FREQUENCY_HZ * 3 / 4,
+        duration:     Duration::from_millis(250),
+        pause:        Duration::from_millis(500),
+    },
+];
+
+/// Driver for the external piezo buzzer.
+pub struct Buzzer {
+    pwm: SimplePwm<'static, peripherals::PWM0>,
+}
+
+impl Buzzer {
+    /// Create a new [`Buzzer`] driver, initially silent.
+    pub fn new(
+        pwm: Peri<'static, peripherals::PWM0>,
+        pin: Peri<'static, peripherals::P1_12>,
+    ) -> Self {
+        // The pin is driven low while the PWM is disabled.
+        let pwm = SimplePwm::new_1ch(pwm, pin);
+        pwm.set_ch0_drive(OutputDrive::HighDrive);
+        pwm.disable();
+
+        Self { pwm }
+    }
+
+    /// Sound a tone of `frequency_hz` for `duration`.
+    pub async fn beep(&mut self, frequency_hz: u32, duration: Duration) {
+        // A square wave: high for half of each period. The duty only takes
+        // effect once the PWM is enabled, which starts its sequence.
+        self.pwm.set_period(frequency_hz);
+        self.pwm.enable();
+        let half_period = self.pwm.max_duty() / 2;
+        self.pwm.set_duty(0, half_period);
+
+        Timer::after(duration).await;
+
+        // The PWM is stopped between beeps so it draws no current.
+        self.pwm.disable();
+    }
+
+    /// Play each beep of `pattern` in turn.
+    pub async fn play(&mut self, pattern: &Pattern) {
+        for beep in pattern {
+            self.beep(beep.frequency_hz, beep.duration).await;
+            Timer::after(beep.pause).await;
+        }
+    }
+
+    /// Alert at `level`: three beeps for a mild alert, or a siren for up to
+    /// [`HIGH_ALERT_DURATION`] for a high alert.
+    async fn alert(&mut self, level: AlertLevel) {
+        match level {
+            AlertLevel::NoAlert => {}
+            AlertLevel::Mild => self.play(THREE_BEEPS).await,
+            AlertLevel::High => {
+                let _ = with_timeout(HIGH_ALERT_DURATION, async {
+                    loop {
+                        self.play(SIREN).await;
+                    }
+                })
+                .await;
+            }
+        }
+    }
+}
+
+/// Task sounding the buzzer for alerts posted to the [`indicator`] module. A
+/// new alert level, including [`AlertLevel::NoAlert`], replaces the current
+/// alert.
+#[embassy_executor::task]
+pub async fn buzzer_task(mut buzzer: Buzzer) -> ! {
+    let mut level = indicator::ALERT.wait().await;
+
+    loop {
+        match select(buzzer.alert(level), indicator::ALERT.wait()).await {
+            Either::First(()) => level = indicator::ALERT.wait().await,
+            Either::Second(next) => level = next,
+        }
+
+        // Dropping a beep midway leaves the PWM running.
+        buzzer.pwm.disable();
+    }
+}
@@ -0,0 +1,50 @@
+// SPDX-FileCopyrightText: 2025 Derek Sauer
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! The Nano 33 BLE sources its low frequency clock (LFCLK) from an external
+//! 32.768 kHz crystal. The LFCLK drives the RTCs used by Embassy's time driver
+//! and the MPSL.
+//!
+//! Both `embassy_nrf::init` and the MPSL wait, without a timeout, for the
+//! LFCLK to start. A board with a dead or badly soldered crystal would hang
+//! silently at boot. Probing the crystal beforehand with a bounded wait lets us
+//! report the fault instead.
+
+use embassy_nrf::pac;
+use embassy_nrf::pac::clock::vals::Lfclksrc;
+
+use super::CPU_CYCLES_PER_MS;
+
+/// The nRF52840's datasheet specifies a typical crystal start up time of
+/// 0.25 seconds. Allow four times that before declaring the crystal dead.
+const LFCLK_START_TIMEOUT_MS: u32 = 1000;
+
+/// Returns `true` if the external LFCLK crystal started within
+/// [`LFCLK_START_TIMEOUT_MS`].
+///
+/// The LFCLK is stopped again before returning since its source may only be
+/// configured while it is stopped. `embassy_nrf::init` will start it normally.
+pub fn probe_lfclk_crystal() -> bool {
+    let clock = pac::CLOCK;
+
+    clock.lfclksrc().write(|w| w.set_src(Lfclksrc::XTAL));
+    clock.events_lfclkstarted().write_value(0);
+    clock.tasks_lfclkstart().write_value(1);
+
+    let mut started = false;
+    for _ in 0..LFCLK_START_TIMEOUT_MS {
+        if clock.events_lfclkstarted().read() != 0 {
+            started = true;
+            break;
+        }
+
+        cortex_m::asm::delay(CPU_CYCLES_PER_MS);
+    }
+
+    clock.tasks_lfclkstop().write_value(1);
+    while clock.lfclkstat().read().state() {}
+    clock.events_lfclkstarted().write_value(0);
+
+    started
+}
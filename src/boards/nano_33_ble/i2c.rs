@@ -0,0 +1,72 @@
+// SPDX-FileCopyrightText: 2025 Derek Sauer
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! The Nano 33 BLE's onboard sensors share one internal I2C bus:
+//!
+//! - SDA: P0.14
+//! - SCL: P0.15
+//!
+//! The TWIM peripheral driving it is initialized once and shared behind a
+//! mutex. Each sensor driver gets its own [`SensorI2c`] device on the bus, so
+//! their transactions never interleave.
+//!
+//! The bus is pulled up through the sensor rail, see
+//! [`sensor_power`](super::sensor_power). Drivers must power the rail before
+//! using the bus.
+
+use embassy_embedded_hal::shared_bus::asynch::i2c::I2cDevice;
+use embassy_nrf::interrupt::{self, InterruptExt, Priority};
+use embassy_nrf::twim::{self, Twim};
+use embassy_nrf::{Peri, bind_interrupts, peripherals};
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::mutex::Mutex;
+use static_cell::StaticCell;
+
+/// Buffer used to copy writes from flash to RAM, where the TWIM's EasyDMA can
+/// reach them. Sized for the longest register write of the sensor drivers.
+const TX_RAM_BUFFER_LENGTH: usize = 32;
+
+/// The shared sensor I2C bus.
+pub type SensorBus = Mutex<CriticalSectionRawMutex, Twim<'static>>;
+
+/// A sensor driver's device on the shared [`SensorBus`].
+pub type SensorI2c = I2cDevice<'static, CriticalSectionRawMutex, Twim<'static>>;
+
+/// Initialize the TWIM peripheral driving the sensor bus.
+pub fn init(
+    twim: Peri<'static, peripherals::TWISPI0>,
+    sda: Peri<'static, peripherals::P0_14>,
+    scl: Peri<'static, peripherals::P0_15>,
+) -> &'static SensorBus {
+    bind_interrupts!(struct TwimIrq {
+        TWISPI0 => twim::InterruptHandler<peripherals::TWISPI0>;
+    });
+
+    // The SoftDevice BLE controller reserves interrupt priorities 0, 1, and 4.
+    interrupt::TWISPI0.set_priority(Priority::P2);
+
+    // Every sensor on the bus supports fast mode.
+    let mut config = twim::Config::default();
+    config.frequency = twim::Frequency::K400;
+
+    let tx_ram_buffer = {
+        static TX_RAM_BUFFER: StaticCell<[u8; TX_RAM_BUFFER_LENGTH]> = StaticCell::new();
+        TX_RAM_BUFFER.init([0; TX_RAM_BUFFER_LENGTH])
+    };
+
+    static SENSOR_BUS: StaticCell<SensorBus> = StaticCell::new();
+    SENSOR_BUS.init(Mutex::new(Twim::new(
+        twim,
+        TwimIrq,
+        sda,
+        scl,
+        config,
+        tx_ram_buffer,
+    )))
+}
+
+/// Returns a new device on the sensor bus for a sensor driver.
+pub fn device(bus: &'static SensorBus) -> SensorI2c {
+    I2cDevice::new(bus)
+}
@@ -0,0 +1,271 @@
+// SPDX-FileCopyrightText: 2025 Derek Sauer
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! The Nano 33 BLE Rev2 carries a Bosch BMI270 IMU, and a BMM150
+//! magnetometer, on the internal sensor bus:
+//!
+//! - I2C address: 0x68
+//! - INT1: P0.11
+//!
+//! The BMI270's own any-motion detector runs on its feature engine, which only
+//! starts once Bosch's 8 KiB configuration file is uploaded after every power
+//! up. Rather than carrying that blob, the accelerometer samples in its low
+//! power mode and raises INT1 as each sample is ready. The driver compares
+//! consecutive samples, and reports motion once their slope exceeds the same
+//! threshold Bosch's detector defaults to for several samples in a row.
+//!
+//! The CPU wakes briefly at the 12.5 Hz sample rate, while the accelerometer
+//! draws about 10 µA. The sensor rail is only powered while motion sensing is
+//! enabled, see [`Sensor::Imu`].
+
+use embassy_futures::select::{Either, select};
+use embassy_nrf::gpio::{Input, Pull};
+use embassy_nrf::{Peri, peripherals};
+use embassy_time::{Duration, Timer};
+use embedded_hal_async::i2c::I2c;
+
+use super::i2c::SensorI2c;
+use super::sensor_power::{self, SensorPowerGuard};
+use crate::motion;
+use crate::sensors::{self, Sensor, SensorDriver, SensorError};
+
+/// I2C address of the BMI270, its SDO pin tied to ground.
+const ADDRESS: u8 = 0x68;
+
+/// Value of the `CHIP_ID` register identifying a BMI270.
+const CHIP_ID: u8 = 0x24;
+
+/// Registers of the BMI270 used by the driver.
+mod register {
+    pub const CHIP_ID: u8 = 0x00;
+    pub const ACC_X_LSB: u8 = 0x0c;
+    pub const INT_STATUS_1: u8 = 0x1d;
+    pub const ACC_CONF: u8 = 0x40;
+    pub const ACC_RANGE: u8 = 0x41;
+    pub const INT1_IO_CTRL: u8 = 0x53;
+    pub const INT_LATCH: u8 = 0x55;
+    pub const INT_MAP_DATA: u8 = 0x58;
+    pub const PWR_CONF: u8 = 0x7c;
+    pub const PWR_CTRL: u8 = 0x7d;
+    pub const CMD: u8 = 0x7e;
+}
+
+/// `CMD` value resetting the BMI270 to its power on state.
+const CMD_SOFT_RESET: u8 = 0xb6;
+
+/// `ACC_CONF` value: 12.5 Hz output data rate, power optimized filter,
+/// averaging 4 samples.
+const ACC_CONF_LOW_POWER: u8 = 0x25;
+
+/// `ACC_RANGE` value: ±2 g, where 1 g reads as 16384.
+const ACC_RANGE_2G: u8 = 0x00;
+
+/// `INT1_IO_CTRL` value: output enabled, push-pull, active high.
+const INT1_ACTIVE_HIGH: u8 = 0x0a;
+
+/// `INT_LATCH` value: interrupts stay raised until `INT_STATUS_1` is read, so
+/// a sample cannot be missed between two waits.
+const INT_LATCHED: u8 = 0x01;
+
+/// `INT_MAP_DATA` value: data ready raises INT1.
+const INT_MAP_DRDY_INT1: u8 = 0x04;
+
+/// `PWR_CTRL` value: accelerometer enabled, gyroscope and temperature sensor
+/// off.
+const PWR_CTRL_ACC_EN: u8 = 0x04;
+
+/// `PWR_CONF` value: advanced power save off, so registers may be written
+/// back to back.
+const PWR_CONF_NORMAL: u8 = 0x00;
+
+/// `PWR_CONF` value: advanced power save on, between samples.
+const PWR_CONF_POWER_SAVE: u8 = 0x01;
+
+/// Time the BMI270 needs after a soft reset before it answers again.
+const SOFT_RESET_TIME: Duration = Duration::from_millis(2);
+
+/// Time the BMI270 needs between register writes while advanced power save is
+/// on, as it is after a reset.
+const POWER_SAVE_WRITE_DELAY: Duration = Duration::from_micros(450);
+
+/// Change of acceleration on any axis between consecutive samples counted as
+/// motion: 83 mg, the default threshold of Bosch's any-motion detector.
+const MOTION_THRESHOLD: u16 = 1360;
+
+/// Consecutive samples the threshold must be exceeded for motion to be
+/// reported, filtering out a single knock.
+const MOTION_SAMPLES: u8 = 2;
+
+/// Time after reporting motion before watching for it again. A tracker being
+/// carried would otherwise report motion several times a second.
+const MOTION_HOLDOFF: Duration = Duration::from_secs(10);
+
+/// Time to wait after the IMU fails before powering it up again.
+const RETRY_DELAY: Duration = Duration::from_secs(60);
+
+/// Acceleration along the X, Y, and Z axes, in counts of the ±2 g range.
+pub type Acceleration = [i16; 3];
+
+/// Driver for the onboard BMI270 IMU.
+pub struct Imu {
+    i2c:       SensorI2c,
+    interrupt: Input<'static>,
+
+    /// Keeps the sensor rail powered while the IMU is initialized.
+    power: Option<SensorPowerGuard>,
+}
+
+impl Imu {
+    /// Create a new [`Imu`] driver. The IMU is left unpowered until first
+    /// used.
+    pub fn new(i2c: SensorI2c, interrupt: Peri<'static, peripherals::P0_11>) -> Self {
+        Self {
+            i2c,
+            // Driven push-pull by the IMU, and floating while it is unpowered.
+            interrupt: Input::new(interrupt, Pull::Down),
+            power: None,
+        }
+    }
+
+    /// Wait for the IMU to detect motion, initializing it first if needed.
+    pub async fn wait_for_motion(&mut self) -> Result<(), SensorError> {
+        if self.power.is_none() {
+            self.init().await?;
+        }
+
+        let mut previous = None;
+        let mut moving_samples = 0;
+
+        loop {
+            let acceleration = self.read().await?;
+
+            let moved = previous.is_some_and(|previous: Acceleration| {
+                previous
+                    .iter()
+                    .zip(acceleration)
+                    .any(|(previous, current)| previous.abs_diff(current) > MOTION_THRESHOLD)
+            });
+            previous = Some(acceleration);
+
+            if !moved {
+                moving_samples = 0;
+                continue;
+            }
+
+            moving_samples += 1;
+            if moving_samples >= MOTION_SAMPLES {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Power the IMU down until it is next initialized.
+    pub fn power_off(&mut self) {
+        // The rail switches off once the last guard drops.
+        self.power = None;
+    }
+
+    /// Write `value` to the IMU's `register`.
+    async fn write_register(&mut self, register: u8, value: u8) -> Result<(), SensorError> {
+        self.i2c
+            .write(ADDRESS, &[register, value])
+            .await
+            .map_err(|_| SensorError::BusError)
+    }
+
+    /// Read the IMU's registers starting at `register` into `buffer`.
+    async fn read_registers(&mut self, register: u8, buffer: &mut [u8]) -> Result<(), SensorError> {
+        self.i2c
+            .write_read(ADDRESS, &[register], buffer)
+            .await
+            .map_err(|_| SensorError::BusError)
+    }
+}
+
+impl SensorDriver for Imu {
+    type Reading = Acceleration;
+
+    /// Power the IMU, check it is a BMI270, and start sampling the
+    /// accelerometer.
+    async fn init(&mut self) -> Result<(), SensorError> {
+        self.power = Some(sensor_power::power_on().await);
+
+        // The IMU may have been left configured by a previous attempt.
+        self.write_register(register::CMD, CMD_SOFT_RESET).await?;
+        Timer::after(SOFT_RESET_TIME).await;
+
+        let mut chip_id = [0];
+        self.read_registers(register::CHIP_ID, &mut chip_id).await?;
+        if chip_id[0] != CHIP_ID {
+            self.power_off();
+            return Err(SensorError::NotPresent);
+        }
+
+        self.write_register(register::PWR_CONF, PWR_CONF_NORMAL)
+            .await?;
+        Timer::after(POWER_SAVE_WRITE_DELAY).await;
+
+        let configuration = [
+            (register::ACC_CONF, ACC_CONF_LOW_POWER),
+            (register::ACC_RANGE, ACC_RANGE_2G),
+            (register::INT1_IO_CTRL, INT1_ACTIVE_HIGH),
+            (register::INT_LATCH, INT_LATCHED),
+            (register::INT_MAP_DATA, INT_MAP_DRDY_INT1),
+            (register::PWR_CTRL, PWR_CTRL_ACC_EN),
+            (register::PWR_CONF, PWR_CONF_POWER_SAVE),
+        ];
+        for (register, value) in configuration {
+            self.write_register(register, value).await?;
+        }
+
+        defmt::debug!("[imu] BMI270 initialized");
+        Ok(())
+    }
+
+    /// Wait for the next accelerometer sample and read it.
+    async fn read(&mut self) -> Result<Acceleration, SensorError> {
+        self.interrupt.wait_for_high().await;
+
+        // Reading the status releases the latched interrupt.
+        let mut status = [0];
+        self.read_registers(register::INT_STATUS_1, &mut status)
+            .await?;
+
+        let mut data = [0; 6];
+        self.read_registers(register::ACC_X_LSB, &mut data).await?;
+
+        Ok([
+            i16::from_le_bytes([data[0], data[1]]),
+            i16::from_le_bytes([data[2], data[3]]),
+            i16::from_le_bytes([data[4], data[5]]),
+        ])
+    }
+}
+
+/// Task reporting motion detected by the IMU to [`motion::record_motion`]
+/// while motion sensing is enabled. The IMU is powered down while it is
+/// disabled, and after a failure until [`RETRY_DELAY`] elapses.
+#[embassy_executor::task]
+pub async fn imu_task(mut imu: Imu) -> ! {
+    loop {
+        sensors::wait_enabled(Sensor::Imu).await;
+
+        match select(imu.wait_for_motion(), sensors::wait_disabled(Sensor::Imu)).await {
+            Either::First(Ok(())) => {
+                defmt::debug!("[imu] motion detected");
+                motion::record_motion();
+                Timer::after(MOTION_HOLDOFF).await;
+            }
+            Either::First(Err(error)) => {
+                defmt::warn!("[imu] motion detection failed: {}", error);
+                imu.power_off();
+                Timer::after(RETRY_DELAY).await;
+            }
+            Either::Second(()) => {
+                defmt::debug!("[imu] motion sensing disabled");
+                imu.power_off();
+            }
+        }
+    }
+}
@@ -0,0 +1,173 @@
+// SPDX-FileCopyrightText: 2025 Derek Sauer
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! The Nano 33 BLE has an onboard RGB LED. Each color is wired active-low:
+//!
+//! - Red: P0.24
+//! - Green: P0.16
+//! - Blue: P0.06
+//!
+//! The LED shows blue while advertising and green while connected. It shows
+//! red for a few seconds after an error, and blinks white while identifying.
+
+use embassy_futures::select::{Either, Either3, select, select3};
+use embassy_nrf::gpio::{Level, Output, OutputDrive};
+use embassy_nrf::{Peri, peripherals};
+use embassy_time::{Duration, Instant, Timer};
+
+use super::CPU_CYCLES_PER_MS;
+use crate::indicator::{self, Status};
+
+/// Time the LED spends on, then off, while identifying.
+const IDENTIFY_BLINK_PERIOD: Duration = Duration::from_millis(100);
+
+/// Time the LED shows red after an error is reported.
+const ERROR_DURATION: Duration = Duration::from_secs(3);
+
+/// Colors the RGB LED can display by combining its red, green, and blue
+/// elements.
+#[derive(Clone, Copy, PartialEq, defmt::Format)]
+pub enum Color {
+    Off,
+    Red,
+    Green,
+    Blue,
+    White,
+}
+
+/// Driver for the board's RGB LED.
+pub struct Led {
+    red:   Output<'static>,
+    green: Output<'static>,
+    blue:  Output<'static>,
+}
+
+impl Led {
+    /// Create a new [`Led`] driver, initially off.
+    pub fn new(
+        red: Peri<'static, peripherals::P0_24>,
+        green: Peri<'static, peripherals::P0_16>,
+        blue: Peri<'static, peripherals::P0_06>,
+    ) -> Self {
+        // The LED is active-low, drive the pins high to turn it off.
+        Self {
+            red:   Output::new(red, Level::High, OutputDrive::Standard),
+            green: Output::new(green, Level::High, OutputDrive::Standard),
+            blue:  Output::new(blue, Level::High, OutputDrive::Standard),
+        }
+    }
+
+    /// Display `color` on the LED.
+    pub fn set(&mut self, color: Color) {
+        let (red, green, blue) = match color {
+            Color::Off => (false, false, false),
+            Color::Red => (true, false, false),
+            Color::Green => (false, true, false),
+            Color::Blue => (false, false, true),
+            Color::White => (true, true, true),
+        };
+
+        self.red.set_level(Level::from(!red));
+        self.green.set_level(Level::from(!green));
+        self.blue.set_level(Level::from(!blue));
+    }
+
+    /// Show that the device is advertising: blue.
+    pub fn set_advertising(&mut self) {
+        self.set(Color::Blue);
+    }
+
+    /// Show that a central is connected: green.
+    pub fn set_connected(&mut self) {
+        self.set(Color::Green);
+    }
+
+    /// Show that an error occurred: red.
+    pub fn set_error(&mut self) {
+        self.set(Color::Red);
+    }
+
+    /// Show `status`, turning the LED off while idle.
+    fn show(&mut self, status: Status) {
+        match status {
+            Status::Idle => self.set(Color::Off),
+            Status::Advertising => self.set_advertising(),
+            Status::Connected => self.set_connected(),
+        }
+    }
+
+    /// Blink the LED white until `deadline`. A new identify request received
+    /// meanwhile extends the blinking.
+    async fn identify(&mut self, mut deadline: Instant) {
+        let mut lit = false;
+
+        while Instant::now() < deadline {
+            lit = !lit;
+            self.set(if lit { Color::White } else { Color::Off });
+
+            if let Either::Second(duration) = select(
+                Timer::after(IDENTIFY_BLINK_PERIOD),
+                indicator::IDENTIFY.wait(),
+            )
+            .await
+            {
+                deadline = Instant::now() + duration;
+            }
+        }
+    }
+}
+
+/// Task driving the LED from requests posted to the [`indicator`] module.
+/// Shows the device's [`Status`], interrupted by identify requests and
+/// reported errors.
+#[embassy_executor::task]
+pub async fn led_task(mut led: Led) -> ! {
+    loop {
+        led.show(indicator::status());
+
+        match select3(
+            indicator::STATUS_CHANGED.wait(),
+            indicator::IDENTIFY.wait(),
+            indicator::ERROR.wait(),
+        )
+        .await
+        {
+            Either3::First(()) => {}
+            Either3::Second(duration) => led.identify(Instant::now() + duration).await,
+            Either3::Third(()) => {
+                led.set_error();
+                Timer::after(ERROR_DURATION).await;
+            }
+        }
+    }
+}
+
+/// Hardware faults that prevent the board from starting. Each is reported by
+/// blinking the red LED the number of times given by its value.
+#[derive(Clone, Copy, defmt::Format)]
+#[repr(u8)]
+pub enum ErrorCode {
+    /// The external low frequency crystal did not start.
+    LfClockFailed = 2,
+}
+
+/// Blink `code` on the red LED forever: a burst of short pulses, one per unit
+/// of the code's value, followed by a long pause.
+///
+/// Used for faults that occur before the executor and time driver can be
+/// relied upon, so delays are busy waits.
+pub fn blink_error_code(red: Peri<'static, peripherals::P0_24>, code: ErrorCode) -> ! {
+    let mut red = Output::new(red, Level::High, OutputDrive::Standard);
+
+    loop {
+        for _ in 0..code as u8 {
+            red.set_low();
+            cortex_m::asm::delay(200 * CPU_CYCLES_PER_MS);
+            red.set_high();
+            cortex_m::asm::delay(200 * CPU_CYCLES_PER_MS);
+        }
+
+        cortex_m::asm::delay(1500 * CPU_CYCLES_PER_MS);
+    }
+}
@@ -33,10 +33,14 @@
 //! https://docs.nordicsemi.com/bundle/ncs-latest/page/nrfxlib/mpsl/README.html
 
 use embassy_nrf::{Peri, peripherals};
+use embassy_time::Ticker;
 use nrf_mpsl::SessionMem;
-use nrf_sdc::mpsl::{self, MultiprotocolServiceLayer};
+use nrf_sdc::mpsl::{self, Flash, MultiprotocolServiceLayer};
 use static_cell::StaticCell;
 
+use super::power::PowerGuardedFlash;
+use crate::liveness::{self, Task};
+
 /// Number of timeslots the Service Layer will make available to the
 /// application. Two slots is sufficient for flash and temperature operations.
 const NUM_TIMESLOTS: usize = 2;
@@ -50,6 +54,42 @@ pub async fn mpsl_task(mpsl: &'static mpsl::MultiprotocolServiceLayer<'static>)
     mpsl.run().await;
 }
 
+/// Task performing queued flash writes, scheduled by the MPSL around radio
+/// activity. See [`crate::storage`].
+#[embassy_executor::task]
+pub async fn flash_writer_task(flash: PowerGuardedFlash<Flash<'static>>) -> ! {
+    defmt::info!("[mpsl] flash writer task started");
+    crate::storage::run_writer(flash).await
+}
+
+/// Returns the chip's die temperature in hundredths of a degree Celsius.
+///
+/// The MPSL schedules the measurement so it does not interfere with the radio.
+/// Taking a reference to the MPSL ensures it has been initialized.
+pub fn temperature(_mpsl: &MultiprotocolServiceLayer) -> i32 {
+    // SAFETY: The MPSL has been initialized. The measurement is returned in
+    // quarters of a degree Celsius.
+    let quarter_celsius = unsafe { mpsl::raw::mpsl_temperature_get() };
+    quarter_celsius * 25
+}
+
+/// Task periodically checking the die temperature, throttling the radio when
+/// the chip runs hot.
+#[embassy_executor::task]
+pub async fn thermal_task(mpsl: &'static MultiprotocolServiceLayer<'static>) -> ! {
+    let mut ticker = Ticker::every(crate::thermal::CHECK_INTERVAL);
+    liveness::register(Task::Mpsl);
+
+    loop {
+        crate::thermal::update(temperature(mpsl));
+
+        // The measurement completing shows the MPSL is still servicing
+        // requests.
+        liveness::check_in(Task::Mpsl);
+        ticker.next().await;
+    }
+}
+
 /// Initialize the Multiprotocol Service Layer.
 ///
 /// # Panic
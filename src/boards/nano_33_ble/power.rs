@@ -0,0 +1,205 @@
+// SPDX-FileCopyrightText: 2025 Derek Sauer
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Power failure detection protecting flash writes, and System OFF.
+//!
+//! Erasing or writing flash while the supply sags can corrupt the page being
+//! written. The POWER peripheral's power failure comparator (POF) raises a
+//! warning event when VDD drops below [`POF_THRESHOLD`]. Flash operations
+//! check for the warning before starting and between chunks of a write, and
+//! refuse to proceed once it fired.
+//!
+//! The MPSL owns the POWER interrupt, so the warning event is polled rather
+//! than handled in an interrupt.
+//!
+//! System OFF is the chip's deepest sleep. Everything but the GPIO sense
+//! logic is powered down, and the chip resets when a wake pin is driven low.
+
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use embassy_nrf::gpio::{Pin, Port};
+use embassy_nrf::pac;
+use embassy_nrf::pac::gpio::vals::{Dir, Input, Pull, Sense};
+use embassy_nrf::pac::power::vals::Threshold;
+use embedded_storage_async::nor_flash::{
+    ErrorType, NorFlash, NorFlashError, NorFlashErrorKind, ReadNorFlash,
+};
+
+/// VDD below which a power failure is assumed.
+///
+/// The board's regulator supplies 3.3 V. A sag to 2.8 V, the highest threshold
+/// the comparator supports, means the input supply is failing while leaving
+/// the most time to give up before the nRF52840's 1.7 V minimum is reached.
+pub const POF_THRESHOLD: Threshold = Threshold::V28;
+
+/// Largest chunk written between power failure checks. A multiple of the
+/// flash's word size.
+const WRITE_CHUNK_LENGTH: usize = 64;
+
+/// Latched once a power failure warning is seen, until reset.
+static POWER_FAILING: AtomicBool = AtomicBool::new(false);
+
+/// Enable the power failure comparator at [`POF_THRESHOLD`].
+pub fn enable_power_failure_comparator() {
+    let power = pac::POWER;
+
+    power.events_pofwarn().write_value(0);
+    power.pofcon().write(|w| {
+        w.set_pof(true);
+        w.set_threshold(POF_THRESHOLD);
+    });
+}
+
+/// Returns `true` if the supply has dropped below [`POF_THRESHOLD`] since
+/// boot.
+pub fn is_power_failing() -> bool {
+    if POWER_FAILING.load(Ordering::Relaxed) {
+        return true;
+    }
+
+    let power = pac::POWER;
+    if power.events_pofwarn().read() == 0 {
+        return false;
+    }
+
+    power.events_pofwarn().write_value(0);
+    POWER_FAILING.store(true, Ordering::Relaxed);
+    defmt::error!("[power] supply dropped below the power failure threshold");
+
+    true
+}
+
+/// Configure `pin` as a pulled up input waking the chip from System OFF when
+/// driven low, such as by a button to ground.
+pub fn wake_on_low(pin: &impl Pin) {
+    let port = match pin.port() {
+        Port::Port0 => pac::P0,
+        Port::Port1 => pac::P1,
+    };
+
+    port.pin_cnf(usize::from(pin.pin())).write(|w| {
+        w.set_dir(Dir::INPUT);
+        w.set_input(Input::CONNECT);
+        w.set_pull(Pull::PULLUP);
+        w.set_sense(Sense::LOW);
+    });
+}
+
+/// Shut down the BLE controller and the MPSL, and enter System OFF. The chip
+/// resets when a pin configured by [`wake_on_low`] is driven low.
+pub fn system_off() -> ! {
+    // SAFETY: Nothing runs after this function, so neither the controller
+    // nor the MPSL is used once shut down. The controller must be disabled
+    // before the MPSL it relies on.
+    unsafe {
+        nrf_sdc::raw::sdc_disable();
+        nrf_sdc::mpsl::raw::mpsl_uninit();
+    }
+
+    pac::POWER.systemoff().write(|w| w.set_systemoff(true));
+
+    // A debugger keeps the chip in an emulated System OFF, where execution
+    // continues.
+    loop {
+        cortex_m::asm::wfe();
+    }
+}
+
+/// Set the GPREGRET retention register, which survives a soft reset, to
+/// `value`. Bootloaders read it at boot to learn what the application asked
+/// of them.
+#[cfg(feature = "dfu")]
+pub fn set_retained_register(value: u8) {
+    pac::POWER.gpregret().write(|w| w.set_gpregret(value));
+}
+
+/// Errors of a [`PowerGuardedFlash`].
+#[derive(Debug)]
+pub enum PowerGuardedFlashError<E> {
+    /// The underlying flash failed.
+    Flash(E),
+
+    /// The operation was refused or aborted because the supply is failing.
+    PowerFailure,
+}
+
+impl<E: NorFlashError> NorFlashError for PowerGuardedFlashError<E> {
+    fn kind(&self) -> NorFlashErrorKind {
+        match self {
+            Self::Flash(error) => error.kind(),
+            Self::PowerFailure => NorFlashErrorKind::Other,
+        }
+    }
+}
+
+/// Flash refusing to erase or write once the supply is failing.
+///
+/// Page erases cannot be interrupted and are only checked before starting.
+/// Writes are split into chunks and abort between chunks.
+pub struct PowerGuardedFlash<F> {
+    flash: F,
+}
+
+impl<F> PowerGuardedFlash<F> {
+    pub fn new(flash: F) -> Self {
+        Self { flash }
+    }
+}
+
+impl<F: ErrorType> ErrorType for PowerGuardedFlash<F> {
+    type Error = PowerGuardedFlashError<F::Error>;
+}
+
+impl<F: ReadNorFlash> ReadNorFlash for PowerGuardedFlash<F> {
+    const READ_SIZE: usize = F::READ_SIZE;
+
+    async fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
+        self.flash
+            .read(offset, bytes)
+            .await
+            .map_err(PowerGuardedFlashError::Flash)
+    }
+
+    fn capacity(&self) -> usize {
+        self.flash.capacity()
+    }
+}
+
+impl<F: NorFlash> NorFlash for PowerGuardedFlash<F> {
+    const ERASE_SIZE: usize = F::ERASE_SIZE;
+    const WRITE_SIZE: usize = F::WRITE_SIZE;
+
+    async fn erase(&mut self, from: u32, to: u32) -> Result<(), Self::Error> {
+        for page in (from..to).step_by(F::ERASE_SIZE) {
+            if is_power_failing() {
+                return Err(PowerGuardedFlashError::PowerFailure);
+            }
+
+            self.flash
+                .erase(page, page + F::ERASE_SIZE as u32)
+                .await
+                .map_err(PowerGuardedFlashError::Flash)?;
+        }
+
+        Ok(())
+    }
+
+    async fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Self::Error> {
+        let mut offset = offset;
+
+        for chunk in bytes.chunks(WRITE_CHUNK_LENGTH) {
+            if is_power_failing() {
+                return Err(PowerGuardedFlashError::PowerFailure);
+            }
+
+            self.flash
+                .write(offset, chunk)
+                .await
+                .map_err(PowerGuardedFlashError::Flash)?;
+            offset += chunk.len() as u32;
+        }
+
+        Ok(())
+    }
+}
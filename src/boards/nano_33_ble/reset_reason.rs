@@ -0,0 +1,21 @@
+// SPDX-FileCopyrightText: 2025 Derek Sauer
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Why the chip last reset, read from the POWER peripheral's RESETREAS
+//! register. The register accumulates reasons until cleared, so it is cleared
+//! once read to report only the latest reset next boot.
+
+use embassy_nrf::pac;
+pub use lookpoint_logic::reset_reason::ResetReason;
+
+/// Read and clear the reason of the last reset.
+pub fn take() -> ResetReason {
+    let power = pac::POWER;
+    let resetreas = power.resetreas().read();
+
+    // The register's bits are cleared by writing ones to them.
+    power.resetreas().write_value(resetreas);
+
+    ResetReason::from_resetreas(resetreas.0)
+}
@@ -38,14 +38,42 @@ use rand_chacha::ChaChaRng;
 use rand_core::SeedableRng;
 use static_cell::StaticCell;
 use trouble_host::Stack;
-use trouble_host::prelude::DefaultPacketPool;
 
-use crate::ble::BleResources;
+use crate::ble::connection_params::CONNECTION_CONFIG;
+use crate::ble::{BlePacketPool, BleResources, MAX_CONNECTIONS, advertise, privacy};
 
 /// Amount of memory needed by the Softdevice.
-const SDC_MEM: usize = 1432;
+///
+/// The controller's memory is allocated for its connections, advertising sets
+/// and packet buffers. Both the full and the `minimal_controller` builds use
+/// [`MAX_CONNECTIONS`] peripheral connections with the default buffers, and the
+/// features the minimal build leaves out do not change that allocation, so
+/// both need the same amount. The controller logs the amount it needs if this
+/// is too small.
+const SDC_MEM: usize = 2440;
+
+/// Errors initializing the BLE stack.
+#[derive(defmt::Format)]
+pub enum BleInitError {
+    /// The controller's random number generator failed to seed one of the
+    /// host's generators, named here.
+    RandomSeed(&'static str),
+
+    /// The Softdevice Controller rejected its configuration or failed to
+    /// start.
+    Controller(nrf_sdc::Error),
+}
+
+impl From<nrf_sdc::Error> for BleInitError {
+    fn from(error: nrf_sdc::Error) -> Self {
+        Self::Controller(error)
+    }
+}
 
 /// Initialize the BLE controller and host.
+///
+/// The peripherals and static memory handed to the stack are consumed even if
+/// it fails, so initialization cannot be retried without a reset.
 #[allow(clippy::too_many_arguments)]
 pub fn init_ble_stack<'stack>(
     ppi_ch17: Peri<'static, peripherals::PPI_CH17>,
@@ -63,7 +91,8 @@ pub fn init_ble_stack<'stack>(
     rng: Peri<'static, peripherals::RNG>,
     mpsl: &'static nrf_sdc::mpsl::MultiprotocolServiceLayer<'static>,
     address: trouble_host::Address,
-) -> Stack<'stack, SoftdeviceController<'static>, DefaultPacketPool> {
+    irk: [u8; 16],
+) -> Result<Stack<'stack, SoftdeviceController<'static>, BlePacketPool>, BleInitError> {
     let softdevice_peripherals = nrf_sdc::Peripherals::new(
         ppi_ch17, ppi_ch18, ppi_ch20, ppi_ch21, ppi_ch22, ppi_ch23, ppi_ch24, ppi_ch25, ppi_ch26,
         ppi_ch27, ppi_ch28, ppi_ch29,
@@ -83,12 +112,18 @@ pub fn init_ble_stack<'stack>(
     // The BLE controller will own the RNG peripheral. Use it to seed another random
     // number generator for the host. The host only uses this to seed its internal
     // RNG and this RNG will drop at end of function.
-    let mut host_rng = match ChaChaRng::from_rng(&mut controller_rng) {
-        Ok(rng) => rng,
-        Err(_) => {
-            defmt::panic!("[ble] failed to initialize the BLE host's random number generator",)
-        }
-    };
+    let mut host_rng =
+        ChaChaRng::from_rng(&mut controller_rng).map_err(|_| BleInitError::RandomSeed("host"))?;
+
+    // Likewise seed the generator drawing the advertising interval jitter.
+    let jitter_rng = ChaChaRng::from_rng(&mut controller_rng)
+        .map_err(|_| BleInitError::RandomSeed("advertising interval jitter"))?;
+    advertise::seed_interval_jitter(jitter_rng);
+
+    // And the generator drawing resolvable private addresses.
+    let privacy_rng = ChaChaRng::from_rng(&mut controller_rng)
+        .map_err(|_| BleInitError::RandomSeed("private address"))?;
+    privacy::init(irk, privacy_rng);
 
     // The Softdevice BLE controller reserves some memory for its own state.
     // Will panic if not enough memory is provided. A log message will be emitted
@@ -105,23 +140,13 @@ pub fn init_ble_stack<'stack>(
         })
     };
 
-    let controller = match build_softdevice(
+    let controller = build_softdevice(
         softdevice_peripherals,
         controller_rng,
         controller_memory,
         mpsl,
-    ) {
-        Ok(sdc) => {
-            defmt::info!("[sdc] Softdevice BLE controller initialized");
-            sdc
-        }
-        Err(error) => {
-            defmt::panic!(
-                "[sdc] failed to initialize the Softdevice BLE controller, error code: {}",
-                error
-            )
-        }
-    };
+    )?;
+    defmt::info!("[sdc] Softdevice BLE controller initialized");
 
     // Memory reserved for the BLE host's internal state.
     let host_resources = {
@@ -129,13 +154,17 @@ pub fn init_ble_stack<'stack>(
         HOST_RESOURCES.init_with(BleResources::new)
     };
 
-    trouble_host::new(controller, host_resources)
+    let stack = trouble_host::new(controller, host_resources)
         .set_random_address(address)
-        .set_random_generator_seed(&mut host_rng)
+        .set_random_generator_seed(&mut host_rng);
+
+    stack.set_io_capabilities(CONNECTION_CONFIG.pairing.io_capabilities());
+    Ok(stack)
 }
 
 /// Convenience function to construct a [`SoftdeviceController`] with simple
 /// error return.
+#[cfg(not(feature = "minimal_controller"))]
 pub fn build_softdevice<'a>(
     softdevice_peripherals: nrf_sdc::Peripherals<'a>,
     rng_driver: &'a mut Rng<'a, peripherals::RNG, Async>,
@@ -148,6 +177,29 @@ pub fn build_softdevice<'a>(
         .support_dle_peripheral()?
         .support_phy_update_peripheral()?
         .support_le_2m_phy()?
-        .peripheral_count(1)?
+        .support_le_coded_phy()?
+        .support_ext_adv()?
+        .peripheral_count(MAX_CONNECTIONS as u8)?
+        .build(softdevice_peripherals, rng_driver, mpsl, softdevice_memory)
+}
+
+/// Convenience function to construct a [`SoftdeviceController`] with simple
+/// error return.
+///
+/// Minimal controller for power optimized builds: only legacy advertising and
+/// a peripheral connection on the 1M PHY with the default packet length.
+/// Leaving out data length extension and PHY updates spares the controller the
+/// procedures negotiating them on every connection.
+#[cfg(feature = "minimal_controller")]
+pub fn build_softdevice<'a>(
+    softdevice_peripherals: nrf_sdc::Peripherals<'a>,
+    rng_driver: &'a mut Rng<'a, peripherals::RNG, Async>,
+    softdevice_memory: &'a mut nrf_sdc::Mem<SDC_MEM>,
+    mpsl: &'a MultiprotocolServiceLayer,
+) -> Result<SoftdeviceController<'a>, nrf_sdc::Error> {
+    nrf_sdc::Builder::new()?
+        .support_adv()?
+        .support_peripheral()?
+        .peripheral_count(MAX_CONNECTIONS as u8)?
         .build(softdevice_peripherals, rng_driver, mpsl, softdevice_memory)
 }
@@ -0,0 +1,110 @@
+// SPDX-FileCopyrightText: 2025 Derek Sauer
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! The Nano 33 BLE powers its onboard sensors from a switchable 3.3 V rail and
+//! pulls up their internal I2C bus through a second switch:
+//!
+//! - Sensor rail enable (VDD_ENV_ENABLE): P0.22
+//! - I2C pull-up enable (R_PULLUP): P1.00
+//!
+//! Both are off at boot. The sensor drivers (IMU, HTS221, LPS22HB, APDS9960)
+//! each hold a [`SensorPowerGuard`] while they are active, the rail stays on
+//! only while at least one guard exists.
+
+use core::cell::RefCell;
+
+use embassy_nrf::gpio::{Level, Output, OutputDrive};
+use embassy_nrf::{Peri, peripherals};
+use embassy_sync::blocking_mutex::Mutex;
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_time::{Duration, Instant, Timer};
+
+/// Time the sensors need after the rail powers up before they answer on the
+/// I2C bus. The BMI270 IMU needs 2 ms after power on, as after a soft reset,
+/// and the BMM150 magnetometer reaches its suspend mode 1 ms after power on.
+/// The margin covers the rail's switch and decoupling charging up.
+const SETTLING_TIME: Duration = Duration::from_millis(5);
+
+/// The sensor rail and the number of drivers using it.
+struct SensorRail {
+    rail:    Output<'static>,
+    pullups: Output<'static>,
+
+    /// Number of [`SensorPowerGuard`]s alive.
+    users: u8,
+
+    /// When the rail was last powered up.
+    powered_at: Instant,
+}
+
+/// The sensor rail, once the board initialized it.
+static SENSOR_RAIL: Mutex<CriticalSectionRawMutex, RefCell<Option<SensorRail>>> =
+    Mutex::new(RefCell::new(None));
+
+/// Take control of the sensor rail, initially off.
+pub fn init(rail: Peri<'static, peripherals::P0_22>, pullups: Peri<'static, peripherals::P1_00>) {
+    let sensor_rail = SensorRail {
+        rail:       Output::new(rail, Level::Low, OutputDrive::Standard),
+        pullups:    Output::new(pullups, Level::Low, OutputDrive::Standard),
+        users:      0,
+        powered_at: Instant::now(),
+    };
+
+    SENSOR_RAIL.lock(|sensor_rail_cell| sensor_rail_cell.replace(Some(sensor_rail)));
+}
+
+/// Keeps the sensor rail powered while it exists.
+pub struct SensorPowerGuard {
+    _private: (),
+}
+
+impl Drop for SensorPowerGuard {
+    fn drop(&mut self) {
+        SENSOR_RAIL.lock(|sensor_rail| {
+            let mut sensor_rail = sensor_rail.borrow_mut();
+            let Some(sensor_rail) = sensor_rail.as_mut() else {
+                return;
+            };
+
+            sensor_rail.users = sensor_rail.users.saturating_sub(1);
+            if sensor_rail.users == 0 {
+                sensor_rail.pullups.set_low();
+                sensor_rail.rail.set_low();
+                defmt::debug!("[sensor_power] sensor rail off");
+            }
+        });
+    }
+}
+
+/// Power the sensor rail, if it is not already, and wait until the sensors are
+/// ready for their first I2C transaction. The rail stays powered until the
+/// returned guard, and any others, are dropped.
+pub async fn power_on() -> SensorPowerGuard {
+    let powered_at = SENSOR_RAIL.lock(|sensor_rail| {
+        let mut sensor_rail = sensor_rail.borrow_mut();
+
+        // UNWRAP: Infallible. The board initializes the rail before spawning
+        // the sensor drivers.
+        let sensor_rail = sensor_rail.as_mut().unwrap();
+
+        if sensor_rail.users == 0 {
+            sensor_rail.rail.set_high();
+            sensor_rail.pullups.set_high();
+            sensor_rail.powered_at = Instant::now();
+            defmt::debug!("[sensor_power] sensor rail on");
+        }
+
+        sensor_rail.users += 1;
+        sensor_rail.powered_at
+    });
+
+    // Created before waiting so the count is released if the caller gives up.
+    let guard = SensorPowerGuard { _private: () };
+
+    // A driver joining a rail powered up moments ago still waits for it to
+    // settle.
+    Timer::at(powered_at + SETTLING_TIME).await;
+
+    guard
+}
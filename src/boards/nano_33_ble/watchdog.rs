@@ -0,0 +1,65 @@
+// SPDX-FileCopyrightText: 2025 Derek Sauer
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! The watchdog resets the chip unless it is fed regularly. It is only fed
+//! while every critical task is alive, see [`crate::liveness`].
+//!
+//! Once started the watchdog cannot be stopped, or reconfigured, until the
+//! chip resets. It pauses while the CPU is halted by a debugger.
+
+use embassy_nrf::wdt::{self, Watchdog, WatchdogHandle};
+use embassy_nrf::{Peri, peripherals};
+use embassy_time::{Duration, Ticker};
+
+use crate::liveness;
+
+/// Ticks of the 32.768 kHz low frequency clock before the watchdog resets the
+/// chip: 10 seconds, long enough for an orderly reset to flush queued flash
+/// writes.
+const TIMEOUT_TICKS: u32 = 10 * 32_768;
+
+/// How often the liveness of the critical tasks is checked and the watchdog
+/// fed.
+const FEED_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Start the watchdog and return the handle used to feed it.
+///
+/// # Panic
+///
+/// Panics if the watchdog is already running with a different number of
+/// handles, left over from firmware running before a soft reset.
+pub fn init(wdt: Peri<'static, peripherals::WDT>) -> WatchdogHandle {
+    // A watchdog left running by a soft reset keeps its configuration.
+    let config = wdt::Config::try_new(&wdt).unwrap_or(wdt::Config {
+        timeout_ticks: TIMEOUT_TICKS,
+        ..Default::default()
+    });
+
+    match Watchdog::try_new(wdt, config) {
+        Ok((_watchdog, [handle])) => {
+            defmt::info!("[watchdog] started");
+            handle
+        }
+        Err(_) => defmt::panic!("[watchdog] already running with an incompatible configuration"),
+    }
+}
+
+/// Task feeding the watchdog while every critical task is alive.
+///
+/// Once a task goes stale the device is reset, flushing queued flash writes
+/// first. Should that hang too, the unfed watchdog resets the chip.
+#[embassy_executor::task]
+pub async fn watchdog_task(mut handle: WatchdogHandle) -> ! {
+    let mut ticker = Ticker::every(FEED_INTERVAL);
+
+    loop {
+        if let Some(task) = liveness::stale_task() {
+            defmt::error!("[watchdog] {} task stopped responding, resetting", task);
+            crate::system::reset().await;
+        }
+
+        handle.pet();
+        ticker.next().await;
+    }
+}
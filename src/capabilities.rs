@@ -0,0 +1,59 @@
+// SPDX-FileCopyrightText: 2025 Derek Sauer
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Optional hardware and features present on a unit, reported to gateways so
+//! they need not probe each service.
+
+use core::sync::atomic::{AtomicU16, Ordering};
+
+use crate::sensors::Sensor;
+
+/// Bits of the capabilities mask.
+///
+/// Bit assignments are part of the capabilities characteristic's format and
+/// must remain stable. Sensors use the same bits as in the enabled sensor
+/// mask.
+#[derive(Clone, Copy, defmt::Format)]
+#[repr(u16)]
+pub enum Capability {
+    /// Inertial measurement unit, feeding motion detection.
+    Imu         = Sensor::Imu as u16,
+
+    /// Ambient temperature sensor.
+    Temperature = Sensor::Temperature as u16,
+
+    /// Relative humidity sensor.
+    Humidity    = Sensor::Humidity as u16,
+
+    /// Barometric pressure sensor.
+    Pressure    = Sensor::Pressure as u16,
+
+    /// Ambient light sensor.
+    Light       = Sensor::Light as u16,
+
+    /// Status indicator able to identify the unit.
+    Indicator   = 1 << 8,
+
+    /// Beacon identities can be provisioned.
+    Beacon      = 1 << 9,
+}
+
+/// Capabilities provided by the firmware on every board.
+const FIRMWARE_CAPABILITIES: u16 = Capability::Beacon as u16;
+
+/// Capabilities of this unit, computed at boot.
+static CAPABILITIES: AtomicU16 = AtomicU16::new(0);
+
+/// Compute the unit's capabilities from the `hardware` capabilities reported
+/// by its board.
+pub fn init(hardware: u16) {
+    let capabilities = hardware | FIRMWARE_CAPABILITIES;
+    CAPABILITIES.store(capabilities, Ordering::Relaxed);
+    defmt::info!("[capabilities] capabilities: {:#06x}", capabilities);
+}
+
+/// Returns the unit's capabilities mask.
+pub fn capabilities() -> u16 {
+    CAPABILITIES.load(Ordering::Relaxed)
+}
@@ -0,0 +1,66 @@
+// SPDX-FileCopyrightText: 2025 Derek Sauer
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Device configuration provisioned at runtime and persisted to flash.
+//!
+//! The configuration is loaded once at startup with [`load`]. Changes made with
+//! [`update`] take effect immediately and are written back to flash as a
+//! deferred write.
+
+use core::cell::Cell;
+
+use embassy_sync::blocking_mutex::Mutex;
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+pub use lookpoint_logic::config::DeviceConfig;
+use lookpoint_logic::config::ENCODED_LENGTH;
+
+use crate::storage::{self, StorageError, WritePriority};
+
+/// Storage page holding the device configuration.
+const CONFIG_PAGE: u32 = 0;
+
+/// Current device configuration.
+static DEVICE_CONFIG: Mutex<CriticalSectionRawMutex, Cell<DeviceConfig>> =
+    Mutex::new(Cell::new(DeviceConfig::DEFAULT));
+
+/// Load the device configuration from flash, falling back to the defaults if
+/// none was stored.
+pub fn load() -> DeviceConfig {
+    let mut bytes = [0; ENCODED_LENGTH];
+    let config = match storage::read(CONFIG_PAGE, &mut bytes) {
+        Ok(()) => DeviceConfig::decode(&bytes),
+        Err(error) => {
+            defmt::error!(
+                "[config] failed to read the device configuration: {}",
+                error
+            );
+            None
+        }
+    };
+
+    let config = config.unwrap_or_else(|| {
+        defmt::info!("[config] no device configuration stored, using defaults");
+        DeviceConfig::DEFAULT
+    });
+
+    DEVICE_CONFIG.lock(|device_config| device_config.set(config));
+    config
+}
+
+/// Returns the current device configuration.
+pub fn get() -> DeviceConfig {
+    DEVICE_CONFIG.lock(|device_config| device_config.get())
+}
+
+/// Modify the device configuration with `change` and persist it.
+pub fn update(change: impl FnOnce(&mut DeviceConfig)) -> Result<(), StorageError> {
+    let config = DEVICE_CONFIG.lock(|device_config| {
+        let mut config = device_config.get();
+        change(&mut config);
+        device_config.set(config);
+        config
+    });
+
+    storage::try_write(CONFIG_PAGE, &config.encode(), WritePriority::Deferred)
+}
@@ -0,0 +1,131 @@
+// SPDX-FileCopyrightText: 2025 Derek Sauer
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Board independent requests for the device's status indicator.
+//!
+//! The BLE stack and application logic post requests here. Each board support
+//! module owns its indicator hardware (such as an LED) and acts on them.
+
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::signal::Signal;
+use embassy_time::Duration;
+
+/// Signaled with the duration the device should identify itself for.
+pub static IDENTIFY: Signal<CriticalSectionRawMutex, Duration> = Signal::new();
+
+/// Ask the board to conspicuously identify itself for `duration`, after which
+/// the indicator returns to its previous state.
+pub fn identify(duration: Duration) {
+    IDENTIFY.signal(duration);
+}
+
+/// Alert levels of the Immediate Alert Service.
+#[derive(Clone, Copy, PartialEq, defmt::Format)]
+#[repr(u8)]
+pub enum AlertLevel {
+    /// Stop alerting.
+    NoAlert = 0,
+
+    /// Alert briefly.
+    Mild    = 1,
+
+    /// Alert until stopped, or for as long as the board allows.
+    High    = 2,
+}
+
+impl TryFrom<u8> for AlertLevel {
+    type Error = u8;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::NoAlert),
+            1 => Ok(Self::Mild),
+            2 => Ok(Self::High),
+            _ => Err(value),
+        }
+    }
+}
+
+/// Signaled with the level the device should alert at, such as by beeping to
+/// be found.
+pub static ALERT: Signal<CriticalSectionRawMutex, AlertLevel> = Signal::new();
+
+/// Time the indicator identifies for during a mild alert.
+const MILD_ALERT_IDENTIFY: Duration = Duration::from_secs(2);
+
+/// Time the indicator identifies for during a high alert.
+const HIGH_ALERT_IDENTIFY: Duration = Duration::from_secs(30);
+
+/// Ask the board to alert at `level`, beeping if it can. The indicator
+/// identifies the device alongside, so a board without a buzzer, or a tracker
+/// in a noisy place, can still be found.
+#[cfg_attr(feature = "beacon_only", allow(dead_code))]
+pub fn alert(level: AlertLevel) {
+    ALERT.signal(level);
+
+    // Identifying for no time stops the indicator identifying.
+    identify(match level {
+        AlertLevel::NoAlert => Duration::from_ticks(0),
+        AlertLevel::Mild => MILD_ALERT_IDENTIFY,
+        AlertLevel::High => HIGH_ALERT_IDENTIFY,
+    });
+}
+
+/// State of the device shown by the indicator while not identifying.
+#[derive(Clone, Copy, PartialEq, defmt::Format)]
+pub enum Status {
+    /// Neither advertising nor connected.
+    Idle,
+
+    /// Advertising, waiting for a central to connect.
+    Advertising,
+
+    /// At least one central is connected. Takes precedence over advertising,
+    /// which continues while connected.
+    Connected,
+}
+
+/// Whether the device is advertising.
+static ADVERTISING: AtomicBool = AtomicBool::new(false);
+
+/// Whether any central is connected.
+static CONNECTED: AtomicBool = AtomicBool::new(false);
+
+/// Signaled whenever the [`Status`] may have changed.
+pub static STATUS_CHANGED: Signal<CriticalSectionRawMutex, ()> = Signal::new();
+
+/// Signaled when an error occurred that the user should notice, such as a
+/// failed flash write.
+pub static ERROR: Signal<CriticalSectionRawMutex, ()> = Signal::new();
+
+/// Record whether the device is advertising.
+pub fn set_advertising(advertising: bool) {
+    ADVERTISING.store(advertising, Ordering::Relaxed);
+    STATUS_CHANGED.signal(());
+}
+
+/// Record whether any central is connected.
+pub fn set_connected(connected: bool) {
+    CONNECTED.store(connected, Ordering::Relaxed);
+    STATUS_CHANGED.signal(());
+}
+
+/// Returns the current [`Status`] of the device.
+pub fn status() -> Status {
+    if CONNECTED.load(Ordering::Relaxed) {
+        Status::Connected
+    } else if ADVERTISING.load(Ordering::Relaxed) {
+        Status::Advertising
+    } else {
+        Status::Idle
+    }
+}
+
+/// Ask the board to briefly show that an error occurred, after which the
+/// indicator returns to the current [`Status`].
+pub fn report_error() {
+    ERROR.signal(());
+}
@@ -0,0 +1,145 @@
+// SPDX-FileCopyrightText: 2025 Derek Sauer
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Liveness of the critical tasks, gating the watchdog.
+//!
+//! Each critical task registers and then checks in regularly. The watchdog is
+//! only fed while every registered task has checked in within its timeout, so
+//! a single hung task resets the device rather than only a fully stalled
+//! system.
+//!
+//! Tasks check in from points where they made progress, never from a timer
+//! running alongside them, which would keep checking in while the task itself
+//! is stuck. A task waiting on something that may legitimately never happen,
+//! such as a button press, waits [`idle`] rather than checking in.
+
+use core::future::{Future, poll_fn};
+use core::pin::pin;
+use core::sync::atomic::{AtomicU8, AtomicU32, Ordering};
+
+use embassy_time::{Duration, Instant};
+
+/// Critical tasks monitored for liveness.
+#[derive(Clone, Copy, defmt::Format)]
+#[repr(u8)]
+pub enum Task {
+    /// The BLE host's event loop, checked in whenever it handles an event.
+    /// Restarting the advertiser is one, so it is only monitored while
+    /// advertising.
+    BleRunner   = 0,
+
+    /// The advertising task, or the broadcast loop of a beacon build, checked
+    /// in on every iteration of its loop.
+    Advertising = 1,
+
+    /// The Multiprotocol Service Layer, checked in whenever it completes a
+    /// die temperature measurement.
+    Mpsl        = 2,
+}
+
+/// Number of monitored tasks.
+const TASK_COUNT: usize = 3;
+
+/// Every monitored task.
+const ALL_TASKS: [Task; TASK_COUNT] = [Task::BleRunner, Task::Advertising, Task::Mpsl];
+
+impl Task {
+    /// Longest time between check ins before the task is considered hung.
+    pub const fn timeout(self) -> Duration {
+        match self {
+            // Advertising restarts at least once per window.
+            Task::BleRunner | Task::Advertising => {
+                Duration::from_secs(3 * crate::ble::advertise::MAX_ADVERTISING_WINDOW.as_secs())
+            }
+            // The MPSL checks in once per thermal check.
+            Task::Mpsl => Duration::from_secs(3 * crate::thermal::CHECK_INTERVAL.as_secs()),
+        }
+    }
+
+    /// Bit of this task in the mask of registered tasks.
+    const fn bit(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+/// Mask of the tasks that registered. Tasks that have not registered yet are
+/// not monitored.
+static REGISTERED: AtomicU8 = AtomicU8::new(0);
+
+/// Time of each task's last check in, in milliseconds since boot.
+static HEARTBEATS: [AtomicU32; TASK_COUNT] = [const { AtomicU32::new(0) }; TASK_COUNT];
+
+/// Returns the time since boot in milliseconds, wrapping after about 49 days.
+fn now_millis() -> u32 {
+    Instant::now().as_millis() as u32
+}
+
+/// Start monitoring `task`, which must then check in within its timeout.
+pub fn register(task: Task) {
+    check_in(task);
+    REGISTERED.fetch_or(task.bit(), Ordering::Relaxed);
+}
+
+/// Record that `task` is alive.
+pub fn check_in(task: Task) {
+    HEARTBEATS[task as usize].store(now_millis(), Ordering::Relaxed);
+}
+
+/// Returns the first registered task that has not checked in within its
+/// timeout, if any.
+pub fn stale_task() -> Option<Task> {
+    let registered = REGISTERED.load(Ordering::Relaxed);
+    let now = now_millis();
+
+    ALL_TASKS.into_iter().find(|&task| {
+        let last_check_in = HEARTBEATS[task as usize].load(Ordering::Relaxed);
+        registered & task.bit() != 0
+            && now.wrapping_sub(last_check_in) > task.timeout().as_millis() as u32
+    })
+}
+
+/// Stop monitoring `task`, until it registers again.
+fn unregister(task: Task) {
+    REGISTERED.fetch_and(!task.bit(), Ordering::Relaxed);
+}
+
+/// Register `task` and run `future`, checking in each time it is polled.
+///
+/// For tasks built around a single long running future, such as the BLE
+/// host's event loop, with no loop of their own to check in from. The future
+/// is polled each time it handles an event, so the task goes stale if no
+/// event is handled within its timeout.
+pub async fn monitor<F: Future>(task: Task, future: F) -> F::Output {
+    register(task);
+    let mut future = pin!(future);
+
+    poll_fn(|context| {
+        check_in(task);
+        future.as_mut().poll(context)
+    })
+    .await
+}
+
+/// Stop monitoring `tasks` while waiting for `future`, which may never
+/// complete without that meaning they are hung. They register again once it
+/// completes or is dropped.
+pub async fn idle<F: Future>(tasks: &[Task], future: F) -> F::Output {
+    /// Registers the tasks again when dropped.
+    struct Idle<'a>(&'a [Task]);
+
+    impl Drop for Idle<'_> {
+        fn drop(&mut self) {
+            for &task in self.0 {
+                register(task);
+            }
+        }
+    }
+
+    for &task in tasks {
+        unregister(task);
+    }
+
+    let _idle = Idle(tasks);
+    future.await
+}
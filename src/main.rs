@@ -6,34 +6,350 @@
 #![no_main]
 #![no_std]
 
+mod battery;
 mod ble;
 mod boards;
+mod capabilities;
+mod config;
+mod indicator;
+mod liveness;
+mod motion;
+mod sensors;
+mod settings;
+mod storage;
+mod system;
+mod thermal;
+// Only set and read over the GATT server.
+#[cfg_attr(feature = "beacon_only", allow(dead_code))]
+mod wall_clock;
 
+use embassy_time::{Duration, with_timeout};
 use {defmt_rtt as _, panic_probe as _};
 
-use crate::ble::advertise::advertise_task;
+#[cfg(any(
+    all(feature = "ibeacon", feature = "eddystone"),
+    all(feature = "ibeacon", feature = "eddystone_url"),
+    all(feature = "eddystone", feature = "eddystone_url")
+))]
+compile_error!("the ibeacon, eddystone and eddystone_url features are mutually exclusive");
+
+#[cfg(not(feature = "beacon_only"))]
+use crate::ble::advertise::ProvisioningTimeout;
+#[cfg(feature = "eddystone")]
+use crate::ble::advertise::advertise_eddystone_uid;
+#[cfg(feature = "eddystone_url")]
+use crate::ble::advertise::advertise_eddystone_url;
+#[cfg(feature = "ibeacon")]
+use crate::ble::advertise::advertise_ibeacon;
+#[cfg(not(any(feature = "ibeacon", feature = "eddystone", feature = "eddystone_url")))]
+use crate::ble::advertise::{
+    AdvertisingChannels, AdvertisingConfig, AdvertisingDutyCycle, AdvertisingInterval,
+    advertise_task,
+};
+#[cfg(not(feature = "beacon_only"))]
+use crate::ble::advertise::{BeaconRotation, RotationEntry};
+#[cfg(any(feature = "ibeacon", feature = "eddystone"))]
+use crate::ble::beacon::BeaconIdentity;
 use crate::ble::ble_background_task;
-use crate::ble::gatt_server::GattServer;
-use crate::boards::Board;
+use crate::ble::device_name::DeviceName;
+#[cfg(not(feature = "beacon_only"))]
+use crate::ble::gatt_server::{AcceptedConnections, GattServer};
+use crate::ble::status::{self, MOVED_FLAG_DURATION, StatusFlag};
+use crate::boards::{BUTTON_EVENTS, Board, ButtonEvent};
 
-/// Device name advertised over BLE.
+/// Device name advertised over BLE until the user saves another.
 static ADV_NAME: &str = "Lookpoint Tracker";
 
+/// URL broadcast by Eddystone-URL builds, set at build time.
+#[cfg(feature = "eddystone_url")]
+static EDDYSTONE_URL: &str = env!("LOOKPOINT_EDDYSTONE_URL");
+
+/// Limits on how long the device remains discoverable.
+#[cfg(not(feature = "beacon_only"))]
+static ADVERTISING_CONFIG: AdvertisingConfig = AdvertisingConfig {
+    max_duration:         None,
+    max_connections:      None,
+    // A tracker nobody has connected to for an hour powers off, see
+    // `system_off_when_idle`. The button, or the reset button on boards
+    // without one, wakes it.
+    idle_timeout:         Some(Duration::from_secs(60 * 60)),
+    interval:             Some(AdvertisingInterval {
+        min: Duration::from_millis(500),
+        max: Duration::from_millis(1000),
+    }),
+    // A burst of fast advertising every few minutes gets the tracker found
+    // quickly, at a fraction of the average current of advertising fast.
+    duty_cycle:           Some(AdvertisingDutyCycle {
+        fast_window:   Duration::from_secs(30),
+        fast_interval: Duration::from_millis(100),
+        slow_window:   Duration::from_secs(5 * 60),
+        slow_interval: Some(Duration::from_millis(1000)),
+    }),
+    backoff:              None,
+    pause_when_full:      true,
+    provisioning_timeout: Some(ProvisioningTimeout {
+        window:           Duration::from_secs(30 * 60),
+        shelved_interval: Duration::from_millis(10_240),
+    }),
+    // Only the owners who bonded may connect, see `PAIRING_WINDOW`.
+    allow_list:           true,
+    // Trackers are often powered up together, spread their advertisements.
+    interval_jitter:      true,
+    long_range:           false,
+    // The BLE host pairs from the identity address and never distributes the
+    // IRK, so bonded peers could not follow a rotating address.
+    address_rotation:     None,
+    // Once a beacon identity is provisioned, it is broadcast a quarter of the
+    // time so positioning systems see the tracker too.
+    beacon_rotation:      Some(BeaconRotation {
+        schedule: &[
+            RotationEntry::Device,
+            RotationEntry::Device,
+            RotationEntry::Device,
+            RotationEntry::Beacon,
+        ],
+        cadence:  Duration::from_secs(5),
+    }),
+    channels:             AdvertisingChannels::ALL,
+};
+
+/// Advertising of a beacon only build. Nobody ever connects to it, so it
+/// broadcasts for as long as it is powered rather than timing out or being
+/// shelved, and has no bonded peers to filter on.
+#[cfg(all(
+    feature = "beacon_only",
+    not(any(feature = "ibeacon", feature = "eddystone", feature = "eddystone_url"))
+))]
+static ADVERTISING_CONFIG: AdvertisingConfig = AdvertisingConfig {
+    max_duration:         None,
+    max_connections:      None,
+    idle_timeout:         None,
+    interval:             Some(AdvertisingInterval {
+        min: Duration::from_millis(500),
+        max: Duration::from_millis(1000),
+    }),
+    duty_cycle:           Some(AdvertisingDutyCycle {
+        fast_window:   Duration::from_secs(30),
+        fast_interval: Duration::from_millis(100),
+        slow_window:   Duration::from_secs(5 * 60),
+        slow_interval: Some(Duration::from_millis(1000)),
+    }),
+    backoff:              None,
+    pause_when_full:      false,
+    provisioning_timeout: None,
+    allow_list:           false,
+    interval_jitter:      true,
+    long_range:           false,
+    address_rotation:     None,
+    beacon_rotation:      None,
+    channels:             AdvertisingChannels::ALL,
+};
+
+/// How long a short press of the button lets a new central connect and bond
+/// despite the allow list.
+const PAIRING_WINDOW: Duration = Duration::from_secs(2 * 60);
+
+/// How long after a long press a short press confirms the factory reset,
+/// while the indicator blinks.
+const FACTORY_RESET_CONFIRM_WINDOW: Duration = Duration::from_secs(10);
+
+/// Task acting on presses of the button: a short press wakes the device,
+/// restarting advertising at its fast interval and opening the pairing window.
+/// A long press followed by a short press within
+/// [`FACTORY_RESET_CONFIRM_WINDOW`] requests a factory reset, so a button
+/// held down by accident in a bag cannot erase the bonds.
+#[embassy_executor::task]
+async fn button_events_task() -> ! {
+    loop {
+        match BUTTON_EVENTS.wait().await {
+            ButtonEvent::ShortPress => {
+                defmt::info!("[main] button pressed, advertising");
+                ble::allow_list::open_pairing_window(PAIRING_WINDOW);
+                ble::advertise::start_advertising();
+                ble::advertise::RESET_ADVERTISING_BACKOFF.signal(());
+            }
+            ButtonEvent::LongPress => {
+                defmt::info!("[main] button held, press again to factory reset");
+                indicator::identify(FACTORY_RESET_CONFIRM_WINDOW);
+
+                let confirmation =
+                    with_timeout(FACTORY_RESET_CONFIRM_WINDOW, BUTTON_EVENTS.wait()).await;
+                if matches!(confirmation, Ok(ButtonEvent::ShortPress)) {
+                    system::request_factory_reset();
+                } else {
+                    defmt::info!("[main] factory reset not confirmed");
+                    indicator::identify(Duration::from_ticks(0));
+                }
+            }
+        }
+    }
+}
+
+/// Task acting on motion: the moved flag is broadcast, and advertising
+/// restarts at its fast interval so the disturbed tracker is found quickly.
+/// The flag is cleared once the tracker has been still for
+/// [`MOVED_FLAG_DURATION`].
+#[embassy_executor::task]
+async fn motion_events_task() -> ! {
+    // UNWRAP: Infallible. The first receiver taken.
+    let mut motion_detected = motion::MOTION_DETECTED.receiver().unwrap();
+
+    loop {
+        motion_detected.changed().await;
+        defmt::info!("[main] motion detected, advertising");
+        status::set_flag(StatusFlag::Moved, true);
+        ble::advertise::RESET_ADVERTISING_BACKOFF.signal(());
+
+        while with_timeout(MOVED_FLAG_DURATION, motion_detected.changed())
+            .await
+            .is_ok()
+        {}
+
+        defmt::info!("[main] still again, moved flag cleared");
+        status::set_flag(StatusFlag::Moved, false);
+    }
+}
+
+/// Enter System OFF once advertising stops while no central is connected, such
+/// as after the idle timeout of the [`ADVERTISING_CONFIG`]. A press of the
+/// button, or of the reset button, boots the device again.
+#[cfg(not(feature = "beacon_only"))]
+async fn system_off_when_idle(board: &Board) -> ! {
+    loop {
+        ble::advertise::ADVERTISING_STOPPED.wait().await;
+
+        if ble::connections::active_connections() == 0 {
+            board.enter_system_off().await;
+        }
+    }
+}
+
 #[embassy_executor::main]
 async fn main(task_spawner: embassy_executor::Spawner) {
-    let board = Board::init(&task_spawner);
+    // Flash is memory mapped, so the settings load before the board, which
+    // needs the saved device name and counts the boot.
+    settings::load();
+
+    // Declared before the board so it outlives the BLE stack borrowing it.
+    let device_name = DeviceName::load(ADV_NAME);
+
+    let board = Board::init(&task_spawner, &device_name);
+    task_spawner.must_spawn(system::factory_reset_task());
+    task_spawner.must_spawn(system::boot_count_task());
+    #[cfg(feature = "dfu")]
+    task_spawner.must_spawn(system::bootloader_task());
+    task_spawner.must_spawn(button_events_task());
+    task_spawner.must_spawn(motion_events_task());
+    capabilities::init(board.capabilities());
+
+    let device_config = config::load();
+    defmt::info!("[main] beacon identity: {}", device_config.beacon);
+    sensors::set_enabled_mask(device_config.enabled_sensors);
 
     let mut host = board.get_ble_host();
 
-    let gatt_server = match GattServer::start(ADV_NAME) {
-        Ok(gatt_server) => gatt_server,
-        Err(error) => defmt::panic!("[gatt] failed to start the GATT server: {}", error),
-    };
-
-    // Main loop
-    embassy_futures::join::join(
-        ble_background_task(&mut host.runner),
-        advertise_task(ADV_NAME, &mut host.peripheral, &gatt_server),
-    )
-    .await;
+    #[cfg(not(feature = "beacon_only"))]
+    {
+        // Previously paired peers reconnect without pairing again.
+        ble::bonds::load(board.ble_stack());
+
+        let gatt_server = match GattServer::start(&device_name) {
+            Ok(gatt_server) => gatt_server,
+            Err(error) => defmt::panic!("[gatt] failed to start the GATT server: {}", error),
+        };
+
+        // Connections are served alongside advertising, so another client can
+        // connect while one is being served.
+        let accepted_connections = AcceptedConnections::new();
+
+        // Main loop
+        embassy_futures::join::join4(
+            ble_background_task(&mut host.runner),
+            advertise_task(
+                &device_name,
+                board.ble_stack(),
+                &mut host.peripheral,
+                &gatt_server,
+                &accepted_connections,
+                &ADVERTISING_CONFIG,
+            ),
+            gatt_server.serve_connections(board.ble_stack(), &accepted_connections),
+            system_off_when_idle(&board),
+        )
+        .await;
+    }
+
+    // Beacon only builds never serve connections and have no GATT server.
+    #[cfg(all(
+        feature = "beacon_only",
+        not(any(feature = "ibeacon", feature = "eddystone", feature = "eddystone_url"))
+    ))]
+    {
+        embassy_futures::join::join(
+            ble_background_task(&mut host.runner),
+            advertise_task(
+                &device_name,
+                board.ble_stack(),
+                &mut host.peripheral,
+                &ADVERTISING_CONFIG,
+            ),
+        )
+        .await;
+    }
+
+    #[cfg(feature = "ibeacon")]
+    {
+        let BeaconIdentity::IBeacon(identity) = device_config.beacon else {
+            defmt::panic!("[main] the ibeacon feature requires a provisioned iBeacon identity");
+        };
+
+        let beacon = embassy_futures::select::select(
+            ble_background_task(&mut host.runner),
+            advertise_ibeacon(
+                &mut host.peripheral,
+                identity.uuid,
+                identity.major,
+                identity.minor,
+                identity.measured_power,
+            ),
+        );
+
+        if let embassy_futures::select::Either::Second(Err(error)) = beacon.await {
+            defmt::error!("[main] failed to broadcast the iBeacon: {}", error);
+            indicator::report_error();
+        }
+    }
+
+    #[cfg(feature = "eddystone")]
+    {
+        let BeaconIdentity::EddystoneUid(identity) = device_config.beacon else {
+            defmt::panic!("[main] the eddystone feature requires a provisioned Eddystone identity");
+        };
+
+        let beacon = embassy_futures::select::select(
+            ble_background_task(&mut host.runner),
+            advertise_eddystone_uid(&mut host.peripheral, &identity),
+        );
+
+        if let embassy_futures::select::Either::Second(Err(error)) = beacon.await {
+            defmt::error!("[main] failed to broadcast the Eddystone beacon: {}", error);
+            indicator::report_error();
+        }
+    }
+
+    #[cfg(feature = "eddystone_url")]
+    {
+        let beacon = embassy_futures::select::select(
+            ble_background_task(&mut host.runner),
+            advertise_eddystone_url(&mut host.peripheral, EDDYSTONE_URL),
+        );
+
+        if let embassy_futures::select::Either::Second(Err(error)) = beacon.await {
+            defmt::error!(
+                "[main] failed to broadcast the Eddystone-URL beacon: {}",
+                error
+            );
+            indicator::report_error();
+        }
+    }
 }
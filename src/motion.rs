@@ -0,0 +1,59 @@
+// SPDX-FileCopyrightText: 2025 Derek Sauer
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Board independent motion state, fed by the board's motion detection.
+
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::watch::Watch;
+use embassy_time::Instant;
+
+use crate::ble::MAX_CONNECTIONS;
+
+/// Value of [`LAST_MOTION`] before any motion has been detected.
+const NO_MOTION: u32 = u32::MAX;
+
+/// Uptime, in seconds, at which motion was last detected.
+static LAST_MOTION: AtomicU32 = AtomicU32::new(NO_MOTION);
+
+/// Tasks waiting for motion: the one acting on it, and one notifying the
+/// client of each connection.
+const MOTION_RECEIVERS: usize = 1 + MAX_CONNECTIONS;
+
+/// Updated with the uptime of each detected motion.
+pub static MOTION_DETECTED: Watch<CriticalSectionRawMutex, Instant, MOTION_RECEIVERS> =
+    Watch::new();
+
+/// Record that motion was just detected.
+pub fn record_motion() {
+    // Uptime in seconds only reaches `NO_MOTION` after 136 years.
+    let now = Instant::now().as_secs() as u32;
+    LAST_MOTION.store(now, Ordering::Relaxed);
+    MOTION_DETECTED
+        .sender()
+        .send(Instant::from_secs(u64::from(now)));
+}
+
+/// Returns the uptime at which motion was last detected, to the second, or
+/// `None` if no motion has been detected since boot.
+#[cfg_attr(feature = "beacon_only", allow(dead_code))]
+pub fn last_motion() -> Option<Instant> {
+    match LAST_MOTION.load(Ordering::Relaxed) {
+        NO_MOTION => None,
+        last_motion => Some(Instant::from_secs(u64::from(last_motion))),
+    }
+}
+
+/// Returns the number of seconds the device has been stationary, or `None` if
+/// no motion has been detected since boot.
+pub fn seconds_since_motion() -> Option<u32> {
+    match LAST_MOTION.load(Ordering::Relaxed) {
+        NO_MOTION => None,
+        last_motion => {
+            let now = Instant::now().as_secs() as u32;
+            Some(now.saturating_sub(last_motion))
+        }
+    }
+}
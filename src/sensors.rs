@@ -0,0 +1,153 @@
+// SPDX-FileCopyrightText: 2025 Derek Sauer
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! The device's optional sensors: their drivers' common interface and runtime
+//! enabling.
+//!
+//! Each sensor's sampling task waits with [`wait_enabled`] before sampling,
+//! trading features for battery life. A disabled sensor's characteristic
+//! remains in the attribute table but its value stops changing.
+//!
+//! Sensor drivers report failures as a [`SensorError`] rather than panicking.
+//! [`run_sampling`] logs and skips failed samples so a flaky sensor cannot
+//! take down the firmware.
+
+use core::sync::atomic::{AtomicU8, Ordering};
+
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::watch::Watch;
+use embassy_time::{Duration, Ticker};
+pub use lookpoint_logic::config::ALL_SENSORS;
+use lookpoint_logic::config::SENSOR_COUNT;
+
+/// Bits of the enabled sensor mask.
+///
+/// Bit assignments are part of the control point and configuration formats and
+/// must remain stable.
+#[derive(Clone, Copy, defmt::Format)]
+#[repr(u8)]
+pub enum Sensor {
+    /// Inertial measurement unit, feeding motion detection.
+    Imu         = 1 << 0,
+
+    /// Ambient temperature.
+    Temperature = 1 << 1,
+
+    /// Relative humidity.
+    Humidity    = 1 << 2,
+
+    /// Barometric pressure.
+    Pressure    = 1 << 3,
+
+    /// Ambient light.
+    Light       = 1 << 4,
+}
+
+/// Errors of the sensor drivers.
+#[derive(Clone, Copy, Debug, defmt::Format)]
+pub enum SensorError {
+    /// A transaction on the sensor's bus failed.
+    BusError,
+
+    /// The sensor did not answer or identified as another part.
+    NotPresent,
+
+    /// The sensor's factory calibration could not be read or is implausible.
+    CalibrationInvalid,
+
+    /// The sensor returned a reading outside of its measurement range.
+    OutOfRange,
+}
+
+/// Common interface of the sensor drivers.
+#[allow(async_fn_in_trait)]
+pub trait SensorDriver {
+    /// A single reading of the sensor.
+    type Reading;
+
+    /// Check the sensor is present and configure it for sampling.
+    async fn init(&mut self) -> Result<(), SensorError>;
+
+    /// Take a reading of the sensor.
+    async fn read(&mut self) -> Result<Self::Reading, SensorError>;
+}
+
+/// Sample `driver` every `interval` while `sensor` is enabled, handing each
+/// reading to `on_reading`.
+///
+/// The driver is initialized before its first reading and again after an
+/// initialization failure. Failures are logged and the sample skipped.
+pub async fn run_sampling<D: SensorDriver>(
+    sensor: Sensor,
+    driver: &mut D,
+    interval: Duration,
+    mut on_reading: impl FnMut(D::Reading),
+) -> ! {
+    let mut initialized = false;
+    let mut ticker = Ticker::every(interval);
+
+    loop {
+        wait_enabled(sensor).await;
+        ticker.next().await;
+
+        if !initialized {
+            if let Err(error) = driver.init().await {
+                defmt::warn!("[sensors] failed to initialize the {}: {}", sensor, error);
+                continue;
+            }
+            initialized = true;
+        }
+
+        match driver.read().await {
+            Ok(reading) => on_reading(reading),
+            Err(error) => defmt::warn!("[sensors] failed to read the {}: {}", sensor, error),
+        }
+    }
+}
+
+/// Mask of the currently enabled sensors.
+static ENABLED_SENSORS: AtomicU8 = AtomicU8::new(ALL_SENSORS);
+
+/// Updated with the new mask whenever sensors are enabled or disabled.
+static SENSORS_CHANGED: Watch<CriticalSectionRawMutex, u8, SENSOR_COUNT> = Watch::new();
+
+/// Returns the mask of the currently enabled sensors.
+pub fn enabled_mask() -> u8 {
+    ENABLED_SENSORS.load(Ordering::Relaxed)
+}
+
+/// Returns `true` if `sensor` is enabled.
+pub fn is_enabled(sensor: Sensor) -> bool {
+    enabled_mask() & sensor as u8 != 0
+}
+
+/// Enable the sensors set in `mask` and disable the others. Bits not assigned
+/// to a sensor are ignored.
+pub fn set_enabled_mask(mask: u8) {
+    let mask = mask & ALL_SENSORS;
+    ENABLED_SENSORS.store(mask, Ordering::Relaxed);
+    SENSORS_CHANGED.sender().send(mask);
+}
+
+/// Wait until `sensor` is enabled. Returns immediately if it already is.
+pub async fn wait_enabled(sensor: Sensor) {
+    // UNWRAP: Infallible. Each sensor's sampling task holds at most one
+    // receiver at a time.
+    let mut receiver = SENSORS_CHANGED.receiver().unwrap();
+
+    while !is_enabled(sensor) {
+        receiver.changed().await;
+    }
+}
+
+/// Wait until `sensor` is disabled. Returns immediately if it already is.
+pub async fn wait_disabled(sensor: Sensor) {
+    // UNWRAP: Infallible. Each sensor's sampling task holds at most one
+    // receiver at a time.
+    let mut receiver = SENSORS_CHANGED.receiver().unwrap();
+
+    while is_enabled(sensor) {
+        receiver.changed().await;
+    }
+}
@@ -0,0 +1,120 @@
+// SPDX-FileCopyrightText: 2025 Derek Sauer
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Typed settings persisted to flash, such as the device name.
+//!
+//! Each setting is stored under a [`Key`] naming its type, so [`get`] and
+//! [`set`] can only read and write it as that type. Every setting shares one
+//! record, loaded into RAM once at startup with [`load`]. Changes made with
+//! [`set`] are visible immediately and written back to flash as a deferred
+//! write, scheduled through the MPSL so it does not clash with the radio.
+//!
+//! A brownout can cut a write short and leave a torn record. Records are
+//! therefore written alternately to two pages, each numbered and framed by
+//! its length and a CRC, and [`load`] takes the newest intact one: a torn
+//! write reverts to the record written before it. The framing lives in
+//! [`lookpoint_logic::settings`], where it is tested on the host.
+
+use core::cell::RefCell;
+
+use embassy_sync::blocking_mutex::Mutex;
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use lookpoint_logic::settings::{self as framing, RECORD_SLOTS, Record, Settings};
+pub use lookpoint_logic::settings::{Key, MAX_VALUE_LENGTH, Value};
+
+use crate::storage::{self, MAX_RECORD_LENGTH, StorageError, WritePriority};
+
+/// Storage pages the settings records are written to in turn, the record
+/// numbered `n` to page `SETTINGS_PAGES[n % 2]`.
+const SETTINGS_PAGES: [u32; RECORD_SLOTS] = [3, 4];
+
+/// Device name chosen by the user.
+pub const DEVICE_NAME: Key<heapless::String<MAX_VALUE_LENGTH>> = Key::new(1);
+
+/// Number of times the device booted.
+pub const BOOT_COUNT: Key<u32> = Key::new(4);
+
+/// Offset added to the die temperature, in hundredths of a degree Celsius.
+pub const TEMPERATURE_OFFSET: Key<i16> = Key::new(5);
+
+/// Temperature above which an alert is raised, in hundredths of a degree
+/// Celsius.
+pub const TEMPERATURE_HIGH_ALERT: Key<i16> = Key::new(6);
+
+/// Temperature below which an alert is raised, in hundredths of a degree
+/// Celsius.
+pub const TEMPERATURE_LOW_ALERT: Key<i16> = Key::new(7);
+
+/// Settings, loaded from flash and changed since.
+static SETTINGS: Mutex<CriticalSectionRawMutex, RefCell<Settings>> =
+    Mutex::new(RefCell::new(Settings::new()));
+
+/// Read the settings record stored in `page`. Erased flash is returned should
+/// the read fail, as if no record was stored.
+fn read_record(page: u32) -> [u8; MAX_RECORD_LENGTH] {
+    let mut bytes = [0; MAX_RECORD_LENGTH];
+    if let Err(error) = storage::read(page, &mut bytes) {
+        defmt::error!("[settings] failed to read page {}: {}", page, error);
+        return [0xff; MAX_RECORD_LENGTH];
+    }
+
+    if framing::is_torn(&bytes) {
+        defmt::warn!(
+            "[settings] discarded a torn or corrupt record in page {}",
+            page
+        );
+    }
+
+    bytes
+}
+
+/// Load the newest intact settings record from flash. Every setting is unset
+/// if none were stored.
+pub fn load() {
+    let settings = Settings::from_records(&SETTINGS_PAGES.map(read_record));
+    defmt::info!(
+        "[settings] {} bytes of settings loaded from record {}",
+        settings.len(),
+        settings.sequence()
+    );
+
+    SETTINGS.lock(|stored| *stored.borrow_mut() = settings);
+}
+
+/// Returns the value of the setting `key`, or `None` if it is unset or its
+/// stored value is invalid.
+///
+/// Settings are held in RAM, so reading one does not wait on flash.
+pub fn get<T: Value>(key: Key<T>) -> Option<T> {
+    SETTINGS.lock(|settings| settings.borrow().get(key))
+}
+
+/// Set the setting `key` to `value`, returning the settings' record to
+/// persist and the page to write it to.
+fn update<T: Value>(key: Key<T>, value: &T) -> Result<(u32, Record), StorageError> {
+    let (slot, record) = SETTINGS
+        .lock(|settings| settings.borrow_mut().set(key, value))
+        .map_err(|_| StorageError::RecordTooLong)?;
+
+    Ok((SETTINGS_PAGES[slot], record))
+}
+
+/// Set the setting `key` to `value` and persist the settings. Waits if the
+/// storage write queue is full.
+///
+/// Fails with [`StorageError::RecordTooLong`], leaving the settings unchanged,
+/// if they no longer fit in a storage page.
+pub async fn set<T: Value>(key: Key<T>, value: &T) -> Result<(), StorageError> {
+    let (page, record) = update(key, value)?;
+    storage::write(page, &record, WritePriority::Deferred).await
+}
+
+/// Set the setting `key` to `value` and persist the settings without waiting,
+/// for callers that cannot await. Fails like [`set`], or with
+/// [`StorageError::QueueFull`] if the storage write queue is full, in which
+/// case the new value is only kept until reset.
+pub fn try_set<T: Value>(key: Key<T>, value: &T) -> Result<(), StorageError> {
+    let (page, record) = update(key, value)?;
+    storage::try_write(page, &record, WritePriority::Deferred)
+}
@@ -0,0 +1,273 @@
+// SPDX-FileCopyrightText: 2025 Derek Sauer
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Persistent storage in the nRF52840's internal flash.
+//!
+//! The last [`STORAGE_PAGES`] pages of flash are reserved for storage and
+//! excluded from the firmware image in `memory.x`.
+//!
+//! Erasing and writing flash through the MPSL competes with the radio for
+//! timeslots, and doing so mid-connection can hurt connection quality. Writes
+//! are therefore queued with a [`WritePriority`] and performed by a background
+//! writer:
+//!
+//! - [`WritePriority::Urgent`] writes, such as a factory reset, are performed
+//!   immediately.
+//! - [`WritePriority::Deferred`] writes, such as settings, wait until no
+//!   connection is active or connections have been quiet for [`QUIET_PERIOD`].
+//!   A deferred write to a page replaces any write to the same page still
+//!   waiting.
+//!
+//! Before a reset, [`flush`] waits for every queued and deferred write to be
+//! performed, regardless of connections.
+//!
+//! Reads do not need to be scheduled: flash is memory mapped on the nRF52.
+
+use embassy_futures::select::{Either3, select3};
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::channel::Channel;
+use embassy_sync::signal::Signal;
+use embassy_time::{Duration, Timer, with_timeout};
+use embedded_storage_async::nor_flash::NorFlash;
+pub use lookpoint_logic::storage::{MAX_RECORD_LENGTH, crc32};
+
+use crate::ble::connections;
+use crate::indicator;
+
+/// Size of a flash page, the smallest erasable unit.
+pub const PAGE_SIZE: u32 = 4096;
+
+/// Number of flash pages reserved for storage.
+pub const STORAGE_PAGES: u32 = 8;
+
+/// Address of the first page reserved for storage, at the end of the 1 MB
+/// flash.
+pub const STORAGE_START: u32 = 0x0010_0000 - STORAGE_PAGES * PAGE_SIZE;
+
+/// Writes are rounded up to a multiple of the flash's 4 byte word.
+const WORD_SIZE: usize = 4;
+
+/// Deferred writes proceed once connections have been idle this long.
+pub const QUIET_PERIOD: Duration = Duration::from_secs(2);
+
+/// How often the writer checks whether deferred writes may proceed.
+const QUIET_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Number of write requests that can wait to be picked up by the writer.
+const QUEUE_DEPTH: usize = 4;
+
+/// Number of deferred writes the writer can hold while waiting for quiet.
+const MAX_DEFERRED_WRITES: usize = 4;
+
+/// Whether a write must be performed right away or may wait for connections to
+/// be quiet.
+#[derive(Clone, Copy, PartialEq, defmt::Format)]
+pub enum WritePriority {
+    /// Performed immediately, even during a connection.
+    Urgent,
+
+    /// Performed once no connection is active or connections are quiet.
+    Deferred,
+}
+
+/// Errors queueing a write.
+#[derive(Clone, Copy, defmt::Format)]
+pub enum StorageError {
+    /// The page is outside of the flash reserved for storage.
+    InvalidPage,
+
+    /// The record is longer than [`MAX_RECORD_LENGTH`].
+    RecordTooLong,
+
+    /// The write queue is full.
+    QueueFull,
+
+    /// The writer did not finish flushing the queued writes in time.
+    FlushTimeout,
+}
+
+/// A request to replace the contents of a page with a record.
+struct WriteRequest {
+    page:     u32,
+    record:   heapless::Vec<u8, MAX_RECORD_LENGTH>,
+    priority: WritePriority,
+}
+
+/// Write requests waiting for the writer.
+static WRITE_QUEUE: Channel<CriticalSectionRawMutex, WriteRequest, QUEUE_DEPTH> = Channel::new();
+
+/// Signaled to ask the writer to perform every queued and deferred write now.
+static FLUSH_REQUESTED: Signal<CriticalSectionRawMutex, ()> = Signal::new();
+
+/// Signaled by the writer once a requested flush completed.
+static FLUSHED: Signal<CriticalSectionRawMutex, ()> = Signal::new();
+
+/// Returns the address of the storage page `page`.
+pub fn page_address(page: u32) -> u32 {
+    STORAGE_START + page * PAGE_SIZE
+}
+
+/// Read `buffer.len()` bytes from the start of storage page `page`.
+pub fn read(page: u32, buffer: &mut [u8]) -> Result<(), StorageError> {
+    if page >= STORAGE_PAGES || buffer.len() > PAGE_SIZE as usize {
+        return Err(StorageError::InvalidPage);
+    }
+
+    // SAFETY: Flash is memory mapped and the range lies within the pages
+    // reserved for storage, which are not part of the firmware image.
+    unsafe {
+        core::ptr::copy_nonoverlapping(
+            page_address(page) as *const u8,
+            buffer.as_mut_ptr(),
+            buffer.len(),
+        );
+    }
+
+    Ok(())
+}
+
+/// Queue a request to erase storage page `page` and write `record` at its
+/// start. Waits if the queue is full.
+pub async fn write(page: u32, record: &[u8], priority: WritePriority) -> Result<(), StorageError> {
+    if page >= STORAGE_PAGES {
+        return Err(StorageError::InvalidPage);
+    }
+
+    let record = heapless::Vec::from_slice(record).map_err(|_| StorageError::RecordTooLong)?;
+
+    WRITE_QUEUE
+        .send(WriteRequest {
+            page,
+            record,
+            priority,
+        })
+        .await;
+
+    Ok(())
+}
+
+/// Queue a request to erase storage page `page` and write `record` at its
+/// start without waiting, for callers that cannot await.
+pub fn try_write(page: u32, record: &[u8], priority: WritePriority) -> Result<(), StorageError> {
+    if page >= STORAGE_PAGES {
+        return Err(StorageError::InvalidPage);
+    }
+
+    let record = heapless::Vec::from_slice(record).map_err(|_| StorageError::RecordTooLong)?;
+
+    WRITE_QUEUE
+        .try_send(WriteRequest {
+            page,
+            record,
+            priority,
+        })
+        .map_err(|_| StorageError::QueueFull)
+}
+
+/// Wait until every write queued so far, deferred or not, has been performed.
+///
+/// Gives up with [`StorageError::FlushTimeout`] after `timeout` so a stuck
+/// writer cannot block a reset forever.
+pub async fn flush(timeout: Duration) -> Result<(), StorageError> {
+    FLUSHED.reset();
+    FLUSH_REQUESTED.signal(());
+
+    with_timeout(timeout, FLUSHED.wait())
+        .await
+        .map_err(|_| StorageError::FlushTimeout)
+}
+
+/// Erase the page of `request` and write its record.
+async fn perform<F: NorFlash>(flash: &mut F, request: &WriteRequest) {
+    let address = page_address(request.page);
+
+    if let Err(error) = flash.erase(address, address + PAGE_SIZE).await {
+        defmt::error!(
+            "[storage] failed to erase page {}: {}",
+            request.page,
+            defmt::Debug2Format(&error)
+        );
+        indicator::report_error();
+        return;
+    }
+
+    // Pad the record to a whole number of words with erased bytes.
+    let mut padded = [0xff; MAX_RECORD_LENGTH];
+    padded[..request.record.len()].copy_from_slice(&request.record);
+    let length = request.record.len().next_multiple_of(WORD_SIZE);
+
+    match flash.write(address, &padded[..length]).await {
+        Ok(()) => defmt::debug!("[storage] wrote {} bytes to page {}", length, request.page),
+        Err(error) => {
+            defmt::error!(
+                "[storage] failed to write page {}: {}",
+                request.page,
+                defmt::Debug2Format(&error)
+            );
+            indicator::report_error();
+        }
+    }
+}
+
+/// Run the background flash writer forever.
+pub async fn run_writer<F: NorFlash>(mut flash: F) -> ! {
+    let mut deferred: heapless::Vec<WriteRequest, MAX_DEFERRED_WRITES> = heapless::Vec::new();
+
+    loop {
+        // Only check for quiet connections while writes are deferred.
+        let has_deferred = !deferred.is_empty();
+        let quiet_poll = async {
+            if !has_deferred {
+                core::future::pending::<()>().await;
+            }
+            Timer::after(QUIET_POLL_INTERVAL).await;
+        };
+
+        let request = match select3(WRITE_QUEUE.receive(), FLUSH_REQUESTED.wait(), quiet_poll).await
+        {
+            Either3::First(request) => request,
+            Either3::Second(()) => {
+                // Deferred writes are older than those still queued.
+                for request in deferred.iter() {
+                    perform(&mut flash, request).await;
+                }
+                deferred.clear();
+
+                while let Ok(request) = WRITE_QUEUE.try_receive() {
+                    perform(&mut flash, &request).await;
+                }
+
+                defmt::debug!("[storage] flushed queued writes");
+                FLUSHED.signal(());
+                continue;
+            }
+            Either3::Third(()) => {
+                if connections::is_quiet(QUIET_PERIOD) {
+                    for request in deferred.iter() {
+                        perform(&mut flash, request).await;
+                    }
+                    deferred.clear();
+                }
+                continue;
+            }
+        };
+
+        match request.priority {
+            WritePriority::Urgent => {
+                // The waiting write to the same page would undo this one.
+                deferred.retain(|waiting| waiting.page != request.page);
+                perform(&mut flash, &request).await;
+            }
+            WritePriority::Deferred => {
+                // A newer write to the same page supersedes the waiting one.
+                deferred.retain(|waiting| waiting.page != request.page);
+
+                if let Err(request) = deferred.push(request) {
+                    defmt::warn!("[storage] too many deferred writes, writing page now");
+                    perform(&mut flash, &request).await;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,132 @@
+// SPDX-FileCopyrightText: 2025 Derek Sauer
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Orderly shutdown of the device.
+
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::signal::Signal;
+use embassy_time::{Duration, Timer};
+
+use crate::settings;
+use crate::storage::{self, WritePriority};
+
+/// How long queued flash writes may take to complete before the device resets
+/// regardless.
+const FLUSH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Uptime after which this boot is counted in the settings. Shorter than a
+/// typical session, longer than a crash or brownout loop takes to reset.
+const BOOT_COUNT_UPTIME: Duration = Duration::from_secs(60);
+
+/// Number of boots so far, this one included, set by [`count_boot`].
+static BOOT_COUNT: AtomicU32 = AtomicU32::new(0);
+
+/// How long a factory reset, or a reset into the bootloader, waits before
+/// acting, letting the response to the request reach the client.
+const REQUEST_DELAY: Duration = Duration::from_millis(500);
+
+/// Signaled to request a factory reset, performed by [`factory_reset_task`].
+static FACTORY_RESET_REQUESTED: Signal<CriticalSectionRawMutex, ()> = Signal::new();
+
+/// Signaled to request a reset into the bootloader, performed by
+/// [`bootloader_task`].
+#[cfg(feature = "dfu")]
+static BOOTLOADER_REQUESTED: Signal<CriticalSectionRawMutex, ()> = Signal::new();
+
+/// Count this boot, returning the number of boots so far. A count climbing
+/// faster than expected points at brownouts or crashes.
+///
+/// The count is persisted by [`boot_count_task`] once the device has been up
+/// for [`BOOT_COUNT_UPTIME`].
+pub fn count_boot() -> u32 {
+    let boot_count = settings::get(settings::BOOT_COUNT)
+        .unwrap_or(0)
+        .wrapping_add(1);
+
+    BOOT_COUNT.store(boot_count, Ordering::Relaxed);
+    boot_count
+}
+
+/// Task persisting the count of [`count_boot`] once the device has been up for
+/// [`BOOT_COUNT_UPTIME`].
+///
+/// Each count rewrites the settings page, whose flash is rated for 10,000
+/// erase cycles. Counting every boot right away would let a crash or brownout
+/// loop wear it out within hours. Boots ending sooner, or whose write the
+/// power failure comparator refuses, are not counted. Their reset reason is
+/// still logged at boot.
+#[embassy_executor::task]
+pub async fn boot_count_task() {
+    Timer::after(BOOT_COUNT_UPTIME).await;
+
+    let boot_count = BOOT_COUNT.load(Ordering::Relaxed);
+    if let Err(error) = settings::set(settings::BOOT_COUNT, &boot_count).await {
+        defmt::error!("[system] failed to persist the boot count: {}", error);
+    }
+}
+
+/// Reset the device once queued flash writes have completed, for example
+/// after a factory reset or to hand off to the bootloader.
+pub async fn reset() -> ! {
+    flush_storage().await;
+
+    defmt::info!("[system] resetting");
+    cortex_m::peripheral::SCB::sys_reset()
+}
+
+/// Wait for queued flash writes to complete before the device resets or
+/// powers down, giving up after [`FLUSH_TIMEOUT`].
+pub async fn flush_storage() {
+    defmt::info!("[system] flushing queued flash writes");
+
+    if let Err(error) = storage::flush(FLUSH_TIMEOUT).await {
+        defmt::error!("[system] flash writes did not complete: {}", error);
+    }
+}
+
+/// Request a factory reset: erase every storage page, returning the
+/// configuration, settings, allow list, and bonds to their defaults, then
+/// reset the device.
+pub fn request_factory_reset() {
+    defmt::warn!("[system] factory reset requested");
+    FACTORY_RESET_REQUESTED.signal(());
+}
+
+/// Task performing a factory reset once requested.
+#[embassy_executor::task]
+pub async fn factory_reset_task() -> ! {
+    FACTORY_RESET_REQUESTED.wait().await;
+    Timer::after(REQUEST_DELAY).await;
+
+    // An empty record leaves the page erased. Erasing goes through the
+    // storage writer, scheduled with the radio by the MPSL.
+    for page in 0..storage::STORAGE_PAGES {
+        if let Err(error) = storage::write(page, &[], WritePriority::Urgent).await {
+            defmt::error!("[system] failed to erase storage page {}: {}", page, error);
+        }
+    }
+
+    defmt::warn!("[system] factory reset, storage erased");
+    reset().await
+}
+
+/// Request a reset into the bootloader's DFU mode, for a firmware update.
+#[cfg(feature = "dfu")]
+pub fn request_bootloader() {
+    defmt::warn!("[system] reset into the bootloader requested");
+    BOOTLOADER_REQUESTED.signal(());
+}
+
+/// Task resetting into the bootloader once requested.
+#[cfg(feature = "dfu")]
+#[embassy_executor::task]
+pub async fn bootloader_task() -> ! {
+    BOOTLOADER_REQUESTED.wait().await;
+    Timer::after(REQUEST_DELAY).await;
+
+    crate::boards::request_bootloader();
+    reset().await
+}
@@ -0,0 +1,285 @@
+// SPDX-FileCopyrightText: 2025 Derek Sauer
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Thermal management.
+//!
+//! Transmitting heats the chip. When the die temperature exceeds
+//! [`THROTTLE_ABOVE`], the device enters a throttled state in which it
+//! advertises less often and at reduced transmit power so it can cool down.
+//! Normal operation resumes once the temperature drops below
+//! [`RESTORE_BELOW`].
+//!
+//! Single die temperature readings are noisy. The reported temperature is the
+//! average of the last [`AVERAGED_SAMPLES`] readings, taken every
+//! [`CHECK_INTERVAL`].
+//!
+//! The die runs a few degrees warmer than its surroundings. Reported
+//! temperatures are corrected by a calibration offset saved in the settings,
+//! zero until set, which can be characterized by comparing the raw reading
+//! with a reference thermometer. Throttling uses the raw reading, as the
+//! chip's ratings apply to the die.
+//!
+//! High and low alert thresholds, saved in the settings, raise a
+//! [`TemperatureAlert`] when the reported temperature crosses them. The alert
+//! is flagged in the advertisement and subscribed clients are notified of the
+//! temperature. It clears once the temperature is back within the thresholds
+//! by [`ALERT_HYSTERESIS`].
+
+use core::cell::RefCell;
+use core::sync::atomic::{AtomicBool, AtomicI32, AtomicU8, Ordering};
+
+use embassy_sync::blocking_mutex::Mutex;
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::signal::Signal;
+use embassy_time::Duration;
+
+use crate::ble::status::{self, StatusFlag};
+use crate::settings;
+use crate::storage::StorageError;
+
+/// Die temperature, in hundredths of a degree Celsius, above which the device
+/// is throttled. The nRF52840 is rated for operation up to 85 °C.
+pub const THROTTLE_ABOVE: i32 = 70_00;
+
+/// Die temperature, in hundredths of a degree Celsius, below which a throttled
+/// device resumes normal operation. The gap with [`THROTTLE_ABOVE`] prevents a
+/// temperature hovering around the threshold from flapping the state.
+pub const RESTORE_BELOW: i32 = 60_00;
+
+/// How often the die temperature is checked.
+///
+/// Each reading is a single conversion of the TEMP peripheral, about 36 µs,
+/// which the MPSL schedules between radio events. Reading every 10 s leaves
+/// the radio unaffected.
+pub const CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Number of die temperature readings averaged into the reported temperature,
+/// covering the last minute.
+pub const AVERAGED_SAMPLES: usize = 6;
+
+/// How far, in hundredths of a degree Celsius, the temperature must return
+/// within an alert threshold to clear the alert, so a temperature hovering
+/// around the threshold does not raise it over and over.
+pub const ALERT_HYSTERESIS: i32 = 1_00;
+
+/// High alert threshold disabling the high alert.
+pub const HIGH_ALERT_DISABLED: i16 = i16::MAX;
+
+/// Low alert threshold disabling the low alert.
+pub const LOW_ALERT_DISABLED: i16 = i16::MIN;
+
+/// Marks the die temperature as not measured yet.
+const TEMPERATURE_UNKNOWN: i32 = i32::MIN;
+
+/// Latest die temperature reading, in hundredths of a degree Celsius.
+static RAW_TEMPERATURE: AtomicI32 = AtomicI32::new(TEMPERATURE_UNKNOWN);
+
+/// Average of the latest die temperature readings, in hundredths of a degree
+/// Celsius.
+static SMOOTHED_TEMPERATURE: AtomicI32 = AtomicI32::new(TEMPERATURE_UNKNOWN);
+
+/// The latest die temperature readings, in hundredths of a degree Celsius.
+static SAMPLES: Mutex<CriticalSectionRawMutex, RefCell<Samples>> =
+    Mutex::new(RefCell::new(Samples::new()));
+
+/// Ring buffer of the latest [`AVERAGED_SAMPLES`] die temperature readings.
+struct Samples {
+    readings: [i32; AVERAGED_SAMPLES],
+    count:    usize,
+    next:     usize,
+}
+
+impl Samples {
+    const fn new() -> Self {
+        Self {
+            readings: [0; AVERAGED_SAMPLES],
+            count:    0,
+            next:     0,
+        }
+    }
+
+    /// Add a reading, replacing the oldest once full, and return the average
+    /// of the readings held.
+    fn push(&mut self, reading: i32) -> i32 {
+        self.readings[self.next] = reading;
+        self.next = (self.next + 1) % AVERAGED_SAMPLES;
+        self.count = (self.count + 1).min(AVERAGED_SAMPLES);
+
+        let sum: i32 = self.readings[..self.count].iter().sum();
+        sum / self.count as i32
+    }
+}
+
+/// Current [`TemperatureAlert`].
+static ALERT: AtomicU8 = AtomicU8::new(TemperatureAlert::None as u8);
+
+/// Whether the temperature is beyond one of the alert thresholds.
+#[derive(Clone, Copy, PartialEq, defmt::Format)]
+#[repr(u8)]
+pub enum TemperatureAlert {
+    /// The temperature is within the thresholds.
+    None,
+
+    /// The temperature rose above the high threshold.
+    High,
+
+    /// The temperature fell below the low threshold.
+    Low,
+}
+
+/// Whether the device is currently throttled.
+static THROTTLED: AtomicBool = AtomicBool::new(false);
+
+/// Signaled with the new state whenever the device enters or leaves the
+/// throttled state.
+pub static THROTTLE_CHANGED: Signal<CriticalSectionRawMutex, bool> = Signal::new();
+
+/// Returns `true` if the device is currently throttled.
+pub fn is_throttled() -> bool {
+    THROTTLED.load(Ordering::Relaxed)
+}
+
+/// Returns the latest die temperature reading, in hundredths of a degree
+/// Celsius, or `None` if the temperature was not measured yet.
+pub fn raw_temperature() -> Option<i32> {
+    let temperature = RAW_TEMPERATURE.load(Ordering::Relaxed);
+    (temperature != TEMPERATURE_UNKNOWN).then_some(temperature)
+}
+
+/// Returns the average of the latest temperature readings, corrected by the
+/// calibration offset, in hundredths of a degree Celsius, or `None` if the
+/// temperature was not measured yet.
+pub fn temperature() -> Option<i32> {
+    let temperature = SMOOTHED_TEMPERATURE.load(Ordering::Relaxed);
+    (temperature != TEMPERATURE_UNKNOWN).then(|| temperature + i32::from(calibration_offset()))
+}
+
+/// Returns the calibration offset added to the die temperature, in hundredths
+/// of a degree Celsius.
+pub fn calibration_offset() -> i16 {
+    settings::get(settings::TEMPERATURE_OFFSET).unwrap_or(0)
+}
+
+/// Set and save the calibration offset added to the die temperature, in
+/// hundredths of a degree Celsius.
+pub fn set_calibration_offset(centi_celsius: i16) -> Result<(), StorageError> {
+    defmt::info!(
+        "[thermal] calibration offset: {} c°C, raw temperature: {} c°C",
+        centi_celsius,
+        raw_temperature()
+    );
+    settings::try_set(settings::TEMPERATURE_OFFSET, &centi_celsius)
+}
+
+/// Returns the current temperature alert.
+pub fn alert() -> TemperatureAlert {
+    match ALERT.load(Ordering::Relaxed) {
+        1 => TemperatureAlert::High,
+        2 => TemperatureAlert::Low,
+        _ => TemperatureAlert::None,
+    }
+}
+
+/// Returns the high and low alert thresholds, in hundredths of a degree
+/// Celsius, or [`HIGH_ALERT_DISABLED`] and [`LOW_ALERT_DISABLED`] if unset.
+pub fn alert_thresholds() -> (i16, i16) {
+    (
+        settings::get(settings::TEMPERATURE_HIGH_ALERT).unwrap_or(HIGH_ALERT_DISABLED),
+        settings::get(settings::TEMPERATURE_LOW_ALERT).unwrap_or(LOW_ALERT_DISABLED),
+    )
+}
+
+/// Set and save the high and low alert thresholds, in hundredths of a degree
+/// Celsius. [`HIGH_ALERT_DISABLED`] and [`LOW_ALERT_DISABLED`] disable either
+/// alert.
+pub fn set_alert_thresholds(high: i16, low: i16) -> Result<(), StorageError> {
+    defmt::info!(
+        "[thermal] alert thresholds: high {} c°C, low {} c°C",
+        high,
+        low
+    );
+    settings::try_set(settings::TEMPERATURE_HIGH_ALERT, &high)?;
+    settings::try_set(settings::TEMPERATURE_LOW_ALERT, &low)
+}
+
+/// Raise or clear the temperature alert for the reported `temperature`, in
+/// hundredths of a degree Celsius.
+fn update_alert(temperature: i32) {
+    let (high, low) = alert_thresholds();
+    let above_high = high != HIGH_ALERT_DISABLED && temperature > i32::from(high);
+    let below_low = low != LOW_ALERT_DISABLED && temperature < i32::from(low);
+
+    let current = alert();
+    let alert = match current {
+        TemperatureAlert::High
+            if high != HIGH_ALERT_DISABLED && temperature > i32::from(high) - ALERT_HYSTERESIS =>
+        {
+            TemperatureAlert::High
+        }
+        TemperatureAlert::Low
+            if low != LOW_ALERT_DISABLED && temperature < i32::from(low) + ALERT_HYSTERESIS =>
+        {
+            TemperatureAlert::Low
+        }
+        _ if above_high => TemperatureAlert::High,
+        _ if below_low => TemperatureAlert::Low,
+        _ => TemperatureAlert::None,
+    };
+
+    if alert == current {
+        return;
+    }
+
+    match alert {
+        TemperatureAlert::None => {
+            defmt::info!("[thermal] temperature {} c°C, alert cleared", temperature);
+        }
+        TemperatureAlert::High | TemperatureAlert::Low => {
+            defmt::warn!("[thermal] temperature {} c°C, {} alert", temperature, alert);
+        }
+    }
+
+    ALERT.store(alert as u8, Ordering::Relaxed);
+    status::set_flag(
+        StatusFlag::TemperatureAlert,
+        alert != TemperatureAlert::None,
+    );
+}
+
+/// Feed a new die temperature reading, in hundredths of a degree Celsius,
+/// entering or leaving the throttled state as needed.
+pub fn update(centi_celsius: i32) {
+    RAW_TEMPERATURE.store(centi_celsius, Ordering::Relaxed);
+    let smoothed = SAMPLES.lock(|samples| samples.borrow_mut().push(centi_celsius));
+    SMOOTHED_TEMPERATURE.store(smoothed, Ordering::Relaxed);
+    defmt::debug!(
+        "[thermal] die temperature: {} c°C, averaged: {} c°C, corrected: {} c°C",
+        centi_celsius,
+        smoothed,
+        smoothed + i32::from(calibration_offset())
+    );
+
+    update_alert(smoothed + i32::from(calibration_offset()));
+
+    let throttled = is_throttled();
+
+    if !throttled && centi_celsius > THROTTLE_ABOVE {
+        defmt::warn!(
+            "[thermal] die temperature {} c°C above {} c°C, throttling radio",
+            centi_celsius,
+            THROTTLE_ABOVE
+        );
+    } else if throttled && centi_celsius < RESTORE_BELOW {
+        defmt::info!(
+            "[thermal] die temperature {} c°C below {} c°C, restoring radio",
+            centi_celsius,
+            RESTORE_BELOW
+        );
+    } else {
+        return;
+    }
+
+    THROTTLED.store(!throttled, Ordering::Relaxed);
+    THROTTLE_CHANGED.signal(!throttled);
+}
@@ -0,0 +1,190 @@
+// SPDX-FileCopyrightText: 2025 Derek Sauer
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Wall clock time, set by a client such as a phone, so events can be
+//! timestamped.
+//!
+//! The device has no real time clock that survives a reset. Once set, the time
+//! is kept by counting from the `embassy_time` uptime at which it was set, and
+//! drifts with the 32.768 kHz crystal, about 20 ppm or 2 s a day. It is lost on
+//! reset until a client sets it again.
+
+use core::cell::Cell;
+use core::fmt;
+
+use embassy_sync::blocking_mutex::Mutex;
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_time::Instant;
+
+/// Days between 0000-03-01 and 1970-01-01 in the proleptic Gregorian calendar,
+/// see [`days_from_civil`].
+const UNIX_EPOCH_DAYS: i64 = 719_468;
+
+/// Unix time, in 1/256ths of a second, at uptime zero. `None` until set.
+static BOOT_TIME: Mutex<CriticalSectionRawMutex, Cell<Option<i64>>> = Mutex::new(Cell::new(None));
+
+/// Calendar date and time of day, in UTC.
+#[derive(Clone, Copy, PartialEq, defmt::Format)]
+pub struct DateTime {
+    /// Year, such as 2025.
+    pub year: u16,
+
+    /// Month of the year, from 1 for January to 12.
+    pub month: u8,
+
+    /// Day of the month, from 1.
+    pub day: u8,
+
+    pub hours:   u8,
+    pub minutes: u8,
+    pub seconds: u8,
+
+    /// Fraction of the second, in 1/256ths.
+    pub fractions256: u8,
+}
+
+impl DateTime {
+    /// Returns the date and time if each field is in range, such as the day
+    /// existing in the month.
+    pub fn validated(self) -> Option<Self> {
+        let days_in_month = match self.month {
+            1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+            4 | 6 | 9 | 11 => 30,
+            2 if is_leap_year(self.year) => 29,
+            2 => 28,
+            _ => return None,
+        };
+
+        let valid = (1582..=9999).contains(&self.year)
+            && (1..=days_in_month).contains(&self.day)
+            && self.hours < 24
+            && self.minutes < 60
+            && self.seconds < 60;
+
+        valid.then_some(self)
+    }
+
+    /// Returns the day of the week, from 1 for Monday to 7 for Sunday.
+    pub fn day_of_week(&self) -> u8 {
+        let days = days_from_civil(self.year, self.month, self.day);
+
+        // 1970-01-01 was a Thursday.
+        ((days + 3).rem_euclid(7) + 1) as u8
+    }
+
+    /// Returns the date and time `time` 1/256ths of a second after the Unix
+    /// epoch.
+    fn from_unix_time(time: i64) -> Self {
+        let seconds = time.div_euclid(256);
+        let days = seconds.div_euclid(86_400);
+        let second_of_day = seconds.rem_euclid(86_400);
+        let (year, month, day) = civil_from_days(days);
+
+        Self {
+            year,
+            month,
+            day,
+            hours: (second_of_day / 3600) as u8,
+            minutes: (second_of_day / 60 % 60) as u8,
+            seconds: (second_of_day % 60) as u8,
+            fractions256: time.rem_euclid(256) as u8,
+        }
+    }
+
+    /// Returns the date and time in 1/256ths of a second since the Unix epoch.
+    fn unix_time(&self) -> i64 {
+        let days = days_from_civil(self.year, self.month, self.day);
+        let seconds = days * 86_400
+            + i64::from(self.hours) * 3600
+            + i64::from(self.minutes) * 60
+            + i64::from(self.seconds);
+
+        seconds * 256 + i64::from(self.fractions256)
+    }
+}
+
+impl fmt::Display for DateTime {
+    /// Formats the date and time as in ISO 8601, such as
+    /// `2025-01-31 23:59:59 UTC`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+            self.year, self.month, self.day, self.hours, self.minutes, self.seconds
+        )
+    }
+}
+
+/// Returns `true` if `year` has a 29th of February.
+fn is_leap_year(year: u16) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+/// Returns the number of days from 1970-01-01 to the date. From Howard
+/// Hinnant's `days_from_civil`, counting in eras of 400 years that start on
+/// the 1st of March so the leap day ends each year.
+fn days_from_civil(year: u16, month: u8, day: u8) -> i64 {
+    let year = i64::from(year) - i64::from(month <= 2);
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month_from_march = (i64::from(month) + 9) % 12;
+    let day_of_year = (153 * month_from_march + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146_097 + day_of_era - UNIX_EPOCH_DAYS
+}
+
+/// Returns the year, month, and day `days` after 1970-01-01, the inverse of
+/// [`days_from_civil`].
+fn civil_from_days(days: i64) -> (u16, u8, u8) {
+    let days = days + UNIX_EPOCH_DAYS;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_from_march + 2) / 5 + 1;
+    let month = if month_from_march < 10 {
+        month_from_march + 3
+    } else {
+        month_from_march - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    (year as u16, month as u8, day as u8)
+}
+
+/// Returns `instant` in 1/256ths of a second since boot.
+fn uptime_256(instant: Instant) -> i64 {
+    (instant.as_micros() * 256 / 1_000_000) as i64
+}
+
+/// Set the wall clock to `now`.
+pub fn set(now: DateTime) {
+    defmt::info!("[wall_clock] set to {}", now);
+
+    let boot_time = now.unix_time() - uptime_256(Instant::now());
+    BOOT_TIME.lock(|time| time.set(Some(boot_time)));
+}
+
+/// Returns the current date and time, or `None` if the clock was not set
+/// since boot.
+pub fn now() -> Option<DateTime> {
+    at(Instant::now())
+}
+
+/// Returns the date and time at `instant`, such as when an event occurred, or
+/// `None` if the clock was not set since boot.
+pub fn at(instant: Instant) -> Option<DateTime> {
+    let boot_time = BOOT_TIME.lock(|time| time.get())?;
+    Some(DateTime::from_unix_time(boot_time + uptime_256(instant)))
+}
+
+/// Returns the Unix time, in seconds, at `instant`, or `None` if the clock was
+/// not set since boot.
+pub fn unix_seconds_at(instant: Instant) -> Option<i64> {
+    let boot_time = BOOT_TIME.lock(|time| time.get())?;
+    Some((boot_time + uptime_256(instant)).div_euclid(256))
+}